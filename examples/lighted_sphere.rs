@@ -103,10 +103,10 @@ fn main() {
 /// locations.
 struct LightShader {
     program: Program,
-    projection_uniform_loc: u8,
-    modelview_uniform_loc: u8,
-    normal_matrix_uniform_loc: u8,
-    light_dir_uniform_loc: u8,
+    projection_uniform_loc: gl::types::GLint,
+    modelview_uniform_loc: gl::types::GLint,
+    normal_matrix_uniform_loc: gl::types::GLint,
+    light_dir_uniform_loc: gl::types::GLint,
 
     position_attrib_loc: u8,
     normal_attrib_loc: u8,
@@ -0,0 +1,266 @@
+use std::path::Path;
+use super::{read_file, Image, PixelData, PixelFormat};
+
+/// Loads and decodes the BMP file with the given name.
+pub fn load<P: AsRef<Path>>(filename: P) -> Result<Image, String> {
+    let path = filename.as_ref();
+    decode(&read_file(path)?).map_err(|why| format!("couldn't decode {}: {}", path.display(), why))
+}
+
+/// Decodes a Windows bitmap with 1, 4, 8, 16, 24 or 32 bits per pixel, uncompressed, run length
+/// encoded (RLE4, RLE8) or with bit field masks.
+///
+/// The result is an 8 bit Rgb image, or Rgba if the file defines an alpha mask.
+pub fn decode(buffer: &[u8]) -> Result<Image, String> {
+    if buffer.len() < 26 || &buffer[0..2] != b"BM" {
+        return Err("not a BMP file, the magic number BM is missing".to_string());
+    }
+
+    let data_offset = read_u32(buffer, 10) as usize;
+    let header_size = read_u32(buffer, 14) as usize;
+
+    if buffer.len() < 14 + header_size {
+        return Err("the bitmap header is truncated".to_string());
+    }
+
+    let core = header_size == 12;
+
+    let (width, height, bits) = if core {
+        (read_u16(buffer, 18) as i32, read_u16(buffer, 20) as i16 as i32, read_u16(buffer, 24))
+    } else if header_size >= 40 {
+        (read_u32(buffer, 18) as i32, read_u32(buffer, 22) as i32, read_u16(buffer, 28))
+    } else {
+        return Err(format!("unsupported bitmap header size {}", header_size));
+    };
+
+    let compression = if core { 0 } else { read_u32(buffer, 30) };
+    let colors_used = if core { 0 } else { read_u32(buffer, 46) as usize };
+
+    if width <= 0 || height == 0 {
+        return Err(format!("invalid image size {} x {}", width, height));
+    }
+
+    // positive heights are stored bottom up
+    let top_down = height < 0;
+    let width    = width as usize;
+    let height   = height.unsigned_abs() as usize;
+
+    // masks for 16 and 32 bit images follow the info header or are part of the V2 to V5 headers
+    let masks = match compression {
+        3 | 6 => {
+            let count = if compression == 6 { 4 } else { 3 };
+            let at = 14 + 40;
+            if buffer.len() < at + count * 4 {
+                return Err("bit field masks are truncated".to_string());
+            }
+            let alpha = if count == 4 || header_size >= 56 { read_u32(buffer, at + 12) } else { 0 };
+            [read_u32(buffer, at), read_u32(buffer, at + 4), read_u32(buffer, at + 8), alpha]
+        },
+        _ if bits == 16 => [0x7c00, 0x03e0, 0x001f, 0],
+        _               => [0x00ff_0000, 0x0000_ff00, 0x0000_00ff, 0],
+    };
+
+    let palette_offset = 14 + header_size + if compression == 3 && header_size == 40 { 12 } else if compression == 6 && header_size == 40 { 16 } else { 0 };
+    let entry_size     = if core { 3 } else { 4 };
+    let palette_size   = if bits <= 8 { if colors_used == 0 { 1 << bits } else { colors_used.min(1 << bits) } } else { 0 };
+
+    let mut palette : Vec<[u8; 3]> = Vec::with_capacity(palette_size);
+    for i in 0..palette_size {
+        let at = palette_offset + i * entry_size;
+        if buffer.len() < at + 3 {
+            return Err("the color palette is truncated".to_string());
+        }
+        palette.push([buffer[at + 2], buffer[at + 1], buffer[at]]);
+    }
+
+    if buffer.len() < data_offset {
+        return Err("the pixel data offset points behind the end of the file".to_string());
+    }
+    let pixels = &buffer[data_offset..];
+
+    // rows are padded to four bytes
+    let row_bytes = (width * bits as usize).div_ceil(32) * 4;
+
+    // the header must not claim more pixels than the data holds, before anything is allocated
+    if compression == 1 || compression == 2 {
+        // two bytes of run length encoded data cover at most a delta of 255 x 255 pixels, apart
+        // from the end of line and end of bitmap codes, which only sparse images use a lot
+        if width.checked_mul(height).is_none_or(|count| count / (255 * 255) > pixels.len()) {
+            return Err(format!("run length encoded data is too short for {} x {} pixels", width, height));
+        }
+    } else {
+        let size = row_bytes.checked_mul(height)
+            .ok_or_else(|| format!("image size {} x {} is too large", width, height))?;
+        check_length(pixels, size)?;
+    }
+
+    let has_alpha = masks[3] != 0;
+    let format    = if has_alpha { PixelFormat::Rgba } else { PixelFormat::Rgb };
+    let channels  = format.channels();
+    let mut data  = vec![0u8; width * height * channels];
+
+    // maps a row index of the file to the row index in the top to bottom image
+    let target_row = |row: usize| if top_down { row } else { height - 1 - row };
+
+    match (compression, bits) {
+        (1, 8) | (2, 4) => {
+            let indices = decode_rle(pixels, width, height, bits == 4)?;
+            for row in 0..height {
+                for x in 0..width {
+                    let color = lookup(&palette, indices[row * width + x])?;
+                    let at = (target_row(row) * width + x) * channels;
+                    data[at..at + 3].copy_from_slice(&color);
+                }
+            }
+        },
+        (0, 1) | (0, 4) | (0, 8) => {
+            for row in 0..height {
+                let line = &pixels[row * row_bytes..];
+                for x in 0..width {
+                    let bit   = x * bits as usize;
+                    let shift = 8 - bits as usize - bit % 8;
+                    let index = (line[bit / 8] >> shift) & (((1u16 << bits) - 1) as u8);
+                    let color = lookup(&palette, index)?;
+                    let at = (target_row(row) * width + x) * channels;
+                    data[at..at + 3].copy_from_slice(&color);
+                }
+            }
+        },
+        (0, 24) => {
+            for row in 0..height {
+                for x in 0..width {
+                    let source = row * row_bytes + x * 3;
+                    let at = (target_row(row) * width + x) * channels;
+                    data[at]     = pixels[source + 2];
+                    data[at + 1] = pixels[source + 1];
+                    data[at + 2] = pixels[source];
+                }
+            }
+        },
+        (0, 16) | (0, 32) | (3, 16) | (3, 32) | (6, 16) | (6, 32) => {
+            let bytes_per_pixel = bits as usize / 8;
+
+            for row in 0..height {
+                for x in 0..width {
+                    let source = row * row_bytes + x * bytes_per_pixel;
+                    let value = if bits == 16 {
+                        read_u16(pixels, source) as u32
+                    } else {
+                        read_u32(pixels, source)
+                    };
+
+                    let at = (target_row(row) * width + x) * channels;
+                    for c in 0..channels {
+                        data[at + c] = extract_masked(value, masks[c]);
+                    }
+                }
+            }
+        },
+        _ => return Err(format!("unsupported combination of compression {} and {} bits per pixel", compression, bits)),
+    }
+
+    Image::new(width as u32, height as u32, format, PixelData::U8(data))
+}
+
+fn lookup(palette: &[[u8; 3]], index: u8) -> Result<[u8; 3], String> {
+    palette.get(index as usize).cloned().ok_or_else(|| format!("palette index {} is out of range", index))
+}
+
+fn check_length(pixels: &[u8], length: usize) -> Result<(), String> {
+    if pixels.len() < length {
+        return Err(format!("pixel data is truncated, expected {} bytes but found {}", length, pixels.len()));
+    }
+    Ok(())
+}
+
+/// Extracts the bits selected by the mask and scales them to 8 bits.
+fn extract_masked(value: u32, mask: u32) -> u8 {
+    if mask == 0 {
+        return 0;
+    }
+
+    let shift = mask.trailing_zeros();
+    let bits  = (mask >> shift).count_ones();
+    let max   = ((1u64 << bits) - 1) as u32;
+    let raw   = (value & mask) >> shift;
+
+    ((raw as u64 * 255 + max as u64 / 2) / max as u64) as u8
+}
+
+/// Decodes RLE8 or RLE4 compressed palette indices into one index per pixel, in file row order.
+fn decode_rle(pixels: &[u8], width: usize, height: usize, four_bit: bool) -> Result<Vec<u8>, String> {
+    let mut indices = vec![0u8; width * height];
+    let (mut x, mut y) = (0usize, 0usize);
+    let mut i = 0;
+
+    let put = |x: usize, y: usize, index: u8, indices: &mut Vec<u8>| {
+        if x < width && y < height {
+            indices[y * width + x] = index;
+        }
+    };
+
+    while i + 1 < pixels.len() {
+        let count = pixels[i] as usize;
+        let value = pixels[i + 1];
+        i += 2;
+
+        if count > 0 {
+            // encoded run, RLE4 alternates between the high and the low nibble
+            for n in 0..count {
+                let index = if four_bit {
+                    if n % 2 == 0 { value >> 4 } else { value & 0x0f }
+                } else {
+                    value
+                };
+                put(x, y, index, &mut indices);
+                x += 1;
+            }
+            continue;
+        }
+
+        match value {
+            0 => {
+                x = 0;
+                y += 1;
+            },
+            1 => break,
+            2 => {
+                if i + 1 >= pixels.len() {
+                    return Err("RLE delta is truncated".to_string());
+                }
+                x += pixels[i] as usize;
+                y += pixels[i + 1] as usize;
+                i += 2;
+            },
+            absolute => {
+                // absolute mode, the run is padded to 16 bits
+                let absolute = absolute as usize;
+                let bytes = if four_bit { absolute.div_ceil(2) } else { absolute };
+                if i + bytes > pixels.len() {
+                    return Err("RLE absolute run is truncated".to_string());
+                }
+                for n in 0..absolute {
+                    let index = if four_bit {
+                        let byte = pixels[i + n / 2];
+                        if n % 2 == 0 { byte >> 4 } else { byte & 0x0f }
+                    } else {
+                        pixels[i + n]
+                    };
+                    put(x, y, index, &mut indices);
+                    x += 1;
+                }
+                i += bytes.div_ceil(2) * 2;
+            }
+        }
+    }
+
+    Ok(indices)
+}
+
+fn read_u16(buffer: &[u8], index: usize) -> u16 {
+    buffer[index] as u16 | ((buffer[index + 1] as u16) << 8)
+}
+
+fn read_u32(buffer: &[u8], index: usize) -> u32 {
+    buffer[index] as u32 | ((buffer[index + 1] as u32) << 8) | ((buffer[index + 2] as u32) << 16) | ((buffer[index + 3] as u32) << 24)
+}
@@ -0,0 +1,527 @@
+pub mod pnm;
+pub mod png;
+pub mod bmp;
+pub mod tga;
+
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::Path;
+
+/// Loads an image file, picking the decoder by the magic bytes at the start of the file:
+/// PNG, BMP and the Netpbm formats are recognized by their signature, anything else is
+/// tried as TGA, which has none.
+pub fn load<P: AsRef<Path>>(filename: P) -> Result<Image, String> {
+    let path = filename.as_ref();
+    decode(&read_file(path)?).map_err(|why| format!("couldn't decode {}: {}", path.display(), why))
+}
+
+/// Decodes an image held in memory, see `load`.
+pub fn decode(buffer: &[u8]) -> Result<Image, String> {
+    if buffer.starts_with(&png::SIGNATURE) {
+        png::decode(buffer)
+    } else if buffer.starts_with(b"BM") {
+        bmp::decode(buffer)
+    } else if buffer.len() >= 2 && buffer[0] == b'P' && (b'1'..=b'7').contains(&buffer[1]) {
+        pnm::decode(buffer)?.to_image()
+    } else if tga::is_tga(buffer) {
+        tga::decode(buffer)
+    } else {
+        Err("unknown image format".to_string())
+    }
+}
+
+/// Encodes the image and writes it to a file, picking the format by the extension of the file
+/// name: `png`, or `ppm`, `pgm`, `pam` and `pnm` for the Netpbm formats chosen by `pnm::encode`.
+pub fn save<P: AsRef<Path>>(image: &Image, filename: P) -> Result<(), String> {
+    let path = filename.as_ref();
+    let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or("").to_lowercase();
+
+    match extension.as_str() {
+        "png"                         => png::save(image, path),
+        "ppm" | "pgm" | "pam" | "pnm" => pnm::save(image, path),
+        _ => Err(format!("couldn't save {}: unknown image file extension '{}'", path.display(), extension)),
+    }
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>, String> {
+    let mut file = File::open(path).map_err(|why| format!("couldn't open {}: {}", path.display(), why))?;
+    let mut buffer = Vec::new();
+    file.read_to_end(&mut buffer).map_err(|why| format!("couldn't read {}: {}", path.display(), why))?;
+    Ok(buffer)
+}
+
+fn write_file(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    let mut file = File::create(path).map_err(|why| format!("couldn't create {}: {}", path.display(), why))?;
+    file.write_all(bytes).map_err(|why| format!("couldn't write {}: {}", path.display(), why))
+}
+
+/// Layout of the channels of a pixel.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PixelFormat {
+    Gray,
+    GrayAlpha,
+    Rgb,
+    Rgba,
+}
+
+impl PixelFormat {
+
+    /// Number of channels, i.e. components per pixel.
+    pub fn channels(self) -> usize {
+        match self {
+            PixelFormat::Gray      => 1,
+            PixelFormat::GrayAlpha => 2,
+            PixelFormat::Rgb       => 3,
+            PixelFormat::Rgba      => 4,
+        }
+    }
+
+    pub fn has_alpha(self) -> bool {
+        self == PixelFormat::GrayAlpha || self == PixelFormat::Rgba
+    }
+
+    /// Returns the format with the given number of channels.
+    pub fn from_channels(channels: usize) -> Result<PixelFormat, String> {
+        match channels {
+            1 => Ok(PixelFormat::Gray),
+            2 => Ok(PixelFormat::GrayAlpha),
+            3 => Ok(PixelFormat::Rgb),
+            4 => Ok(PixelFormat::Rgba),
+            _ => Err(format!("there is no pixel format with {} channels", channels)),
+        }
+    }
+}
+
+/// The components of all pixels of an Image, interleaved. 8 and 16 bit components cover their
+/// full range (0 to 255 and 0 to 65535), floating point components usually range from 0 to 1.
+#[derive(Clone, PartialEq, Debug)]
+pub enum PixelData {
+    U8(Vec<u8>),
+    U16(Vec<u16>),
+    F32(Vec<f32>),
+}
+
+impl PixelData {
+
+    pub fn len(&self) -> usize {
+        match *self {
+            PixelData::U8(ref data)  => data.len(),
+            PixelData::U16(ref data) => data.len(),
+            PixelData::F32(ref data) => data.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A single component of a pixel, used to implement the Image operations once for all types.
+trait Component: Copy + Default {
+    fn to_f32(self) -> f32;
+    fn from_f32(value: f32) -> Self;
+}
+
+impl Component for u8 {
+    fn to_f32(self) -> f32 {
+        self as f32 / 255.0
+    }
+
+    fn from_f32(value: f32) -> u8 {
+        (value.clamp(0.0, 1.0) * 255.0).round() as u8
+    }
+}
+
+impl Component for u16 {
+    fn to_f32(self) -> f32 {
+        self as f32 / 65535.0
+    }
+
+    fn from_f32(value: f32) -> u16 {
+        (value.clamp(0.0, 1.0) * 65535.0).round() as u16
+    }
+}
+
+impl Component for f32 {
+    fn to_f32(self) -> f32 {
+        self
+    }
+
+    fn from_f32(value: f32) -> f32 {
+        value
+    }
+}
+
+/// A raster image in main memory, independent of OpenGL, such that images can be decoded and
+/// processed in tests, on worker threads or before a context exists.
+///
+/// Rows are stored from top to bottom, as in most image files. Textures have their origin at the
+/// bottom left though, so images usually need to be flipped with `flip_vertical` before uploading.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Image {
+    width  : u32,
+    height : u32,
+    format : PixelFormat,
+    data   : PixelData,
+}
+
+impl Image {
+
+    /// Creates an Image from existing pixel data. Fails if the image has no pixels or the data
+    /// does not hold exactly `width * height` pixels of the given format.
+    pub fn new(width: u32, height: u32, format: PixelFormat, data: PixelData) -> Result<Image, String> {
+        if width == 0 || height == 0 {
+            return Err(format!("a {} x {} image has no pixels", width, height));
+        }
+
+        let expected = width as usize * height as usize * format.channels();
+        if data.len() != expected {
+            return Err(format!("a {} x {} image in {:?} needs {} components, but {} were given",
+                               width, height, format, expected, data.len()));
+        }
+
+        Ok(Image {
+            width,
+            height,
+            format,
+            data,
+        })
+    }
+
+    /// Creates an 8 bit Image with all components set to zero. Panics if the width or the
+    /// height is zero, as neither the image files nor the textures support empty images.
+    pub fn empty(width: u32, height: u32, format: PixelFormat) -> Image {
+        assert!(width > 0 && height > 0, "a {} x {} image has no pixels", width, height);
+
+        let size = width as usize * height as usize * format.channels();
+        Image {
+            width,
+            height,
+            format,
+            data : PixelData::U8(vec![0; size]),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn format(&self) -> PixelFormat {
+        self.format
+    }
+
+    pub fn data(&self) -> &PixelData {
+        &self.data
+    }
+
+    pub fn data_mut(&mut self) -> &mut PixelData {
+        &mut self.data
+    }
+
+    pub fn into_data(self) -> PixelData {
+        self.data
+    }
+
+    fn row_length(&self) -> usize {
+        self.width as usize * self.format.channels()
+    }
+
+    /// Mirrors the image at its horizontal center line, turning the top row into the bottom row.
+    pub fn flip_vertical(&mut self) {
+        let row_length = self.row_length();
+        match self.data {
+            PixelData::U8(ref mut data)  => flip_rows(data, row_length),
+            PixelData::U16(ref mut data) => flip_rows(data, row_length),
+            PixelData::F32(ref mut data) => flip_rows(data, row_length),
+        }
+    }
+
+    /// Mirrors the image at its vertical center line, swapping left and right.
+    pub fn flip_horizontal(&mut self) {
+        let channels = self.format.channels();
+        let row_length = self.row_length();
+        match self.data {
+            PixelData::U8(ref mut data)  => flip_columns(data, row_length, channels),
+            PixelData::U16(ref mut data) => flip_columns(data, row_length, channels),
+            PixelData::F32(ref mut data) => flip_columns(data, row_length, channels),
+        }
+    }
+
+    /// Returns the given rectangle of the image as a new Image.
+    pub fn crop(&self, x: u32, y: u32, width: u32, height: u32) -> Result<Image, String> {
+        if x > self.width || width > self.width - x || y > self.height || height > self.height - y {
+            return Err(format!("crop rectangle {} x {} at ({}, {}) exceeds the image of size {} x {}",
+                               width, height, x, y, self.width, self.height));
+        }
+
+        let channels   = self.format.channels();
+        let row_length = self.row_length();
+        let start      = x as usize * channels;
+        let end        = (x + width) as usize * channels;
+        let rows       = y as usize..(y + height) as usize;
+
+        let data = match self.data {
+            PixelData::U8(ref data)  => PixelData::U8(crop_rows(data, row_length, rows, start, end)),
+            PixelData::U16(ref data) => PixelData::U16(crop_rows(data, row_length, rows, start, end)),
+            PixelData::F32(ref data) => PixelData::F32(crop_rows(data, row_length, rows, start, end)),
+        };
+
+        Image::new(width, height, self.format, data)
+    }
+
+    /// Copies the given image into this one, with its top left corner at `(x, y)`. Parts that
+    /// fall outside of this image are clipped. Both images need to have the same pixel format
+    /// and component type.
+    pub fn blit(&mut self, source: &Image, x: u32, y: u32) -> Result<(), String> {
+        if source.format != self.format {
+            return Err(format!("cannot blit an image in {:?} into an image in {:?}", source.format, self.format));
+        }
+
+        if x >= self.width || y >= self.height {
+            return Ok(());
+        }
+
+        let channels   = self.format.channels();
+        let width      = source.width.min(self.width - x) as usize;
+        let height     = source.height.min(self.height - y) as usize;
+        let region     = Region {
+            target_row_length : self.row_length(),
+            source_row_length : source.row_length(),
+            target_offset     : (y as usize * self.width as usize + x as usize) * channels,
+            row_length        : width * channels,
+            rows              : height,
+        };
+
+        match (&mut self.data, &source.data) {
+            (PixelData::U8(target),  PixelData::U8(data))  => region.copy(target, data),
+            (PixelData::U16(target), PixelData::U16(data)) => region.copy(target, data),
+            (PixelData::F32(target), PixelData::F32(data)) => region.copy(target, data),
+            _ => return Err("cannot blit images with different component types".to_string()),
+        }
+
+        Ok(())
+    }
+
+    /// Converts the image into another pixel format. Gray is computed from RGB with the Rec. 709
+    /// luma coefficients, added alpha channels are opaque.
+    pub fn convert(&self, format: PixelFormat) -> Image {
+        let data = match self.data {
+            PixelData::U8(ref data)  => PixelData::U8(convert_pixels(data, self.format, format)),
+            PixelData::U16(ref data) => PixelData::U16(convert_pixels(data, self.format, format)),
+            PixelData::F32(ref data) => PixelData::F32(convert_pixels(data, self.format, format)),
+        };
+
+        Image {
+            width  : self.width,
+            height : self.height,
+            format,
+            data,
+        }
+    }
+
+    /// Returns the image with 8 bit components.
+    pub fn to_u8(&self) -> Image {
+        self.with_data(PixelData::U8(self.components()))
+    }
+
+    /// Returns the image with 16 bit components.
+    pub fn to_u16(&self) -> Image {
+        self.with_data(PixelData::U16(self.components()))
+    }
+
+    /// Returns the image with floating point components ranging from 0 to 1.
+    pub fn to_f32(&self) -> Image {
+        self.with_data(PixelData::F32(self.components()))
+    }
+
+    fn with_data(&self, data: PixelData) -> Image {
+        Image {
+            width  : self.width,
+            height : self.height,
+            format : self.format,
+            data,
+        }
+    }
+
+    fn components<T: Component>(&self) -> Vec<T> {
+        match self.data {
+            PixelData::U8(ref data)  => data.iter().map(|c| T::from_f32(c.to_f32())).collect(),
+            PixelData::U16(ref data) => data.iter().map(|c| T::from_f32(c.to_f32())).collect(),
+            PixelData::F32(ref data) => data.iter().map(|c| T::from_f32(c.to_f32())).collect(),
+        }
+    }
+
+    /// Multiplies the color channels with the alpha channel, as needed for blending with
+    /// `gl::ONE, gl::ONE_MINUS_SRC_ALPHA`. Images without alpha channel stay untouched.
+    pub fn premultiply_alpha(&mut self) {
+        if !self.format.has_alpha() {
+            return;
+        }
+
+        let channels = self.format.channels();
+        match self.data {
+            PixelData::U8(ref mut data)  => premultiply(data, channels),
+            PixelData::U16(ref mut data) => premultiply(data, channels),
+            PixelData::F32(ref mut data) => premultiply(data, channels),
+        }
+    }
+
+    /// Halves the size of the image with a 2x2 box filter, as needed for the next mipmap level.
+    /// Odd sizes are rounded down, no side gets smaller than one pixel.
+    pub fn downsample(&self) -> Image {
+        let width    = (self.width / 2).max(1);
+        let height   = (self.height / 2).max(1);
+        let channels = self.format.channels();
+        let source   = (self.width as usize, self.height as usize);
+        let target   = (width as usize, height as usize);
+
+        let data = match self.data {
+            PixelData::U8(ref data)  => PixelData::U8(box_filter(data, channels, source, target)),
+            PixelData::U16(ref data) => PixelData::U16(box_filter(data, channels, source, target)),
+            PixelData::F32(ref data) => PixelData::F32(box_filter(data, channels, source, target)),
+        };
+
+        Image {
+            width,
+            height,
+            format : self.format,
+            data,
+        }
+    }
+
+    /// Computes all mipmap levels below this image down to 1x1, i.e. levels 1 to n, for contexts
+    /// without `glGenerateMipmap` or to control the filtering. The components are averaged as they
+    /// are, so sRGB images are filtered in gamma space and alpha should be premultiplied.
+    pub fn mip_chain(&self) -> Vec<Image> {
+        let mut chain : Vec<Image> = Vec::new();
+        let (mut width, mut height) = (self.width, self.height);
+
+        while width > 1 || height > 1 {
+            let level = chain.last().unwrap_or(self).downsample();
+            width  = level.width;
+            height = level.height;
+            chain.push(level);
+        }
+
+        chain
+    }
+}
+
+/// A rectangular copy between two rasters, as used by `Image::blit`.
+struct Region {
+    target_row_length : usize,
+    source_row_length : usize,
+    target_offset     : usize,
+    row_length        : usize,
+    rows              : usize,
+}
+
+impl Region {
+
+    fn copy<T: Copy>(&self, target: &mut [T], source: &[T]) {
+        for row in 0..self.rows {
+            let t = self.target_offset + row * self.target_row_length;
+            let s = row * self.source_row_length;
+            target[t..t + self.row_length].copy_from_slice(&source[s..s + self.row_length]);
+        }
+    }
+}
+
+fn flip_rows<T>(data: &mut [T], row_length: usize) {
+    let rows = data.len() / row_length.max(1);
+    for row in 0..rows / 2 {
+        let (top, bottom) = data.split_at_mut((rows - row - 1) * row_length);
+        top[row * row_length..(row + 1) * row_length].swap_with_slice(&mut bottom[..row_length]);
+    }
+}
+
+fn flip_columns<T>(data: &mut [T], row_length: usize, channels: usize) {
+    let width = row_length / channels.max(1);
+    for row in data.chunks_mut(row_length.max(1)) {
+        for x in 0..width / 2 {
+            let (left, right) = row.split_at_mut((width - x - 1) * channels);
+            left[x * channels..(x + 1) * channels].swap_with_slice(&mut right[..channels]);
+        }
+    }
+}
+
+fn crop_rows<T: Copy>(data: &[T], row_length: usize, rows: ::std::ops::Range<usize>, start: usize, end: usize) -> Vec<T> {
+    let mut cropped = Vec::with_capacity(rows.len() * (end - start));
+    for row in rows {
+        cropped.extend_from_slice(&data[row * row_length + start..row * row_length + end]);
+    }
+    cropped
+}
+
+fn convert_pixels<T: Component>(data: &[T], from: PixelFormat, to: PixelFormat) -> Vec<T> {
+    if from == to {
+        return data.to_vec();
+    }
+
+    let from_channels = from.channels();
+    let mut converted = Vec::with_capacity(data.len() / from_channels * to.channels());
+
+    for pixel in data.chunks(from_channels) {
+        // gray and opacity are only computed when they cannot be copied over
+        let (r, g, b, a) = match from {
+            PixelFormat::Gray      => (pixel[0], pixel[0], pixel[0], None),
+            PixelFormat::GrayAlpha => (pixel[0], pixel[0], pixel[0], Some(pixel[1])),
+            PixelFormat::Rgb       => (pixel[0], pixel[1], pixel[2], None),
+            PixelFormat::Rgba      => (pixel[0], pixel[1], pixel[2], Some(pixel[3])),
+        };
+
+        let gray = || match from {
+            PixelFormat::Gray | PixelFormat::GrayAlpha => r,
+            _ => T::from_f32(0.2126 * r.to_f32() + 0.7152 * g.to_f32() + 0.0722 * b.to_f32()),
+        };
+        let alpha = a.unwrap_or_else(|| T::from_f32(1.0));
+
+        match to {
+            PixelFormat::Gray      => converted.push(gray()),
+            PixelFormat::GrayAlpha => converted.extend_from_slice(&[gray(), alpha]),
+            PixelFormat::Rgb       => converted.extend_from_slice(&[r, g, b]),
+            PixelFormat::Rgba      => converted.extend_from_slice(&[r, g, b, alpha]),
+        }
+    }
+
+    converted
+}
+
+fn premultiply<T: Component>(data: &mut [T], channels: usize) {
+    for pixel in data.chunks_mut(channels) {
+        let alpha = pixel[channels - 1].to_f32();
+        for component in &mut pixel[..channels - 1] {
+            *component = T::from_f32(component.to_f32() * alpha);
+        }
+    }
+}
+
+/// Averages blocks of up to 2x2 source pixels into one target pixel. A side of length one is
+/// kept, such that e.g. a 4x1 image becomes 2x1.
+fn box_filter<T: Component>(data: &[T], channels: usize, source: (usize, usize), target: (usize, usize)) -> Vec<T> {
+    let (source_width, source_height) = source;
+    let (width, height) = target;
+    let mut filtered = Vec::with_capacity(width * height * channels);
+
+    for y in 0..height {
+        let rows = if source_height > 1 { [2 * y, 2 * y + 1] } else { [0, 0] };
+        for x in 0..width {
+            let columns = if source_width > 1 { [2 * x, 2 * x + 1] } else { [0, 0] };
+            for c in 0..channels {
+                let mut sum = 0.0;
+                for row in &rows {
+                    for column in &columns {
+                        sum += data[(row * source_width + column) * channels + c].to_f32();
+                    }
+                }
+                filtered.push(T::from_f32(sum / 4.0));
+            }
+        }
+    }
+
+    filtered
+}
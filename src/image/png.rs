@@ -0,0 +1,478 @@
+extern crate miniz_oxide;
+
+use std::path::Path;
+use super::{read_file, write_file, Image, PixelData, PixelFormat};
+
+/// The eight bytes every PNG file starts with.
+pub const SIGNATURE : [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+
+/// Loads and decodes the PNG file with the given name.
+pub fn load<P: AsRef<Path>>(filename: P) -> Result<Image, String> {
+    let path = filename.as_ref();
+    decode(&read_file(path)?).map_err(|why| format!("couldn't decode {}: {}", path.display(), why))
+}
+
+/// Decodes a PNG file. All color types and bit depths of the specification are supported, as
+/// well as interlacing and transparency via `tRNS`.
+///
+/// Grayscale, RGB and palette images come out as Gray, Rgb or Rgba, depending on whether they
+/// have an alpha channel or transparency information. Images with 16 bits per sample are decoded
+/// into 16 bit components, all others into 8 bit components, with bit depths below 8 scaled up
+/// to the full range.
+pub fn decode(buffer: &[u8]) -> Result<Image, String> {
+    if buffer.len() < SIGNATURE.len() || buffer[..SIGNATURE.len()] != SIGNATURE {
+        return Err("not a PNG file, the signature is missing".to_string());
+    }
+
+    let mut header       : Option<Header> = None;
+    let mut palette      : Vec<[u8; 3]>   = Vec::new();
+    let mut transparency : Option<Vec<u8>> = None;
+    let mut compressed   : Vec<u8>        = Vec::new();
+
+    let mut index = SIGNATURE.len();
+    loop {
+        if buffer.len() < index + 12 {
+            return Err("unexpected end of file, IEND chunk is missing".to_string());
+        }
+
+        let length     = read_u32(buffer, index) as usize;
+        let chunk_type = &buffer[index + 4..index + 8];
+
+        if buffer.len() < index + 12 + length {
+            return Err(format!("chunk {} is truncated", String::from_utf8_lossy(chunk_type)));
+        }
+
+        let data = &buffer[index + 8..index + 8 + length];
+        let crc  = read_u32(buffer, index + 8 + length);
+
+        if crc32(&buffer[index + 4..index + 8 + length]) != crc {
+            return Err(format!("checksum of chunk {} does not match", String::from_utf8_lossy(chunk_type)));
+        }
+
+        match chunk_type {
+            b"IHDR" => header = Some(Header::parse(data)?),
+            b"PLTE" => {
+                if !length.is_multiple_of(3) || length == 0 || length > 256 * 3 {
+                    return Err(format!("invalid palette size of {} bytes", length));
+                }
+                palette = data.chunks(3).map(|c| [c[0], c[1], c[2]]).collect();
+            },
+            b"tRNS" => transparency = Some(data.to_vec()),
+            b"IDAT" => compressed.extend_from_slice(data),
+            b"IEND" => break,
+            _ => {
+                // ancillary chunks have a lower case first letter and can be skipped safely
+                if chunk_type[0] & 0x20 == 0 {
+                    return Err(format!("unknown critical chunk {}", String::from_utf8_lossy(chunk_type)));
+                }
+            }
+        }
+
+        index += 12 + length;
+    }
+
+    let header = header.ok_or("IHDR chunk is missing")?;
+
+    if header.color_type == 3 && palette.is_empty() {
+        return Err("palette image without PLTE chunk".to_string());
+    }
+
+    // the header tells how much data there is, so larger streams are rejected while inflating
+    let raw_size = header.raw_size()
+        .ok_or_else(|| format!("image size {} x {} is too large", header.width, header.height))?;
+    let raw = miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(&compressed, raw_size)
+        .map_err(|why| format!("could not decompress the image data: {:?}", why.status))?;
+
+    let samples = header.unfilter_all(&raw)?;
+
+    header.to_image(&samples, &palette, transparency.as_deref())
+}
+
+struct Header {
+    width      : u32,
+    height     : u32,
+    bit_depth  : u8,
+    color_type : u8,
+    interlaced : bool,
+}
+
+/// Offsets and steps of the seven passes of Adam7 interlacing: (x0, y0, dx, dy).
+const ADAM7 : [(usize, usize, usize, usize); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+impl Header {
+
+    fn parse(data: &[u8]) -> Result<Header, String> {
+        if data.len() != 13 {
+            return Err("IHDR chunk needs to be 13 bytes long".to_string());
+        }
+
+        let header = Header {
+            width      : read_u32(data, 0),
+            height     : read_u32(data, 4),
+            bit_depth  : data[8],
+            color_type : data[9],
+            interlaced : data[12] == 1,
+        };
+
+        if header.width == 0 || header.height == 0 {
+            return Err(format!("invalid image size {} x {}", header.width, header.height));
+        }
+
+        let valid_depths : &[u8] = match header.color_type {
+            0         => &[1, 2, 4, 8, 16],
+            3         => &[1, 2, 4, 8],
+            2 | 4 | 6 => &[8, 16],
+            _         => return Err(format!("invalid color type {}", header.color_type)),
+        };
+
+        if !valid_depths.contains(&header.bit_depth) {
+            return Err(format!("bit depth {} is not allowed for color type {}", header.bit_depth, header.color_type));
+        }
+
+        if data[10] != 0 || data[11] != 0 || data[12] > 1 {
+            return Err("unknown compression, filter or interlace method".to_string());
+        }
+
+        Ok(header)
+    }
+
+    fn channels(&self) -> usize {
+        match self.color_type {
+            2 => 3,
+            4 => 2,
+            6 => 4,
+            _ => 1,
+        }
+    }
+
+    /// Bytes per complete pixel, at least one, as used by the filters.
+    fn filter_stride(&self) -> usize {
+        (self.channels() * self.bit_depth as usize).div_ceil(8)
+    }
+
+    fn row_bytes(&self, width: usize) -> usize {
+        (width * self.channels() * self.bit_depth as usize).div_ceil(8)
+    }
+
+    /// The number of bytes of the filtered rows of all passes, or None if that overflows.
+    fn raw_size(&self) -> Option<usize> {
+        let pass_size = |width: usize, height: usize| {
+            width.checked_mul(self.channels() * self.bit_depth as usize)
+                .map(|bits| bits.div_ceil(8) + 1)
+                .and_then(|row| row.checked_mul(height))
+        };
+
+        let (width, height) = (self.width as usize, self.height as usize);
+        if !self.interlaced {
+            return pass_size(width, height);
+        }
+
+        ADAM7.iter()
+            .filter(|&&(x0, y0, _, _)| x0 < width && y0 < height)
+            .try_fold(0usize, |total, &(x0, y0, dx, dy)| {
+                total.checked_add(pass_size((width - x0).div_ceil(dx), (height - y0).div_ceil(dy))?)
+            })
+    }
+
+    /// Undoes the filters of all rows and returns the samples of every pixel in reading order,
+    /// including interlaced images whose passes are merged back into one image.
+    fn unfilter_all(&self, raw: &[u8]) -> Result<Vec<u16>, String> {
+        let width    = self.width as usize;
+        let height   = self.height as usize;
+        let channels = self.channels();
+
+        // checking the size first keeps a bogus header from allocating more than the data covers
+        if self.raw_size().is_none_or(|size| raw.len() < size) {
+            return Err("image data is truncated".to_string());
+        }
+
+        if !self.interlaced {
+            let rows = self.unfilter(raw, width, height)?;
+            return Ok(self.extract(&rows, width, height));
+        }
+
+        let mut samples = vec![0u16; width * height * channels];
+        let mut offset  = 0;
+
+        for &(x0, y0, dx, dy) in &ADAM7 {
+            if x0 >= width || y0 >= height {
+                continue;
+            }
+
+            let pass_width  = (width - x0).div_ceil(dx);
+            let pass_height = (height - y0).div_ceil(dy);
+            let pass_size   = (self.row_bytes(pass_width) + 1) * pass_height;
+
+            if raw.len() < offset + pass_size {
+                return Err("image data is truncated".to_string());
+            }
+
+            let rows = self.unfilter(&raw[offset..offset + pass_size], pass_width, pass_height)?;
+            let pass = self.extract(&rows, pass_width, pass_height);
+            offset += pass_size;
+
+            for py in 0..pass_height {
+                for px in 0..pass_width {
+                    let source = (py * pass_width + px) * channels;
+                    let target = ((y0 + py * dy) * width + x0 + px * dx) * channels;
+                    samples[target..target + channels].copy_from_slice(&pass[source..source + channels]);
+                }
+            }
+        }
+
+        Ok(samples)
+    }
+
+    fn unfilter(&self, raw: &[u8], width: usize, height: usize) -> Result<Vec<u8>, String> {
+        let row_bytes = self.row_bytes(width);
+        let stride    = self.filter_stride();
+
+        if raw.len() < (row_bytes + 1) * height {
+            return Err("image data is truncated".to_string());
+        }
+
+        let mut rows : Vec<u8> = vec![0; row_bytes * height];
+
+        for y in 0..height {
+            let filter = raw[y * (row_bytes + 1)];
+            let line   = &raw[y * (row_bytes + 1) + 1..(y + 1) * (row_bytes + 1)];
+
+            let (previous, current) = rows.split_at_mut(y * row_bytes);
+            let current  = &mut current[..row_bytes];
+            let previous = if y > 0 { &previous[(y - 1) * row_bytes..] } else { &[][..] };
+
+            for x in 0..row_bytes {
+                let a = if x >= stride { current[x - stride] } else { 0 };
+                let b = if y > 0 { previous[x] } else { 0 };
+                let c = if y > 0 && x >= stride { previous[x - stride] } else { 0 };
+
+                let predictor = match filter {
+                    0 => 0,
+                    1 => a,
+                    2 => b,
+                    3 => ((a as u16 + b as u16) / 2) as u8,
+                    4 => paeth(a, b, c),
+                    _ => return Err(format!("unknown filter type {} in row {}", filter, y)),
+                };
+
+                current[x] = line[x].wrapping_add(predictor);
+            }
+        }
+
+        Ok(rows)
+    }
+
+    /// Splits unfiltered rows into samples, one u16 per sample regardless of the bit depth.
+    fn extract(&self, rows: &[u8], width: usize, height: usize) -> Vec<u16> {
+        let row_bytes = self.row_bytes(width);
+        let per_row   = width * self.channels();
+        let depth     = self.bit_depth as usize;
+        let mut samples = Vec::with_capacity(per_row * height);
+
+        for row in rows.chunks(row_bytes).take(height) {
+            for i in 0..per_row {
+                let sample = match depth {
+                    16 => ((row[i * 2] as u16) << 8) | row[i * 2 + 1] as u16,
+                    8  => row[i] as u16,
+                    _  => {
+                        let bit   = i * depth;
+                        let shift = 8 - depth - bit % 8;
+                        ((row[bit / 8] >> shift) & ((1 << depth) - 1) as u8) as u16
+                    }
+                };
+                samples.push(sample);
+            }
+        }
+
+        samples
+    }
+
+    fn to_image(&self, samples: &[u16], palette: &[[u8; 3]], transparency: Option<&[u8]>) -> Result<Image, String> {
+        let channels = self.channels();
+        let depth    = self.bit_depth;
+
+        if self.color_type == 3 {
+            let alpha = transparency.unwrap_or(&[]);
+            let format = if alpha.is_empty() { PixelFormat::Rgb } else { PixelFormat::Rgba };
+            let mut data = Vec::with_capacity(samples.len() * format.channels());
+
+            for &index in samples {
+                let color = palette.get(index as usize)
+                    .ok_or_else(|| format!("palette index {} is out of range", index))?;
+                data.extend_from_slice(color);
+                if !alpha.is_empty() {
+                    data.push(*alpha.get(index as usize).unwrap_or(&255));
+                }
+            }
+
+            return Image::new(self.width, self.height, format, PixelData::U8(data));
+        }
+
+        // a tRNS chunk for grayscale and RGB images names a single fully transparent color
+        let transparent : Option<Vec<u16>> = match transparency {
+            Some(t) if (self.color_type == 0 && t.len() >= 2) || (self.color_type == 2 && t.len() >= 6) => {
+                Some(t.chunks(2).take(channels).map(|pair| ((pair[0] as u16) << 8) | pair[1] as u16).collect())
+            },
+            _ => None,
+        };
+
+        let format = match (self.color_type, transparent.is_some()) {
+            (0, false) => PixelFormat::Gray,
+            (0, true)  => PixelFormat::GrayAlpha,
+            (2, false) => PixelFormat::Rgb,
+            (2, true)  => PixelFormat::Rgba,
+            (4, _)     => PixelFormat::GrayAlpha,
+            _          => PixelFormat::Rgba,
+        };
+
+        let max = (1u32 << depth) - 1;
+        let mut components : Vec<u16> = Vec::with_capacity(samples.len() / channels * format.channels());
+
+        for pixel in samples.chunks(channels) {
+            components.extend_from_slice(pixel);
+            if let Some(ref color) = transparent {
+                components.push(if pixel == color.as_slice() { 0 } else { max as u16 });
+            }
+        }
+
+        let data = if depth == 16 {
+            PixelData::U16(components)
+        } else {
+            PixelData::U8(components.iter().map(|&c| (c as u32 * 255 / max) as u8).collect())
+        };
+
+        Image::new(self.width, self.height, format, data)
+    }
+}
+
+/// Encodes the image and writes it to a PNG file with the given name.
+pub fn save<P: AsRef<Path>>(image: &Image, filename: P) -> Result<(), String> {
+    write_file(filename.as_ref(), &encode(image))
+}
+
+/// Encodes an image as a non-interlaced PNG. 8 bit images are stored with 8 bits per sample,
+/// 16 bit and floating point images with 16 bits. Each row is filtered with the filter type that
+/// yields the smallest sum of absolute differences, which usually compresses best.
+pub fn encode(image: &Image) -> Vec<u8> {
+    let (bit_depth, samples) : (u8, Vec<u8>) = match *image.data() {
+        PixelData::U8(ref data) => (8, data.clone()),
+        _ => match image.to_u16().into_data() {
+            PixelData::U16(data) => (16, data.iter().flat_map(|sample| sample.to_be_bytes()).collect()),
+            _                    => unreachable!("Image::to_u16 returns 16 bit data"),
+        },
+    };
+
+    let color_type = match image.format() {
+        PixelFormat::Gray      => 0,
+        PixelFormat::GrayAlpha => 4,
+        PixelFormat::Rgb       => 2,
+        PixelFormat::Rgba      => 6,
+    };
+
+    let bytes_per_pixel = image.format().channels() * bit_depth as usize / 8;
+    let row_length      = image.width() as usize * bytes_per_pixel;
+
+    let mut filtered = Vec::with_capacity((row_length + 1) * image.height() as usize);
+    let empty = vec![0u8; row_length];
+    for (index, row) in samples.chunks(row_length.max(1)).enumerate() {
+        let previous = if index == 0 { &empty[..] } else { &samples[(index - 1) * row_length..index * row_length] };
+        filter_row(row, previous, bytes_per_pixel, &mut filtered);
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&image.width().to_be_bytes());
+    header.extend_from_slice(&image.height().to_be_bytes());
+    header.extend_from_slice(&[bit_depth, color_type, 0, 0, 0]);
+
+    let mut png = SIGNATURE.to_vec();
+    write_chunk(&mut png, b"IHDR", &header);
+    write_chunk(&mut png, b"IDAT", &miniz_oxide::deflate::compress_to_vec_zlib(&filtered, 6));
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+/// Appends the row with each of the five filter types applied to `output` and keeps the one
+/// with the smallest sum of absolute values, counting the bytes as signed.
+fn filter_row(row: &[u8], previous: &[u8], bytes_per_pixel: usize, output: &mut Vec<u8>) {
+    let mut best : Option<(u64, Vec<u8>)> = None;
+
+    for filter_type in 0..5u8 {
+        let mut candidate = Vec::with_capacity(row.len() + 1);
+        candidate.push(filter_type);
+
+        for i in 0..row.len() {
+            let a = if i >= bytes_per_pixel { row[i - bytes_per_pixel] } else { 0 };
+            let b = previous[i];
+            let c = if i >= bytes_per_pixel { previous[i - bytes_per_pixel] } else { 0 };
+
+            let prediction = match filter_type {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                _ => paeth(a, b, c),
+            };
+            candidate.push(row[i].wrapping_sub(prediction));
+        }
+
+        let cost = candidate[1..].iter().map(|&byte| (byte as i8).unsigned_abs() as u64).sum();
+        if best.as_ref().is_none_or(|&(best_cost, _)| cost < best_cost) {
+            best = Some((cost, candidate));
+        }
+    }
+
+    if let Some((_, filtered)) = best {
+        output.extend_from_slice(&filtered);
+    }
+}
+
+fn write_chunk(png: &mut Vec<u8>, chunk_type: &[u8], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+
+    let start = png.len();
+    png.extend_from_slice(chunk_type);
+    png.extend_from_slice(data);
+
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p  = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+fn read_u32(buffer: &[u8], index: usize) -> u32 {
+    ((buffer[index] as u32) << 24) | ((buffer[index + 1] as u32) << 16) | ((buffer[index + 2] as u32) << 8) | buffer[index + 3] as u32
+}
+
+/// CRC-32 as used for the chunks of PNG files.
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (!(crc & 1)).wrapping_add(1);
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+    !crc
+}
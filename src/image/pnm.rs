@@ -0,0 +1,481 @@
+use std::path::Path;
+use super::{read_file, write_file, Image, PixelData, PixelFormat};
+
+/// Samples of a Netpbm image. Files with a max value up to 255 store one byte per sample,
+/// all others two bytes.
+#[derive(Clone, PartialEq, Debug)]
+pub enum PnmSamples {
+    U8(Vec<u8>),
+    U16(Vec<u16>),
+}
+
+/// An image decoded from one of the Netpbm formats PBM (P1, P4), PGM (P2, P5), PPM (P3, P6)
+/// or PAM (P7).
+///
+/// Samples are stored interleaved, row by row from top to bottom, exactly as in the file.
+/// Bitmaps are converted into grayscale images with a max value of 1, where 1 is white.
+#[derive(Clone, Debug)]
+pub struct PnmImage {
+    width      : u32,
+    height     : u32,
+    depth      : u8,
+    max_value  : u16,
+    tuple_type : String,
+    samples    : PnmSamples,
+}
+
+impl PnmImage {
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Number of samples per pixel, e.g. 1 for grayscale, 3 for RGB and 4 for RGB with alpha.
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    pub fn max_value(&self) -> u16 {
+        self.max_value
+    }
+
+    /// The PAM tuple type, such as `RGB_ALPHA`. For the other formats it is derived from the
+    /// magic number, i.e. `BLACKANDWHITE`, `GRAYSCALE` or `RGB`.
+    pub fn tuple_type(&self) -> &str {
+        &self.tuple_type
+    }
+
+    pub fn samples(&self) -> &PnmSamples {
+        &self.samples
+    }
+
+    /// True if the last sample of every pixel is an alpha value.
+    pub fn has_alpha(&self) -> bool {
+        self.tuple_type.ends_with("_ALPHA")
+    }
+
+    /// Converts the image into an Image, scaling the samples to the full range of 8 or 16 bits.
+    /// Fails for PAM files with more than four samples per pixel.
+    pub fn to_image(&self) -> Result<Image, String> {
+        let format = PixelFormat::from_channels(self.depth as usize)?;
+        let max    = self.max_value as u32;
+
+        let data = match self.samples {
+            PnmSamples::U8(ref samples) if max == 255      => PixelData::U8(samples.clone()),
+            PnmSamples::U8(ref samples)                    => PixelData::U8(samples.iter().map(|&s| ((s as u32 * 255 + max / 2) / max) as u8).collect()),
+            PnmSamples::U16(ref samples) if max == 65535   => PixelData::U16(samples.clone()),
+            PnmSamples::U16(ref samples)                   => PixelData::U16(samples.iter().map(|&s| ((s as u32 * 65535 + max / 2) / max) as u16).collect()),
+        };
+
+        Image::new(self.width, self.height, format, data)
+    }
+
+    /// Returns the sample at the given index, scaled to the range 0 to 255.
+    fn sample_as_u8(&self, index: usize) -> u8 {
+        let value = match self.samples {
+            PnmSamples::U8(ref samples)  => samples[index] as u32,
+            PnmSamples::U16(ref samples) => samples[index] as u32,
+        };
+        ((value * 255 + self.max_value as u32 / 2) / self.max_value as u32) as u8
+    }
+
+    /// Converts the image into 8 bit RGB, top row first. Grayscale is spread over all three
+    /// channels and alpha is dropped.
+    pub fn to_rgb8(&self) -> Vec<u8> {
+        let depth       = self.depth as usize;
+        let pixel_count = match self.samples {
+            PnmSamples::U8(ref samples)  => samples.len() / depth,
+            PnmSamples::U16(ref samples) => samples.len() / depth,
+        };
+        let mut rgb     = Vec::with_capacity(pixel_count * 3);
+
+        for pixel in 0..pixel_count {
+            let base = pixel * depth;
+            if depth < 3 {
+                let gray = self.sample_as_u8(base);
+                rgb.push(gray);
+                rgb.push(gray);
+                rgb.push(gray);
+            } else {
+                rgb.push(self.sample_as_u8(base));
+                rgb.push(self.sample_as_u8(base + 1));
+                rgb.push(self.sample_as_u8(base + 2));
+            }
+        }
+
+        rgb
+    }
+}
+
+/// Loads and decodes the Netpbm file with the given name.
+pub fn load<P: AsRef<Path>>(filename: P) -> Result<PnmImage, String> {
+    let path = filename.as_ref();
+    decode(&read_file(path)?).map_err(|why| format!("couldn't decode {}: {}", path.display(), why))
+}
+
+/// Loads a Netpbm file into an Image.
+pub fn load_image<P: AsRef<Path>>(filename: P) -> Result<Image, String> {
+    load(filename)?.to_image()
+}
+
+/// Encodes the image and writes it to a Netpbm file with the given name, see `encode`.
+pub fn save<P: AsRef<Path>>(image: &Image, filename: P) -> Result<(), String> {
+    write_file(filename.as_ref(), &encode(image))
+}
+
+/// Encodes an image as binary Netpbm file: Gray images as PGM (P5), Rgb images as PPM (P6) and
+/// images with alpha as PAM (P7), since PGM and PPM have no alpha channel. 8 bit images are
+/// stored with a maximum value of 255, 16 bit and floating point images with 65535.
+pub fn encode(image: &Image) -> Vec<u8> {
+    let (max_value, samples) : (u32, Vec<u8>) = match *image.data() {
+        PixelData::U8(ref data) => (255, data.clone()),
+        _ => match image.to_u16().into_data() {
+            PixelData::U16(data) => (65535, data.iter().flat_map(|sample| sample.to_be_bytes()).collect()),
+            _                    => unreachable!("Image::to_u16 returns 16 bit data"),
+        },
+    };
+
+    let (width, height) = (image.width(), image.height());
+
+    let mut bytes = match image.format() {
+        PixelFormat::Gray => format!("P5\n{} {}\n{}\n", width, height, max_value),
+        PixelFormat::Rgb  => format!("P6\n{} {}\n{}\n", width, height, max_value),
+        PixelFormat::GrayAlpha | PixelFormat::Rgba => {
+            let tuple_type = if image.format() == PixelFormat::Rgba { "RGB_ALPHA" } else { "GRAYSCALE_ALPHA" };
+            format!("P7\nWIDTH {}\nHEIGHT {}\nDEPTH {}\nMAXVAL {}\nTUPLTYPE {}\nENDHDR\n",
+                    width, height, image.format().channels(), max_value, tuple_type)
+        },
+    }.into_bytes();
+
+    bytes.extend_from_slice(&samples);
+    bytes
+}
+
+/// Decodes the first image of a Netpbm file. Any data after the first image is ignored.
+pub fn decode(buffer: &[u8]) -> Result<PnmImage, String> {
+    let mut reader = HeaderReader { buffer, index: 0 };
+
+    let magic_number = reader.magic_number()?;
+
+    match magic_number {
+        1 | 4 => {
+            let width  = reader.number("width")?;
+            let height = reader.number("height")?;
+            check_size(width, height)?;
+
+            let pixel_count = sample_count(width, height, 1)?;
+            let samples = if magic_number == 1 {
+                reader.ascii_bits(pixel_count)?
+            } else {
+                reader.skip_single_whitespace()?;
+                reader.binary_bits(width as usize, height as usize)?
+            };
+
+            Ok(PnmImage {
+                width,
+                height,
+                depth      : 1,
+                max_value  : 1,
+                tuple_type : "BLACKANDWHITE".to_string(),
+                samples    : PnmSamples::U8(samples),
+            })
+        },
+        2 | 3 | 5 | 6 => {
+            let width     = reader.number("width")?;
+            let height    = reader.number("height")?;
+            let max_value = reader.number("max value")?;
+            check_size(width, height)?;
+            let max_value = check_max_value(max_value)?;
+
+            let (depth, tuple_type) = if magic_number == 2 || magic_number == 5 {
+                (1, "GRAYSCALE")
+            } else {
+                (3, "RGB")
+            };
+
+            let sample_count = sample_count(width, height, depth as u32)?;
+            let samples = if magic_number <= 3 {
+                reader.ascii_samples(sample_count, max_value)?
+            } else {
+                reader.skip_single_whitespace()?;
+                reader.binary_samples(sample_count, max_value)?
+            };
+
+            Ok(PnmImage {
+                width,
+                height,
+                depth,
+                max_value,
+                tuple_type : tuple_type.to_string(),
+                samples,
+            })
+        },
+        7 => {
+            let mut width      = None;
+            let mut height     = None;
+            let mut depth      = None;
+            let mut max_value  = None;
+            let mut tuple_type = String::new();
+
+            loop {
+                let line = reader.line()?;
+                let line = line.trim();
+
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+
+                let (key, value) = match line.find(char::is_whitespace) {
+                    Some(split) => (&line[..split], line[split..].trim()),
+                    None        => (line, ""),
+                };
+
+                let parse = |value: &str| value.parse::<u32>()
+                    .map_err(|_| format!("invalid value '{}' for {} in PAM header", value, key));
+
+                match key {
+                    "ENDHDR"   => break,
+                    "WIDTH"    => width     = Some(parse(value)?),
+                    "HEIGHT"   => height    = Some(parse(value)?),
+                    "DEPTH"    => depth     = Some(parse(value)?),
+                    "MAXVAL"   => max_value = Some(parse(value)?),
+                    "TUPLTYPE" => {
+                        if !tuple_type.is_empty() {
+                            tuple_type.push(' ');
+                        }
+                        tuple_type.push_str(value);
+                    },
+                    _ => return Err(format!("unknown PAM header entry '{}'", key)),
+                }
+            }
+
+            let width     = width.ok_or("PAM header is missing WIDTH")?;
+            let height    = height.ok_or("PAM header is missing HEIGHT")?;
+            let depth     = depth.ok_or("PAM header is missing DEPTH")?;
+            let max_value = max_value.ok_or("PAM header is missing MAXVAL")?;
+
+            check_size(width, height)?;
+            let max_value = check_max_value(max_value)?;
+
+            if depth == 0 || depth > 255 {
+                return Err(format!("PAM depth needs to be between 1 and 255, but is {}", depth));
+            }
+
+            let sample_count = sample_count(width, height, depth)?;
+            let samples = reader.binary_samples(sample_count, max_value)?;
+
+            Ok(PnmImage {
+                width,
+                height,
+                depth      : depth as u8,
+                max_value,
+                tuple_type,
+                samples,
+            })
+        },
+        _ => Err(format!("unsupported magic number P{}", magic_number)),
+    }
+}
+
+fn check_size(width: u32, height: u32) -> Result<(), String> {
+    if width == 0 || height == 0 {
+        return Err(format!("invalid image size {} x {}", width, height));
+    }
+    Ok(())
+}
+
+/// The number of samples in the raster, which fails if it does not fit into memory.
+fn sample_count(width: u32, height: u32, depth: u32) -> Result<usize, String> {
+    (width as usize).checked_mul(height as usize)
+        .and_then(|pixels| pixels.checked_mul(depth as usize))
+        .ok_or_else(|| format!("image size {} x {} x {} is too large", width, height, depth))
+}
+
+fn check_max_value(max_value: u32) -> Result<u16, String> {
+    if max_value == 0 || max_value > 65535 {
+        return Err(format!("max value needs to be between 1 and 65535, but is {}", max_value));
+    }
+    Ok(max_value as u16)
+}
+
+/// Reads the header tokens and raster of a Netpbm file. Comments start with '#' and reach to
+/// the end of the line; they may appear anywhere between the header tokens.
+struct HeaderReader<'a> {
+    buffer : &'a [u8],
+    index  : usize,
+}
+
+impl<'a> HeaderReader<'a> {
+
+    fn magic_number(&mut self) -> Result<u8, String> {
+        if self.buffer.len() < 2 || self.buffer[0] != b'P' || !(b'1'..=b'7').contains(&self.buffer[1]) {
+            return Err("not a Netpbm file, the magic number needs to be one of P1 to P7".to_string());
+        }
+        self.index = 2;
+
+        let magic_number = self.buffer[1] - b'0';
+
+        // the magic number needs to be followed by whitespace
+        match self.buffer.get(self.index) {
+            Some(c) if c.is_ascii_whitespace() => Ok(magic_number),
+            _ => Err(format!("magic number P{} is not followed by whitespace", magic_number)),
+        }
+    }
+
+    fn skip_whitespace_and_comments(&mut self) {
+        while self.index < self.buffer.len() {
+            let c = self.buffer[self.index];
+            if c == b'#' {
+                while self.index < self.buffer.len() && self.buffer[self.index] != b'\n' && self.buffer[self.index] != b'\r' {
+                    self.index += 1;
+                }
+            } else if c.is_ascii_whitespace() {
+                self.index += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn number(&mut self, name: &str) -> Result<u32, String> {
+        self.skip_whitespace_and_comments();
+
+        let start = self.index;
+        while self.index < self.buffer.len() && self.buffer[self.index].is_ascii_digit() {
+            self.index += 1;
+        }
+
+        if start == self.index {
+            return Err(format!("expected a number for the {} at byte {}", name, start));
+        }
+
+        let digits = String::from_utf8_lossy(&self.buffer[start..self.index]);
+        digits.parse::<u32>().map_err(|_| format!("{} '{}' is out of range", name, digits))
+    }
+
+    /// The raster of the binary formats starts after exactly one whitespace character.
+    fn skip_single_whitespace(&mut self) -> Result<(), String> {
+        match self.buffer.get(self.index) {
+            Some(c) if c.is_ascii_whitespace() => {
+                self.index += 1;
+                Ok(())
+            },
+            _ => Err("expected a single whitespace character in front of the raster".to_string()),
+        }
+    }
+
+    fn line(&mut self) -> Result<String, String> {
+        if self.index >= self.buffer.len() {
+            return Err("unexpected end of PAM header, ENDHDR is missing".to_string());
+        }
+
+        let start = self.index;
+        while self.index < self.buffer.len() && self.buffer[self.index] != b'\n' {
+            self.index += 1;
+        }
+        let line = String::from_utf8_lossy(&self.buffer[start..self.index]).into_owned();
+
+        // jump over the line feed
+        self.index += 1;
+
+        Ok(line)
+    }
+
+    /// The number of bytes after the current position.
+    fn remaining(&self) -> usize {
+        self.buffer.len() - self.index.min(self.buffer.len())
+    }
+
+    fn ascii_bits(&mut self, count: usize) -> Result<Vec<u8>, String> {
+        // every pixel takes at least one character, so a header can't claim more than that
+        let mut samples = Vec::with_capacity(count.min(self.remaining()));
+
+        while samples.len() < count {
+            self.skip_whitespace_and_comments();
+            match self.buffer.get(self.index) {
+                Some(&b'0') => samples.push(1),
+                Some(&b'1') => samples.push(0),
+                Some(&c)    => return Err(format!("unexpected character '{}' in bitmap", c as char)),
+                None        => return Err(format!("bitmap ends after {} of {} pixels", samples.len(), count)),
+            }
+            self.index += 1;
+        }
+
+        Ok(samples)
+    }
+
+    fn binary_bits(&mut self, width: usize, height: usize) -> Result<Vec<u8>, String> {
+        let bytes_per_row = width.div_ceil(8);
+        let size = bytes_per_row.checked_mul(height)
+            .ok_or_else(|| format!("image size {} x {} is too large", width, height))?;
+
+        if self.remaining() < size {
+            return Err(format!("raster is truncated, expected {} bytes but found {}", size, self.remaining()));
+        }
+
+        let mut samples = Vec::with_capacity(width * height);
+        for y in 0..height {
+            let row = &self.buffer[self.index + y * bytes_per_row..];
+            for x in 0..width {
+                let bit = (row[x / 8] >> (7 - x % 8)) & 1;
+                samples.push(1 - bit);
+            }
+        }
+        self.index += size;
+
+        Ok(samples)
+    }
+
+    fn ascii_samples(&mut self, count: usize, max_value: u16) -> Result<PnmSamples, String> {
+        // every sample takes at least one character, so a header can't claim more than that
+        let mut values : Vec<u16> = Vec::with_capacity(count.min(self.remaining()));
+
+        for _ in 0..count {
+            let value = self.number("sample")?;
+            if value > max_value as u32 {
+                return Err(format!("sample {} exceeds the max value {}", value, max_value));
+            }
+            values.push(value as u16);
+        }
+
+        if max_value <= 255 {
+            Ok(PnmSamples::U8(values.iter().map(|&v| v as u8).collect()))
+        } else {
+            Ok(PnmSamples::U16(values))
+        }
+    }
+
+    fn binary_samples(&mut self, count: usize, max_value: u16) -> Result<PnmSamples, String> {
+        let bytes_per_sample = if max_value <= 255 { 1 } else { 2 };
+        let size = count.checked_mul(bytes_per_sample)
+            .ok_or_else(|| format!("{} samples are too many", count))?;
+        let available = self.remaining();
+
+        if available < size {
+            return Err(format!("raster is truncated, expected {} bytes but found {}", size, available));
+        }
+
+        let raster = &self.buffer[self.index..self.index + size];
+        self.index += size;
+
+        let samples = if bytes_per_sample == 1 {
+            PnmSamples::U8(raster.to_vec())
+        } else {
+            // 16 bit samples are stored most significant byte first
+            PnmSamples::U16(raster.chunks(2).map(|pair| ((pair[0] as u16) << 8) | pair[1] as u16).collect())
+        };
+
+        let largest = match samples {
+            PnmSamples::U8(ref values)  => values.iter().map(|&value| value as u16).max(),
+            PnmSamples::U16(ref values) => values.iter().cloned().max(),
+        };
+        match largest {
+            Some(value) if value > max_value => Err(format!("sample {} exceeds the max value {}", value, max_value)),
+            _                                => Ok(samples),
+        }
+    }
+}
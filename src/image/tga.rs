@@ -0,0 +1,184 @@
+use std::path::Path;
+use super::{read_file, Image, PixelData, PixelFormat};
+
+/// Loads and decodes the TGA file with the given name.
+pub fn load<P: AsRef<Path>>(filename: P) -> Result<Image, String> {
+    let path = filename.as_ref();
+    decode(&read_file(path)?).map_err(|why| format!("couldn't decode {}: {}", path.display(), why))
+}
+
+/// Returns true if the buffer starts with a plausible TGA header. TGA files have no magic number,
+/// so this only checks that the fields hold values the decoder understands.
+pub fn is_tga(buffer: &[u8]) -> bool {
+    buffer.len() >= 18
+        && buffer[1] <= 1
+        && [1, 2, 3, 9, 10, 11].contains(&buffer[2])
+        && [8, 15, 16, 24, 32].contains(&buffer[16])
+        && read_u16(buffer, 12) > 0
+        && read_u16(buffer, 14) > 0
+}
+
+/// Decodes an uncompressed or run length encoded Truevision TGA file with color mapped,
+/// true color or grayscale pixels.
+///
+/// The result is an 8 bit Gray, Rgb or Rgba image, with the top row first regardless of the
+/// origin stored in the file.
+pub fn decode(buffer: &[u8]) -> Result<Image, String> {
+    if !is_tga(buffer) {
+        return Err("not a supported TGA file".to_string());
+    }
+
+    let id_length      = buffer[0] as usize;
+    let color_map_type = buffer[1];
+    let image_type     = buffer[2];
+    let map_first      = read_u16(buffer, 3) as usize;
+    let map_length     = read_u16(buffer, 5) as usize;
+    let map_entry_bits = buffer[7];
+    let width          = read_u16(buffer, 12) as usize;
+    let height         = read_u16(buffer, 14) as usize;
+    let pixel_bits     = buffer[16];
+    let descriptor     = buffer[17];
+
+    let alpha_bits    = descriptor & 0x0f;
+    let right_to_left = descriptor & 0x10 != 0;
+    let top_to_bottom = descriptor & 0x20 != 0;
+
+    let color_mapped = image_type == 1 || image_type == 9;
+    let grayscale    = image_type == 3 || image_type == 11;
+    let compressed   = image_type >= 9;
+
+    if color_mapped && color_map_type != 1 {
+        return Err("color mapped image without color map".to_string());
+    }
+
+    if color_map_type == 1 && ![15, 16, 24, 32].contains(&map_entry_bits) {
+        return Err(format!("unsupported color map entry size of {} bits", map_entry_bits));
+    }
+    if color_mapped && pixel_bits != 8 && pixel_bits != 16 {
+        return Err(format!("unsupported color map index size of {} bits", pixel_bits));
+    }
+
+    let mut index = 18 + id_length;
+    if index > buffer.len() {
+        return Err("the image ID is truncated".to_string());
+    }
+
+    // the color map is stored even if the image does not use it
+    let mut color_map : Vec<[u8; 4]> = Vec::new();
+    if color_map_type == 1 {
+        let entry_bytes = (map_entry_bits as usize).div_ceil(8);
+        if buffer.len() < index + map_length * entry_bytes {
+            return Err("the color map is truncated".to_string());
+        }
+        for i in 0..map_length {
+            color_map.push(read_color(&buffer[index + i * entry_bytes..], map_entry_bits, alpha_bits));
+        }
+        index += map_length * entry_bytes;
+    }
+
+    let format = if grayscale {
+        PixelFormat::Gray
+    } else {
+        let entry_bits = if color_mapped { map_entry_bits } else { pixel_bits };
+        if entry_bits == 32 || (entry_bits == 16 && alpha_bits > 0) {
+            PixelFormat::Rgba
+        } else {
+            PixelFormat::Rgb
+        }
+    };
+
+    let pixel_bytes = (pixel_bits as usize).div_ceil(8);
+    let raw = if compressed {
+        decode_rle(&buffer[index..], width * height, pixel_bytes)?
+    } else {
+        let size = width * height * pixel_bytes;
+        if buffer.len() < index + size {
+            return Err(format!("pixel data is truncated, expected {} bytes but found {}", size, buffer.len().saturating_sub(index)));
+        }
+        buffer[index..index + size].to_vec()
+    };
+
+    let channels = format.channels();
+    let mut data = vec![0u8; width * height * channels];
+
+    for row in 0..height {
+        let y = if top_to_bottom { row } else { height - 1 - row };
+
+        for column in 0..width {
+            let x = if right_to_left { width - 1 - column } else { column };
+            let pixel = &raw[(row * width + column) * pixel_bytes..];
+
+            let color = if grayscale {
+                [pixel[0], pixel[0], pixel[0], 255]
+            } else if color_mapped {
+                let entry = if pixel_bytes == 2 { read_u16(pixel, 0) as usize } else { pixel[0] as usize };
+                *entry.checked_sub(map_first).and_then(|i| color_map.get(i))
+                    .ok_or_else(|| format!("color map index {} is out of range", entry))?
+            } else {
+                read_color(pixel, pixel_bits, alpha_bits)
+            };
+
+            let at = (y * width + x) * channels;
+            data[at..at + channels].copy_from_slice(&color[..channels]);
+        }
+    }
+
+    Image::new(width as u32, height as u32, format, PixelData::U8(data))
+}
+
+/// Reads a BGR(A) color of 15, 16, 24 or 32 bits and returns it as RGBA.
+fn read_color(bytes: &[u8], bits: u8, alpha_bits: u8) -> [u8; 4] {
+    match bits {
+        15 | 16 => {
+            let value = read_u16(bytes, 0);
+            let scale = |v: u16| ((v as u32 * 255 + 15) / 31) as u8;
+            let alpha = if bits == 16 && alpha_bits > 0 && value & 0x8000 == 0 { 0 } else { 255 };
+            [scale((value >> 10) & 0x1f), scale((value >> 5) & 0x1f), scale(value & 0x1f), alpha]
+        },
+        24 => [bytes[2], bytes[1], bytes[0], 255],
+        32 => [bytes[2], bytes[1], bytes[0], bytes[3]],
+        _  => [bytes[0], bytes[0], bytes[0], 255],
+    }
+}
+
+/// Expands run length encoded packets into `count` raw pixels.
+fn decode_rle(buffer: &[u8], count: usize, pixel_bytes: usize) -> Result<Vec<u8>, String> {
+    // a packet of at most 1 + pixel_bytes bytes expands to at most 128 pixels
+    let mut raw = Vec::with_capacity((count * pixel_bytes).min(buffer.len() * 128));
+    let mut i = 0;
+
+    while raw.len() < count * pixel_bytes {
+        if i >= buffer.len() {
+            return Err("run length encoded data is truncated".to_string());
+        }
+
+        let header = buffer[i];
+        let length = (header & 0x7f) as usize + 1;
+        i += 1;
+
+        if header & 0x80 != 0 {
+            if i + pixel_bytes > buffer.len() {
+                return Err("run length encoded data is truncated".to_string());
+            }
+            for _ in 0..length {
+                raw.extend_from_slice(&buffer[i..i + pixel_bytes]);
+            }
+            i += pixel_bytes;
+        } else {
+            if i + length * pixel_bytes > buffer.len() {
+                return Err("run length encoded data is truncated".to_string());
+            }
+            raw.extend_from_slice(&buffer[i..i + length * pixel_bytes]);
+            i += length * pixel_bytes;
+        }
+    }
+
+    // a packet may reach beyond the last pixel
+    raw.truncate(count * pixel_bytes);
+
+    Ok(raw)
+}
+
+fn read_u16(buffer: &[u8], index: usize) -> u16 {
+    buffer[index] as u16 | ((buffer[index + 1] as u16) << 8)
+}
@@ -1,2 +1,3 @@
 pub mod opengl;
-pub mod linalg;
\ No newline at end of file
+pub mod linalg;
+pub mod image;
\ No newline at end of file
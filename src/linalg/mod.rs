@@ -159,6 +159,7 @@ impl Vector4 {
 }
 
 #[allow(dead_code)]
+#[repr(C)]
 pub struct Matrix3 {
     m_0_0 : f32,
     m_0_1 : f32,
@@ -293,6 +294,7 @@ impl Matrix4 {
 }
 
 #[allow(dead_code)]
+#[repr(C)]
 pub struct Matrix4 {
     m_0_0 : f32,
     m_0_1 : f32,
@@ -411,6 +413,21 @@ impl Matrix4 {
         self.m_3_3 = 0.0;
     }
 
+    /// A parallel projection mapping the given box onto the clip space cube, e.g. with
+    /// `orthographic(0.0, width, 0.0, height, -1.0, 1.0)` for drawing in pixels.
+    pub fn orthographic(&mut self, left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) {
+        self.identity();
+
+        self.m_0_0 = 2.0 / (right - left);
+        self.m_3_0 = -(right + left) / (right - left);
+
+        self.m_1_1 = 2.0 / (top - bottom);
+        self.m_3_1 = -(top + bottom) / (top - bottom);
+
+        self.m_2_2 = -2.0 / (far - near);
+        self.m_3_2 = -(far + near) / (far - near);
+    }
+
     pub fn copy(&mut self, c:&Matrix4) {
         self.m_0_0 = c.m_0_0;
         self.m_1_0 = c.m_1_0;
@@ -503,6 +520,14 @@ impl Matrix4 {
         self.m_3_3 = 1.0;
     }
 
+    /// Removes the translation, keeping rotation and scale. Applied to a view matrix, this keeps
+    /// the camera at the origin, as needed for skyboxes.
+    pub fn strip_translation(&mut self) {
+        self.m_3_0 = 0.0;
+        self.m_3_1 = 0.0;
+        self.m_3_2 = 0.0;
+    }
+
     pub fn look_at(&mut self, eye: &Vector3, center: &Vector3, up: &Vector3) {
         let mut u = Vector3::zero();
         let mut v = Vector3::zero();
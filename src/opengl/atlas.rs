@@ -0,0 +1,378 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::Path;
+
+use image::{Image, PixelFormat};
+use super::{Sprite, Texture};
+
+/// Collects named images to be packed into a single atlas image.
+///
+/// The images are placed with the MaxRects algorithm, choosing the free rectangle that leaves
+/// the shortest side over, in an atlas of power of two sides that grows until all images fit.
+#[allow(dead_code)]
+pub struct AtlasBuilder {
+    images    : Vec<(String, Image)>,
+    padding   : u32,
+    extrusion : u32,
+    max_size  : u32,
+}
+
+/// A packed atlas image along with the Sprite of each image, by name.
+#[allow(dead_code)]
+pub struct Atlas {
+    image   : Image,
+    sprites : HashMap<String, Sprite>,
+}
+
+/// A rectangle of the atlas, in pixels from the top left.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Rect {
+    x      : u32,
+    y      : u32,
+    width  : u32,
+    height : u32,
+}
+
+impl Rect {
+
+    fn right(&self) -> u32 {
+        self.x + self.width
+    }
+
+    fn bottom(&self) -> u32 {
+        self.y + self.height
+    }
+
+    fn contains(&self, other: &Rect) -> bool {
+        other.x >= self.x && other.y >= self.y && other.right() <= self.right() && other.bottom() <= self.bottom()
+    }
+
+    fn intersects(&self, other: &Rect) -> bool {
+        self.x < other.right() && other.x < self.right() && self.y < other.bottom() && other.y < self.bottom()
+    }
+}
+
+impl Default for AtlasBuilder {
+    fn default() -> AtlasBuilder {
+        AtlasBuilder::new()
+    }
+}
+
+#[allow(dead_code)]
+impl AtlasBuilder {
+
+    /// An empty atlas without padding and extrusion, up to 4096 pixels on each side.
+    pub fn new() -> AtlasBuilder {
+        AtlasBuilder {
+            images    : Vec::new(),
+            padding   : 0,
+            extrusion : 0,
+            max_size  : 4096,
+        }
+    }
+
+    /// Adds an image under the given name. Images of any pixel format and component type are
+    /// converted to the 8 bit format of the atlas.
+    pub fn add(mut self, name: &str, image: Image) -> AtlasBuilder {
+        self.images.push((name.to_string(), image));
+        self
+    }
+
+    /// Loads an image file with `image::load` and adds it under its file name without extension.
+    pub fn add_file<P: AsRef<Path>>(self, filename: P) -> Result<AtlasBuilder, String> {
+        let path = filename.as_ref();
+        let name = path.file_stem()
+            .ok_or_else(|| format!("{} has no file name", path.display()))?
+            .to_string_lossy().into_owned();
+        let image = ::image::load(path)?;
+
+        Ok(self.add(&name, image))
+    }
+
+    /// Leaves the given number of empty pixels between the images and along the atlas border.
+    pub fn padding(mut self, padding: u32) -> AtlasBuilder {
+        self.padding = padding;
+        self
+    }
+
+    /// Repeats the outermost pixels of each image the given number of times around it, such
+    /// that filtering at the edges of a Sprite does not bleed in its neighbours.
+    pub fn extrusion(mut self, extrusion: u32) -> AtlasBuilder {
+        self.extrusion = extrusion;
+        self
+    }
+
+    /// The largest width and height the atlas may grow to.
+    pub fn max_size(mut self, max_size: u32) -> AtlasBuilder {
+        self.max_size = max_size;
+        self
+    }
+
+    pub fn build(self) -> Result<Atlas, String> {
+        let mut names = HashMap::new();
+        for (index, (name, _)) in self.images.iter().enumerate() {
+            // names end the lines of the sprite map
+            if name.is_empty() || name.contains(['\n', '\r']) {
+                return Err(format!("invalid image name '{}'", name.escape_debug()));
+            }
+            if names.insert(name.as_str(), index).is_some() {
+                return Err(format!("there is more than one image named '{}'", name));
+            }
+        }
+
+        // images with alpha or color turn the whole atlas into a format with alpha or color
+        let alpha = self.images.iter().any(|(_, image)| image.format().has_alpha());
+        let color = self.images.iter().any(|(_, image)| image.format().channels() >= 3);
+        let format = match (color, alpha) {
+            (false, false) => PixelFormat::Gray,
+            (false, true)  => PixelFormat::GrayAlpha,
+            (true,  false) => PixelFormat::Rgb,
+            (true,  true)  => PixelFormat::Rgba,
+        };
+
+        // each cell holds an extruded image and the padding to its right and bottom
+        let border = 2 * self.extrusion + self.padding;
+        let cells : Vec<(u32, u32)> = self.images.iter()
+            .map(|(_, image)| (image.width() + border, image.height() + border))
+            .collect();
+
+        // larger images first, as they are harder to place
+        let mut order : Vec<usize> = (0..self.images.len()).collect();
+        order.sort_by(|&a, &b| {
+            let (a_width, a_height) = cells[a];
+            let (b_width, b_height) = cells[b];
+            b_width.max(b_height).cmp(&a_width.max(a_height))
+                .then((b_width * b_height).cmp(&(a_width * a_height)))
+                .then(self.images[a].0.cmp(&self.images[b].0))
+        });
+
+        let area : u64 = cells.iter().map(|&(width, height)| width as u64 * height as u64).sum();
+        let widest = cells.iter().map(|&(width, _)| width).max().unwrap_or(0) + self.padding;
+        let tallest = cells.iter().map(|&(_, height)| height).max().unwrap_or(0) + self.padding;
+
+        let mut width = widest.max(1).next_power_of_two();
+        let mut height = tallest.max(1).next_power_of_two();
+        while (width as u64) * (height as u64) < area {
+            if width <= height { width *= 2 } else { height *= 2 }
+        }
+
+        let placements = loop {
+            if width > self.max_size || height > self.max_size {
+                return Err(format!("the images do not fit into an atlas of {} x {} pixels", self.max_size, self.max_size));
+            }
+
+            if let Some(placements) = pack(width, height, self.padding, &cells, &order) {
+                break placements;
+            }
+
+            if width <= height { width *= 2 } else { height *= 2 }
+        };
+
+        let mut image = Image::empty(width, height, format);
+        let mut sprites = HashMap::new();
+
+        for ((name, source), &(x, y)) in self.images.iter().zip(&placements) {
+            let source = extrude(&source.convert(format).to_u8(), self.extrusion)?;
+            image.blit(&source, x, y)?;
+
+            let sprite = Sprite::new(x + self.extrusion, y + self.extrusion,
+                                     source.width() - 2 * self.extrusion, source.height() - 2 * self.extrusion);
+            sprites.insert(name.clone(), sprite);
+        }
+
+        Ok(Atlas {
+            image,
+            sprites,
+        })
+    }
+}
+
+#[allow(dead_code)]
+impl Atlas {
+
+    /// The packed images with the top row first, e.g. to be saved with `image::save`.
+    pub fn image(&self) -> &Image {
+        &self.image
+    }
+
+    pub fn sprites(&self) -> &HashMap<String, Sprite> {
+        &self.sprites
+    }
+
+    pub fn sprite(&self, name: &str) -> Option<&Sprite> {
+        self.sprites.get(name)
+    }
+
+    /// Uploads the atlas image into a texture, flipped to the texture origin at the bottom left.
+    pub fn texture(&self) -> Texture {
+        let mut image = self.image.clone();
+        image.flip_vertical();
+        Texture::from_image(&image)
+    }
+
+    /// The sprite map as text: a header with the atlas size, followed by one line per sprite
+    /// with its x, y, width, height and name, sorted by name.
+    ///
+    /// ```text
+    /// atlas 64 32
+    /// sprite 0 0 16 16 arrow left
+    /// ```
+    pub fn to_map(&self) -> String {
+        let mut names : Vec<&String> = self.sprites.keys().collect();
+        names.sort();
+
+        let mut map = format!("atlas {} {}\n", self.image.width(), self.image.height());
+        for name in names {
+            let sprite = &self.sprites[name];
+            map.push_str(&format!("sprite {} {} {} {} {}\n", sprite.x(), sprite.y(), sprite.width(), sprite.height(), name));
+        }
+        map
+    }
+
+    /// Writes the sprite map to a file, see `to_map`.
+    pub fn save_map<P: AsRef<Path>>(&self, filename: P) -> Result<(), String> {
+        let path = filename.as_ref();
+        let mut file = File::create(path).map_err(|why| format!("couldn't create {}: {}", path.display(), why))?;
+        file.write_all(self.to_map().as_bytes()).map_err(|why| format!("couldn't write {}: {}", path.display(), why))
+    }
+
+    /// Parses a sprite map written by `to_map` into the atlas width, height and sprites.
+    pub fn parse_map(map: &str) -> Result<(u32, u32, HashMap<String, Sprite>), String> {
+        let mut size = None;
+        let mut sprites = HashMap::new();
+
+        for (number, line) in map.lines().enumerate() {
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let invalid = || format!("invalid line {} of the sprite map: '{}'", number + 1, line);
+            let fields : Vec<&str> = line.splitn(6, ' ').collect();
+            let numbers = |range: ::std::ops::Range<usize>| -> Result<Vec<u32>, String> {
+                fields[range].iter().map(|field| field.parse::<u32>().map_err(|_| invalid())).collect()
+            };
+
+            match fields[0] {
+                "atlas" if fields.len() == 3 && size.is_none() => {
+                    let numbers = numbers(1..3)?;
+                    size = Some((numbers[0], numbers[1]));
+                },
+                "sprite" if fields.len() == 6 && !fields[5].is_empty() => {
+                    let (width, height) = size.ok_or_else(|| "the sprite map has to start with the atlas size".to_string())?;
+                    let numbers = numbers(1..5)?;
+                    let sprite = Sprite::new(numbers[0], numbers[1], numbers[2], numbers[3]);
+
+                    // in 64 bits, such that the sums cannot overflow
+                    if sprite.x() as u64 + sprite.width() as u64 > width as u64
+                        || sprite.y() as u64 + sprite.height() as u64 > height as u64 {
+                        return Err(format!("the sprite '{}' exceeds the atlas of {} x {} pixels", fields[5], width, height));
+                    }
+                    if sprites.insert(fields[5].to_string(), sprite).is_some() {
+                        return Err(format!("the sprite map has more than one sprite named '{}'", fields[5]));
+                    }
+                },
+                _ => return Err(invalid()),
+            }
+        }
+
+        let (width, height) = size.ok_or_else(|| "the sprite map has no atlas size".to_string())?;
+        Ok((width, height, sprites))
+    }
+
+    /// Loads a sprite map written by `save_map`, see `parse_map`.
+    pub fn load_map<P: AsRef<Path>>(filename: P) -> Result<(u32, u32, HashMap<String, Sprite>), String> {
+        let path = filename.as_ref();
+        let mut map = String::new();
+        File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut map))
+            .map_err(|why| format!("couldn't read {}: {}", path.display(), why))?;
+
+        Atlas::parse_map(&map).map_err(|why| format!("couldn't load {}: {}", path.display(), why))
+    }
+}
+
+/// Places cells of the given sizes in the given order into an atlas, returning the top left
+/// corner of each cell in the original order, or None if they do not fit.
+fn pack(width: u32, height: u32, padding: u32, cells: &[(u32, u32)], order: &[usize]) -> Option<Vec<(u32, u32)>> {
+    // the padding at the top and left border, cells bring their own at the right and bottom
+    let mut free = vec![Rect { x: padding, y: padding, width: width - padding, height: height - padding }];
+    let mut placements = vec![(0, 0); cells.len()];
+
+    for &index in order {
+        let (cell_width, cell_height) = cells[index];
+
+        // the best short side fit, with the long side breaking ties
+        let best = free.iter()
+            .filter(|rect| rect.width >= cell_width && rect.height >= cell_height)
+            .min_by_key(|rect| {
+                let (dx, dy) = (rect.width - cell_width, rect.height - cell_height);
+                (dx.min(dy), dx.max(dy), rect.y, rect.x)
+            })
+            .cloned()?;
+
+        let placed = Rect { x: best.x, y: best.y, width: cell_width, height: cell_height };
+        placements[index] = (placed.x, placed.y);
+
+        // replace each free rectangle overlapping the cell with the parts around it
+        let mut split = Vec::with_capacity(free.len() + 4);
+        for rect in free {
+            if !rect.intersects(&placed) {
+                split.push(rect);
+                continue;
+            }
+
+            if placed.x > rect.x {
+                split.push(Rect { width: placed.x - rect.x, ..rect });
+            }
+            if placed.right() < rect.right() {
+                split.push(Rect { x: placed.right(), width: rect.right() - placed.right(), ..rect });
+            }
+            if placed.y > rect.y {
+                split.push(Rect { height: placed.y - rect.y, ..rect });
+            }
+            if placed.bottom() < rect.bottom() {
+                split.push(Rect { y: placed.bottom(), height: rect.bottom() - placed.bottom(), ..rect });
+            }
+        }
+
+        // drop free rectangles within others, keeping one of equal ones
+        free = split.iter().enumerate()
+            .filter(|&(i, rect)| !split.iter().enumerate().any(|(j, other)| {
+                i != j && other.contains(rect) && (other != rect || j < i)
+            }))
+            .map(|(_, rect)| *rect)
+            .collect();
+    }
+
+    Some(placements)
+}
+
+/// Surrounds the image with copies of its outermost rows and columns.
+fn extrude(image: &Image, extrusion: u32) -> Result<Image, String> {
+    if extrusion == 0 {
+        return Ok(image.clone());
+    }
+
+    let (width, height) = (image.width(), image.height());
+    let mut extruded = Image::empty(width + 2 * extrusion, height + 2 * extrusion, image.format());
+    extruded.blit(image, extrusion, extrusion)?;
+
+    let left  = image.crop(0, 0, 1, height)?;
+    let right = image.crop(width - 1, 0, 1, height)?;
+    for offset in 0..extrusion {
+        extruded.blit(&left, offset, extrusion)?;
+        extruded.blit(&right, extrusion + width + offset, extrusion)?;
+    }
+
+    // the rows along the top and bottom include the extruded columns, which fills the corners
+    let top    = extruded.crop(0, extrusion, extruded.width(), 1)?;
+    let bottom = extruded.crop(0, extrusion + height - 1, extruded.width(), 1)?;
+    for offset in 0..extrusion {
+        extruded.blit(&top, 0, offset)?;
+        extruded.blit(&bottom, 0, extrusion + height + offset)?;
+    }
+
+    Ok(extruded)
+}
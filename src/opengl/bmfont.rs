@@ -0,0 +1,927 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::Path;
+use std::str::FromStr;
+
+use super::Sprite;
+
+/// The `info` block of a BMFont file, describing how the font was generated. The fields mirror
+/// the attributes of the file format.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct FontInfo {
+    pub face      : String,
+    /// Size of the true type font, negative if it was matched by character height.
+    pub size      : i32,
+    pub bold      : bool,
+    pub italic    : bool,
+    pub charset   : String,
+    pub unicode   : bool,
+    /// Height stretch in percent.
+    pub stretch_h : u32,
+    pub smooth    : bool,
+    /// Supersampling level, 1 if none was used.
+    pub aa        : u32,
+    /// Padding of each glyph: up, right, down and left.
+    pub padding   : [u32; 4],
+    /// Spacing between glyphs on the page: horizontal and vertical.
+    pub spacing   : [u32; 2],
+    pub outline   : u32,
+}
+
+/// The `common` block of a BMFont file, the information shared by all glyphs.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct FontCommon {
+    /// Distance between the tops of two consecutive lines, in pixels.
+    pub line_height : u32,
+    /// Distance from the top of a line to the baseline, in pixels.
+    pub base        : u32,
+    /// Width of the pages.
+    pub scale_w     : u32,
+    /// Height of the pages.
+    pub scale_h     : u32,
+    pub pages       : u32,
+    /// Whether monochrome glyphs are packed into the separate channels of the pages.
+    pub packed      : bool,
+    pub alpha_chnl  : u8,
+    pub red_chnl    : u8,
+    pub green_chnl  : u8,
+    pub blue_chnl   : u8,
+}
+
+/// How the pages of a distance field font encode the distance to the glyph outlines.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FieldType {
+    /// A single channel signed distance field.
+    Sdf,
+    /// A single channel pseudo distance field, used like an Sdf.
+    Psdf,
+    /// A multi-channel signed distance field in RGB, the distance is the median of the channels.
+    Msdf,
+    /// A Msdf with a true signed distance field in alpha.
+    Mtsdf,
+}
+
+impl FieldType {
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FieldType::Sdf   => "sdf",
+            FieldType::Psdf  => "psdf",
+            FieldType::Msdf  => "msdf",
+            FieldType::Mtsdf => "mtsdf",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<FieldType, String> {
+        match name {
+            "sdf"   => Ok(FieldType::Sdf),
+            "psdf"  => Ok(FieldType::Psdf),
+            "msdf"  => Ok(FieldType::Msdf),
+            "mtsdf" => Ok(FieldType::Mtsdf),
+            _       => Err(format!("unknown distance field type '{}'", name)),
+        }
+    }
+
+    /// Whether the distance is the median of the red, green and blue channel.
+    pub fn is_multi_channel(self) -> bool {
+        self == FieldType::Msdf || self == FieldType::Mtsdf
+    }
+}
+
+/// The `distanceField` block written by distance field font generators such as
+/// msdf-bmfont-xml. The binary format has no block for it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DistanceField {
+    pub field_type     : FieldType,
+    /// The distance in pixels of the page covered by the values from 0 to 1, centered on the
+    /// outline at 0.5.
+    pub distance_range : u32,
+}
+
+/// A single glyph of a BMFont, i.e. a `char` entry.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Glyph {
+    /// The character, usually a unicode code point.
+    pub id       : u32,
+    /// Position on the page, from the top left.
+    pub x        : u32,
+    pub y        : u32,
+    pub width    : u32,
+    pub height   : u32,
+    /// Offset of the glyph image from the cursor, x to the right and y down from the top of the line.
+    pub xoffset  : i32,
+    pub yoffset  : i32,
+    /// How far the cursor moves after this glyph.
+    pub xadvance : i32,
+    /// Index of the page holding the glyph image.
+    pub page     : u32,
+    /// Channels holding the glyph image, 15 for all of them.
+    pub chnl     : u32,
+}
+
+impl Glyph {
+
+    /// The area of the glyph on its page, with y counted from the top as in the descriptor.
+    pub fn sprite(&self) -> Sprite {
+        Sprite::new(self.x, self.y, self.width, self.height)
+    }
+}
+
+/// Everything a BMFont descriptor file (`.fnt`) tells about a bitmap font: how it was generated,
+/// the line metrics, the file names of the pages and the position and metrics of each glyph,
+/// plus the kerning pairs.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct FontDescription {
+    info           : FontInfo,
+    common         : FontCommon,
+    distance_field : Option<DistanceField>,
+    pages          : Vec<String>,
+    glyphs         : HashMap<u32, Glyph>,
+    kernings       : HashMap<(u32, u32), i32>,
+}
+
+impl FontDescription {
+
+    /// Creates a description without pages, glyphs and kernings, to be filled by a font
+    /// generator.
+    pub fn new(info: FontInfo, common: FontCommon) -> FontDescription {
+        FontDescription {
+            info,
+            common,
+            distance_field : None,
+            pages          : Vec::new(),
+            glyphs         : HashMap::new(),
+            kernings       : HashMap::new(),
+        }
+    }
+
+    /// Loads a BMFont descriptor in the text, XML or binary format.
+    pub fn load<P: AsRef<Path>>(filename: P) -> Result<FontDescription, String> {
+        let path = filename.as_ref();
+
+        let mut file = File::open(path).map_err(|why| format!("couldn't open {}: {}", path.display(), why))?;
+        let mut content = Vec::new();
+        file.read_to_end(&mut content).map_err(|why| format!("couldn't read {}: {}", path.display(), why))?;
+
+        FontDescription::parse(&content).map_err(|why| format!("couldn't parse {}: {}", path.display(), why))
+    }
+
+    /// Parses a BMFont descriptor, telling the binary format by its `BMF` signature and the XML
+    /// format by its leading `<`.
+    pub fn parse(content: &[u8]) -> Result<FontDescription, String> {
+        if content.starts_with(b"BMF") {
+            return FontDescription::parse_binary(content);
+        }
+
+        let content = ::std::str::from_utf8(content).map_err(|_| "the descriptor is not valid UTF-8".to_string())?;
+        let content = content.trim_start_matches('\u{feff}');
+
+        if content.trim_start().starts_with('<') {
+            FontDescription::parse_xml(content)
+        } else {
+            FontDescription::parse_text(content)
+        }
+    }
+
+    /// Parses a BMFont descriptor in the text format, made of lines with a tag followed by
+    /// `key=value` pairs. Unknown tags and keys are ignored.
+    pub fn parse_text(content: &str) -> Result<FontDescription, String> {
+        let mut parser = Parser::default();
+
+        for (index, line) in content.lines().enumerate() {
+            let (tag, pairs) = tokenize(line).map_err(|why| format!("line {}: {}", index + 1, why))?;
+            parser.element(tag, &Attributes { pairs, line: index + 1 })?;
+        }
+
+        parser.finish()
+    }
+
+    /// Parses a BMFont descriptor in the XML format, with one element per line of the text
+    /// format and the same attributes.
+    pub fn parse_xml(content: &str) -> Result<FontDescription, String> {
+        let mut parser = Parser::default();
+        let mut rest = content;
+        let mut line = 1;
+
+        while let Some(start) = rest.find('<') {
+            line += rest[..start].matches('\n').count();
+            rest = &rest[start..];
+
+            let end = if rest.starts_with("<!--") {
+                rest.find("-->").map(|index| index + 3)
+            } else if rest.starts_with("<?") {
+                rest.find("?>").map(|index| index + 2)
+            } else if rest.starts_with("</") || rest.starts_with("<!") {
+                rest.find('>').map(|index| index + 1)
+            } else {
+                let (tag, pairs, remainder) = xml_element(&rest[1..]).map_err(|why| format!("line {}: {}", line, why))?;
+                parser.element(tag, &Attributes { pairs, line })?;
+                Some(rest.len() - remainder.len())
+            };
+
+            let end = end.ok_or_else(|| format!("line {}: unterminated markup", line))?;
+            line += rest[..end].matches('\n').count();
+            rest = &rest[end..];
+        }
+
+        parser.finish()
+    }
+
+    /// Parses a BMFont descriptor in the binary format of version 3, which starts with `BMF`
+    /// followed by the version byte and consists of blocks with a type and a size.
+    pub fn parse_binary(content: &[u8]) -> Result<FontDescription, String> {
+        if content.len() < 4 || &content[..3] != b"BMF" {
+            return Err("not a binary BMFont descriptor".to_string());
+        }
+        if content[3] != 3 {
+            return Err(format!("unsupported binary BMFont version {}", content[3]));
+        }
+
+        let mut description = FontDescription::default();
+        let mut has_common  = false;
+        let mut index = 4;
+
+        while index < content.len() {
+            if index + 5 > content.len() {
+                return Err("block header is truncated".to_string());
+            }
+
+            let block_type = content[index];
+            let size = read_u32(content, index + 1) as usize;
+            index += 5;
+
+            if index + size > content.len() {
+                return Err(format!("block {} is truncated", block_type));
+            }
+            let block = &content[index..index + size];
+            index += size;
+
+            match block_type {
+                1 => {
+                    if block.len() < 14 {
+                        return Err("info block is truncated".to_string());
+                    }
+                    let bits = block[2];
+                    description.info = FontInfo {
+                        face      : null_terminated(&block[14..]).0,
+                        size      : read_u16(block, 0) as i16 as i32,
+                        bold      : bits & 0x08 != 0,
+                        italic    : bits & 0x04 != 0,
+                        charset   : charset_name(block[3]),
+                        unicode   : bits & 0x02 != 0,
+                        stretch_h : read_u16(block, 4) as u32,
+                        smooth    : bits & 0x01 != 0,
+                        aa        : block[6] as u32,
+                        padding   : [block[7] as u32, block[8] as u32, block[9] as u32, block[10] as u32],
+                        spacing   : [block[11] as u32, block[12] as u32],
+                        outline   : block[13] as u32,
+                    };
+                },
+                2 => {
+                    if block.len() < 15 {
+                        return Err("common block is truncated".to_string());
+                    }
+                    has_common = true;
+                    description.common = FontCommon {
+                        line_height : read_u16(block, 0) as u32,
+                        base        : read_u16(block, 2) as u32,
+                        scale_w     : read_u16(block, 4) as u32,
+                        scale_h     : read_u16(block, 6) as u32,
+                        pages       : read_u16(block, 8) as u32,
+                        packed      : block[10] & 0x80 != 0,
+                        alpha_chnl  : block[11],
+                        red_chnl    : block[12],
+                        green_chnl  : block[13],
+                        blue_chnl   : block[14],
+                    };
+                },
+                3 => {
+                    let mut names = block;
+                    while !names.is_empty() {
+                        let (name, length) = null_terminated(names);
+                        description.pages.push(name);
+                        names = &names[length..];
+                    }
+                },
+                4 => {
+                    for entry in block.chunks(20) {
+                        if entry.len() < 20 {
+                            return Err("chars block is truncated".to_string());
+                        }
+                        description.insert_glyph(Glyph {
+                            id       : read_u32(entry, 0),
+                            x        : read_u16(entry, 4) as u32,
+                            y        : read_u16(entry, 6) as u32,
+                            width    : read_u16(entry, 8) as u32,
+                            height   : read_u16(entry, 10) as u32,
+                            xoffset  : read_u16(entry, 12) as i16 as i32,
+                            yoffset  : read_u16(entry, 14) as i16 as i32,
+                            xadvance : read_u16(entry, 16) as i16 as i32,
+                            page     : entry[18] as u32,
+                            chnl     : entry[19] as u32,
+                        });
+                    }
+                },
+                5 => {
+                    for entry in block.chunks(10) {
+                        if entry.len() < 10 {
+                            return Err("kerning block is truncated".to_string());
+                        }
+                        description.insert_kerning(read_u32(entry, 0), read_u32(entry, 4), read_u16(entry, 8) as i16 as i32);
+                    }
+                },
+                _ => return Err(format!("unknown block type {}", block_type)),
+            }
+        }
+
+        if !has_common {
+            return Err("the common block is missing".to_string());
+        }
+
+        description.check_pages()?;
+        Ok(description)
+    }
+
+    /// Writes the description in the text format.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+
+        for (tag, pairs) in self.elements() {
+            text.push_str(tag);
+            for (key, value) in pairs {
+                match value {
+                    Value::Text(value)   => text.push_str(&format!(" {}=\"{}\"", key, value)),
+                    Value::Number(value) => text.push_str(&format!(" {}={}", key, value)),
+                }
+            }
+            text.push('\n');
+        }
+
+        text
+    }
+
+    /// Writes the description in the XML format.
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\"?>\n<font>\n");
+        // pages, chars and kernings are children of an element with the plural name
+        let mut open : Option<&str> = None;
+
+        for (tag, pairs) in self.elements() {
+            let attributes : String = pairs.into_iter().map(|(key, value)| match value {
+                Value::Text(value)   => format!(" {}=\"{}\"", key, escape_xml(&value)),
+                Value::Number(value) => format!(" {}=\"{}\"", key, value),
+            }).collect();
+
+            match tag {
+                "page" | "char" | "kerning" => {
+                    if tag == "page" && open.is_none() {
+                        xml.push_str("  <pages>\n");
+                        open = Some("pages");
+                    }
+                    xml.push_str(&format!("    <{}{}/>\n", tag, attributes));
+                },
+                _ => {
+                    if let Some(parent) = open.take() {
+                        xml.push_str(&format!("  </{}>\n", parent));
+                    }
+                    if tag == "chars" || tag == "kernings" {
+                        xml.push_str(&format!("  <{}{}>\n", tag, attributes));
+                        open = Some(tag);
+                    } else {
+                        xml.push_str(&format!("  <{}{}/>\n", tag, attributes));
+                    }
+                },
+            }
+        }
+
+        if let Some(parent) = open {
+            xml.push_str(&format!("  </{}>\n", parent));
+        }
+        xml.push_str("</font>\n");
+
+        xml
+    }
+
+    /// Writes the description in the binary format of version 3. Glyphs and kerning pairs are
+    /// sorted by id, values beyond the ranges of the format are truncated.
+    pub fn to_binary(&self) -> Vec<u8> {
+        let mut binary = b"BMF\x03".to_vec();
+
+        let info = &self.info;
+        let mut block = Vec::new();
+        block.extend_from_slice(&(info.size as i16).to_le_bytes());
+        block.push((info.smooth as u8) | (info.unicode as u8) << 1 | (info.italic as u8) << 2 | (info.bold as u8) << 3);
+        block.push(charset_id(&info.charset));
+        block.extend_from_slice(&(info.stretch_h as u16).to_le_bytes());
+        block.push(info.aa as u8);
+        block.extend(info.padding.iter().map(|&padding| padding as u8));
+        block.extend(info.spacing.iter().map(|&spacing| spacing as u8));
+        block.push(info.outline as u8);
+        block.extend_from_slice(info.face.as_bytes());
+        block.push(0);
+        push_block(&mut binary, 1, &block);
+
+        let common = &self.common;
+        let mut block = Vec::new();
+        for value in &[common.line_height, common.base, common.scale_w, common.scale_h, common.pages] {
+            block.extend_from_slice(&(*value as u16).to_le_bytes());
+        }
+        block.push(if common.packed { 0x80 } else { 0 });
+        block.extend_from_slice(&[common.alpha_chnl, common.red_chnl, common.green_chnl, common.blue_chnl]);
+        push_block(&mut binary, 2, &block);
+
+        let mut block = Vec::new();
+        for page in &self.pages {
+            block.extend_from_slice(page.as_bytes());
+            block.push(0);
+        }
+        push_block(&mut binary, 3, &block);
+
+        let mut block = Vec::new();
+        for glyph in self.sorted_glyphs() {
+            block.extend_from_slice(&glyph.id.to_le_bytes());
+            for value in &[glyph.x, glyph.y, glyph.width, glyph.height] {
+                block.extend_from_slice(&(*value as u16).to_le_bytes());
+            }
+            for value in &[glyph.xoffset, glyph.yoffset, glyph.xadvance] {
+                block.extend_from_slice(&(*value as i16).to_le_bytes());
+            }
+            block.push(glyph.page as u8);
+            block.push(glyph.chnl as u8);
+        }
+        push_block(&mut binary, 4, &block);
+
+        if !self.kernings.is_empty() {
+            let mut block = Vec::new();
+            for ((first, second), amount) in self.sorted_kernings() {
+                block.extend_from_slice(&first.to_le_bytes());
+                block.extend_from_slice(&second.to_le_bytes());
+                block.extend_from_slice(&(amount as i16).to_le_bytes());
+            }
+            push_block(&mut binary, 5, &block);
+        }
+
+        binary
+    }
+
+    /// The tags and attributes shared by the text and XML formats, in file order.
+    fn elements(&self) -> Vec<(&'static str, Vec<(&'static str, Value)>)> {
+        let info   = &self.info;
+        let common = &self.common;
+        let list = |values: &[u32]| Value::Number(values.iter().map(|value| value.to_string()).collect::<Vec<_>>().join(","));
+
+        let mut elements = vec![
+            ("info", vec![
+                ("face",     Value::Text(info.face.clone())),
+                ("size",     Value::number(info.size)),
+                ("bold",     Value::number(info.bold as u8)),
+                ("italic",   Value::number(info.italic as u8)),
+                ("charset",  Value::Text(info.charset.clone())),
+                ("unicode",  Value::number(info.unicode as u8)),
+                ("stretchH", Value::number(info.stretch_h)),
+                ("smooth",   Value::number(info.smooth as u8)),
+                ("aa",       Value::number(info.aa)),
+                ("padding",  list(&info.padding)),
+                ("spacing",  list(&info.spacing)),
+                ("outline",  Value::number(info.outline)),
+            ]),
+            ("common", vec![
+                ("lineHeight", Value::number(common.line_height)),
+                ("base",       Value::number(common.base)),
+                ("scaleW",     Value::number(common.scale_w)),
+                ("scaleH",     Value::number(common.scale_h)),
+                ("pages",      Value::number(common.pages)),
+                ("packed",     Value::number(common.packed as u8)),
+                ("alphaChnl",  Value::number(common.alpha_chnl)),
+                ("redChnl",    Value::number(common.red_chnl)),
+                ("greenChnl",  Value::number(common.green_chnl)),
+                ("blueChnl",   Value::number(common.blue_chnl)),
+            ]),
+        ];
+
+        if let Some(field) = self.distance_field {
+            elements.push(("distanceField", vec![
+                ("fieldType",     Value::Text(field.field_type.name().to_string())),
+                ("distanceRange", Value::number(field.distance_range)),
+            ]));
+        }
+
+        for (id, file) in self.pages.iter().enumerate() {
+            elements.push(("page", vec![("id", Value::number(id)), ("file", Value::Text(file.clone()))]));
+        }
+
+        elements.push(("chars", vec![("count", Value::number(self.glyphs.len()))]));
+        for glyph in self.sorted_glyphs() {
+            elements.push(("char", vec![
+                ("id",       Value::number(glyph.id)),
+                ("x",        Value::number(glyph.x)),
+                ("y",        Value::number(glyph.y)),
+                ("width",    Value::number(glyph.width)),
+                ("height",   Value::number(glyph.height)),
+                ("xoffset",  Value::number(glyph.xoffset)),
+                ("yoffset",  Value::number(glyph.yoffset)),
+                ("xadvance", Value::number(glyph.xadvance)),
+                ("page",     Value::number(glyph.page)),
+                ("chnl",     Value::number(glyph.chnl)),
+            ]));
+        }
+
+        elements.push(("kernings", vec![("count", Value::number(self.kernings.len()))]));
+        for ((first, second), amount) in self.sorted_kernings() {
+            elements.push(("kerning", vec![
+                ("first",  Value::number(first)),
+                ("second", Value::number(second)),
+                ("amount", Value::number(amount)),
+            ]));
+        }
+
+        elements
+    }
+
+    fn sorted_glyphs(&self) -> Vec<&Glyph> {
+        let mut glyphs : Vec<&Glyph> = self.glyphs.values().collect();
+        glyphs.sort_by_key(|glyph| glyph.id);
+        glyphs
+    }
+
+    fn sorted_kernings(&self) -> Vec<((u32, u32), i32)> {
+        let mut kernings : Vec<((u32, u32), i32)> = self.kernings.iter().map(|(&pair, &amount)| (pair, amount)).collect();
+        kernings.sort();
+        kernings
+    }
+
+    pub fn info(&self) -> &FontInfo {
+        &self.info
+    }
+
+    pub fn info_mut(&mut self) -> &mut FontInfo {
+        &mut self.info
+    }
+
+    pub fn common(&self) -> &FontCommon {
+        &self.common
+    }
+
+    pub fn common_mut(&mut self) -> &mut FontCommon {
+        &mut self.common
+    }
+
+    /// How the pages encode distances, if this is a distance field font.
+    pub fn distance_field(&self) -> Option<&DistanceField> {
+        self.distance_field.as_ref()
+    }
+
+    pub fn set_distance_field(&mut self, distance_field: Option<DistanceField>) {
+        self.distance_field = distance_field;
+    }
+
+    /// File names of the pages, indexed by page id.
+    pub fn pages(&self) -> &[String] {
+        &self.pages
+    }
+
+    /// Sets the file name of the page with the given id, adding pages as needed. Fails if the
+    /// id is not below the number of pages in the common block.
+    pub fn set_page(&mut self, id: usize, file: String) -> Result<(), String> {
+        if id >= self.common.pages as usize {
+            return Err(format!("page id {} is out of range for {} page(s)", id, self.common.pages));
+        }
+
+        if self.pages.len() <= id {
+            self.pages.resize(id + 1, String::new());
+        }
+        self.pages[id] = file;
+        Ok(())
+    }
+
+    /// Fails if a page below the number of pages in the common block has no file name, as the
+    /// font could not be loaded from such a description.
+    fn check_pages(&self) -> Result<(), String> {
+        for id in 0..self.common.pages as usize {
+            if self.pages.get(id).is_none_or(|file| file.is_empty()) {
+                return Err(format!("page id {} is missing, the common block declares {} page(s)", id, self.common.pages));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn glyph(&self, id: u32) -> Option<&Glyph> {
+        self.glyphs.get(&id)
+    }
+
+    pub fn glyphs(&self) -> &HashMap<u32, Glyph> {
+        &self.glyphs
+    }
+
+    pub fn insert_glyph(&mut self, glyph: Glyph) {
+        self.glyphs.insert(glyph.id, glyph);
+    }
+
+    /// The amount the cursor moves additionally between the two glyphs, usually negative.
+    pub fn kerning(&self, first: u32, second: u32) -> i32 {
+        self.kernings.get(&(first, second)).cloned().unwrap_or(0)
+    }
+
+    pub fn kernings(&self) -> &HashMap<(u32, u32), i32> {
+        &self.kernings
+    }
+
+    pub fn insert_kerning(&mut self, first: u32, second: u32, amount: i32) {
+        self.kernings.insert((first, second), amount);
+    }
+}
+
+/// The `key=value` pairs of a line, values are unquoted.
+type Pairs<'a> = Vec<(&'a str, String)>;
+
+/// The `key=value` pairs of a line along with the line number for error messages.
+struct Attributes<'a> {
+    pairs : Pairs<'a>,
+    line  : usize,
+}
+
+impl<'a> Attributes<'a> {
+
+    fn get(&self, key: &str) -> Option<&str> {
+        self.pairs.iter().find(|(k, _)| *k == key).map(|(_, value)| value.as_str())
+    }
+
+    fn string(&self, key: &str) -> String {
+        self.get(key).unwrap_or("").to_string()
+    }
+
+    fn parse<T: FromStr>(&self, key: &str, value: &str) -> Result<T, String> {
+        value.trim().parse::<T>().map_err(|_| format!("line {}: invalid value '{}' for {}", self.line, value, key))
+    }
+
+    fn number_or<T: FromStr>(&self, key: &str, default: T) -> Result<T, String> {
+        match self.get(key) {
+            Some(value) => self.parse(key, value),
+            None        => Ok(default),
+        }
+    }
+
+    fn number<T: FromStr + Default>(&self, key: &str) -> Result<T, String> {
+        self.number_or(key, T::default())
+    }
+
+    fn required<T: FromStr>(&self, key: &str) -> Result<T, String> {
+        match self.get(key) {
+            Some(value) => self.parse(key, value),
+            None        => Err(format!("line {}: {} is missing", self.line, key)),
+        }
+    }
+
+    fn flag(&self, key: &str) -> Result<bool, String> {
+        Ok(self.number::<u32>(key)? != 0)
+    }
+
+    /// Parses a comma separated list such as `padding=1,1,1,1`, missing elements are zero.
+    fn list<T: FromStr + Default + Copy, const N: usize>(&self, key: &str) -> Result<[T; N], String> {
+        let mut list = [T::default(); N];
+        if let Some(value) = self.get(key) {
+            for (element, part) in list.iter_mut().zip(value.split(',')) {
+                *element = self.parse(key, part)?;
+            }
+        }
+        Ok(list)
+    }
+}
+
+/// Splits a line into its tag and its `key=value` pairs. Values may be quoted to contain spaces.
+fn tokenize(line: &str) -> Result<(&str, Pairs<'_>), String> {
+    let line = line.trim();
+    let (tag, mut rest) = match line.find(char::is_whitespace) {
+        Some(end) => (&line[..end], line[end..].trim_start()),
+        None      => (line, ""),
+    };
+
+    let mut pairs = Vec::new();
+    while !rest.is_empty() {
+        let equals = rest.find('=').ok_or_else(|| format!("expected key=value, found '{}'", rest))?;
+        let key = rest[..equals].trim();
+        rest = &rest[equals + 1..];
+
+        let value = if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted.find('"').ok_or_else(|| format!("unterminated string for {}", key))?;
+            rest = &quoted[end + 1..];
+            quoted[..end].to_string()
+        } else {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            let value = rest[..end].to_string();
+            rest = &rest[end..];
+            value
+        };
+
+        pairs.push((key, value));
+        rest = rest.trim_start();
+    }
+
+    Ok((tag, pairs))
+}
+
+/// Builds a description from the elements of the text and XML formats.
+#[derive(Default)]
+struct Parser {
+    description : FontDescription,
+    has_common  : bool,
+}
+
+impl Parser {
+
+    fn element(&mut self, tag: &str, attributes: &Attributes) -> Result<(), String> {
+        let description = &mut self.description;
+
+        match tag {
+            "info" => {
+                description.info = FontInfo {
+                    face      : attributes.string("face"),
+                    size      : attributes.number("size")?,
+                    bold      : attributes.flag("bold")?,
+                    italic    : attributes.flag("italic")?,
+                    charset   : attributes.string("charset"),
+                    unicode   : attributes.flag("unicode")?,
+                    stretch_h : attributes.number_or("stretchH", 100)?,
+                    smooth    : attributes.flag("smooth")?,
+                    aa        : attributes.number_or("aa", 1)?,
+                    padding   : attributes.list("padding")?,
+                    spacing   : attributes.list("spacing")?,
+                    outline   : attributes.number("outline")?,
+                };
+            },
+            "common" => {
+                self.has_common = true;
+                description.common = FontCommon {
+                    line_height : attributes.required("lineHeight")?,
+                    base        : attributes.required("base")?,
+                    scale_w     : attributes.required("scaleW")?,
+                    scale_h     : attributes.required("scaleH")?,
+                    pages       : attributes.number_or("pages", 1)?,
+                    packed      : attributes.flag("packed")?,
+                    alpha_chnl  : attributes.number("alphaChnl")?,
+                    red_chnl    : attributes.number("redChnl")?,
+                    green_chnl  : attributes.number("greenChnl")?,
+                    blue_chnl   : attributes.number("blueChnl")?,
+                };
+            },
+            "distanceField" => {
+                description.distance_field = Some(DistanceField {
+                    field_type     : FieldType::from_name(&attributes.string("fieldType"))
+                        .map_err(|why| format!("line {}: {}", attributes.line, why))?,
+                    distance_range : attributes.required("distanceRange")?,
+                });
+            },
+            "page" => {
+                let id : usize = attributes.required("id")?;
+                description.set_page(id, attributes.string("file"))
+                    .map_err(|why| format!("line {}: {}", attributes.line, why))?;
+            },
+            "char" => {
+                description.insert_glyph(Glyph {
+                    id       : attributes.required("id")?,
+                    x        : attributes.number("x")?,
+                    y        : attributes.number("y")?,
+                    width    : attributes.number("width")?,
+                    height   : attributes.number("height")?,
+                    xoffset  : attributes.number("xoffset")?,
+                    yoffset  : attributes.number("yoffset")?,
+                    xadvance : attributes.number("xadvance")?,
+                    page     : attributes.number("page")?,
+                    chnl     : attributes.number_or("chnl", 15)?,
+                });
+            },
+            "kerning" => {
+                description.insert_kerning(attributes.required("first")?, attributes.required("second")?,
+                                           attributes.number("amount")?);
+            },
+            _ => {},
+        }
+
+        Ok(())
+    }
+
+    fn finish(self) -> Result<FontDescription, String> {
+        if !self.has_common {
+            return Err("the common block is missing".to_string());
+        }
+
+        self.description.check_pages()?;
+        Ok(self.description)
+    }
+}
+
+/// An attribute value to be written, text is quoted in the text format.
+enum Value {
+    Text(String),
+    Number(String),
+}
+
+impl Value {
+
+    fn number<T: ToString>(value: T) -> Value {
+        Value::Number(value.to_string())
+    }
+}
+
+/// Reads the name and attributes of an XML element from just after its `<`, returning the rest
+/// of the content after the element's `>`.
+fn xml_element(content: &str) -> Result<(&str, Pairs<'_>, &str), String> {
+    let end = content.find(|c: char| c.is_whitespace() || c == '/' || c == '>').unwrap_or(content.len());
+    let tag = &content[..end];
+    let mut rest = content[end..].trim_start();
+    let mut pairs = Vec::new();
+
+    loop {
+        if let Some(remainder) = rest.strip_prefix("/>").or_else(|| rest.strip_prefix('>')) {
+            return Ok((tag, pairs, remainder));
+        }
+
+        let equals = rest.find('=').ok_or_else(|| format!("malformed attribute in <{}>", tag))?;
+        let key = rest[..equals].trim();
+        rest = rest[equals + 1..].trim_start();
+
+        let quote = match rest.chars().next() {
+            Some(quote) if quote == '"' || quote == '\'' => quote,
+            _ => return Err(format!("the value of {} is not quoted", key)),
+        };
+        let end = rest[1..].find(quote).ok_or_else(|| format!("unterminated string for {}", key))?;
+
+        pairs.push((key, unescape_xml(&rest[1..end + 1])?));
+        rest = rest[end + 2..].trim_start();
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Replaces the predefined entities and character references.
+fn unescape_xml(text: &str) -> Result<String, String> {
+    let mut result = String::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let end = rest.find(';').ok_or_else(|| format!("unterminated entity in '{}'", text))?;
+        let entity = &rest[1..end];
+        let character = match entity {
+            "amp"  => Some('&'),
+            "lt"   => Some('<'),
+            "gt"   => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ if entity.starts_with("#x") => u32::from_str_radix(&entity[2..], 16).ok().and_then(::std::char::from_u32),
+            _ if entity.starts_with('#')  => entity[1..].parse::<u32>().ok().and_then(::std::char::from_u32),
+            _ => None,
+        };
+
+        result.push(character.ok_or_else(|| format!("unknown entity &{};", entity))?);
+        rest = &rest[end + 1..];
+    }
+
+    result.push_str(rest);
+    Ok(result)
+}
+
+/// Reads a null terminated string, returning it along with the number of bytes read.
+fn null_terminated(bytes: &[u8]) -> (String, usize) {
+    let length = bytes.iter().position(|&byte| byte == 0).unwrap_or(bytes.len());
+    (String::from_utf8_lossy(&bytes[..length]).into_owned(), (length + 1).min(bytes.len()))
+}
+
+fn push_block(binary: &mut Vec<u8>, block_type: u8, block: &[u8]) {
+    binary.push(block_type);
+    binary.extend_from_slice(&(block.len() as u32).to_le_bytes());
+    binary.extend_from_slice(block);
+}
+
+/// Windows character set ids of the binary format and their names in the text format. Unicode
+/// fonts are written with an empty name and id 0.
+const CHARSETS : [(u8, &str); 19] = [
+    (0, "ANSI"), (1, "DEFAULT"), (2, "SYMBOL"), (77, "MAC"), (128, "SHIFTJIS"), (129, "HANGUL"),
+    (130, "JOHAB"), (134, "GB2312"), (136, "CHINESEBIG5"), (161, "GREEK"), (162, "TURKISH"),
+    (163, "VIETNAMESE"), (177, "HEBREW"), (178, "ARABIC"), (186, "BALTIC"), (204, "RUSSIAN"),
+    (222, "THAI"), (238, "EASTEUROPE"), (255, "OEM"),
+];
+
+fn charset_name(id: u8) -> String {
+    match CHARSETS.iter().find(|(charset, _)| *charset == id) {
+        Some((0, _)) | None => String::new(),
+        Some((_, name))     => name.to_string(),
+    }
+}
+
+fn charset_id(name: &str) -> u8 {
+    CHARSETS.iter().find(|(_, charset)| *charset == name).map(|(id, _)| *id).unwrap_or(0)
+}
+
+fn read_u16(buffer: &[u8], index: usize) -> u16 {
+    buffer[index] as u16 | ((buffer[index + 1] as u16) << 8)
+}
+
+fn read_u32(buffer: &[u8], index: usize) -> u32 {
+    read_u16(buffer, index) as u32 | ((read_u16(buffer, index + 2) as u32) << 16)
+}
@@ -0,0 +1,200 @@
+extern crate gl;
+
+use std::path::Path;
+
+use image::{self, Image};
+use super::{texture_pixels, TextureFormat};
+use super::sampler::SamplerDesc;
+
+/// One of the six faces of a CubeMap, in the order OpenGL numbers them.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CubeFace {
+    PositiveX,
+    NegativeX,
+    PositiveY,
+    NegativeY,
+    PositiveZ,
+    NegativeZ,
+}
+
+impl CubeFace {
+
+    /// All faces in the order expected by `CubeMap::from_images`.
+    pub const ALL : [CubeFace; 6] = [
+        CubeFace::PositiveX, CubeFace::NegativeX,
+        CubeFace::PositiveY, CubeFace::NegativeY,
+        CubeFace::PositiveZ, CubeFace::NegativeZ,
+    ];
+
+    /// The texture target of the face, e.g. `gl::TEXTURE_CUBE_MAP_POSITIVE_X`.
+    pub fn gl_enum(self) -> gl::types::GLenum {
+        gl::TEXTURE_CUBE_MAP_POSITIVE_X + self as gl::types::GLenum
+    }
+}
+
+/// Cube map texture, six square faces sampled with a direction vector, as used for skyboxes
+/// and environment reflections.
+///
+/// Other than 2d textures, cube map faces have their origin at the top left, so images are
+/// uploaded as decoded from files, without flipping them.
+#[allow(dead_code)]
+pub struct CubeMap {
+    id     : gl::types::GLuint,
+    size   : u32,
+    format : TextureFormat,
+}
+
+#[allow(dead_code)]
+impl CubeMap {
+
+    /// Creates a cube map from six square images of the same size and format, ordered +X, -X,
+    /// +Y, -Y, +Z, -Z as in `CubeFace::ALL`.
+    pub fn from_images(faces: &[Image]) -> Result<CubeMap, String> {
+        if faces.len() != 6 {
+            return Err(format!("a cube map needs 6 faces, but {} were given", faces.len()));
+        }
+
+        let size = faces[0].width();
+        let mut uploads = Vec::with_capacity(6);
+
+        for (face, image) in CubeFace::ALL.iter().zip(faces) {
+            if image.width() != size || image.height() != size {
+                return Err(format!("cube map face {:?} is {} x {}, but all faces have to be {} x {}",
+                                   face, image.width(), image.height(), size, size));
+            }
+            uploads.push(texture_pixels(image));
+        }
+
+        let format = uploads[0].0;
+        if let Some(&(other, _)) = uploads.iter().find(|&&(f, _)| f != format) {
+            return Err(format!("cube map faces have different formats, {:?} and {:?}", format, other));
+        }
+
+        let mut id = 0;
+        unsafe {
+            gl::GenTextures(1, &mut id);
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, id);
+
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, format.row_alignment(size));
+            for (face, (_, pixels)) in CubeFace::ALL.iter().zip(&uploads) {
+                gl::TexImage2D(face.gl_enum(), 0, format.internal_format() as i32, size as i32, size as i32, 0,
+                               format.pixel_format(), format.pixel_type(), pixels.as_ptr() as *const _);
+            }
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+
+            SamplerDesc::default().apply(gl::TEXTURE_CUBE_MAP);
+
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, 0);
+        }
+
+        Ok(CubeMap {
+            id,
+            size,
+            format,
+        })
+    }
+
+    /// Loads the six faces from image files, ordered +X, -X, +Y, -Y, +Z, -Z.
+    pub fn from_files<P: AsRef<Path>>(filenames: &[P]) -> Result<CubeMap, String> {
+        let faces = filenames.iter().map(image::load).collect::<Result<Vec<Image>, String>>()?;
+        CubeMap::from_images(&faces)
+    }
+
+    /// Creates a cube map from a single image with the faces laid out as a cross, see `split_cross`.
+    pub fn from_cross_image(image: &Image) -> Result<CubeMap, String> {
+        CubeMap::from_images(&split_cross(image)?)
+    }
+
+    /// Loads a cube map from a single image file with the faces laid out as a cross.
+    pub fn from_cross_file<P: AsRef<Path>>(filename: P) -> Result<CubeMap, String> {
+        CubeMap::from_cross_image(&image::load(filename)?)
+    }
+
+    pub fn id(&self) -> gl::types::GLuint {
+        self.id
+    }
+
+    /// Width and height of each face.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    /// Sets how the cube map is sampled. The wrap modes hardly matter for cube maps, but
+    /// `ClampToEdge` avoids seams on drivers without seamless cube map filtering.
+    pub fn set_sampler(&self, desc: &SamplerDesc) {
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, self.id);
+            desc.apply(gl::TEXTURE_CUBE_MAP);
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, 0);
+        }
+    }
+
+    /// Lets the driver compute all mipmap levels of all faces.
+    pub fn generate_mipmaps(&self) {
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, self.id);
+            gl::GenerateMipmap(gl::TEXTURE_CUBE_MAP);
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, 0);
+        }
+    }
+
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, self.id);
+        }
+    }
+
+    pub fn unbind() {
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, 0);
+        }
+    }
+}
+
+/// Cuts the six faces out of an image with a cross layout and returns them ordered +X, -X, +Y,
+/// -Y, +Z, -Z. Horizontal crosses are 4 faces wide and 3 high, vertical crosses 3 wide and 4 high:
+///
+/// ```text
+///      +Y                 +Y
+/// -X   +Z   +X   -Z  -X   +Z   +X
+///      -Y                 -Y
+///                         -Z
+/// ```
+///
+/// In the vertical cross, -Z is stored upside down, as it would be seen when folding the cross
+/// into a cube, and gets rotated back.
+pub fn split_cross(image: &Image) -> Result<Vec<Image>, String> {
+    let (width, height) = (image.width(), image.height());
+
+    let (size, vertical) = if width % 4 == 0 && height % 3 == 0 && width / 4 == height / 3 {
+        (width / 4, false)
+    } else if width % 3 == 0 && height % 4 == 0 && width / 3 == height / 4 {
+        (height / 4, true)
+    } else {
+        return Err(format!("a {} x {} image is neither a horizontal (4:3) nor a vertical (3:4) cube cross",
+                           width, height));
+    };
+
+    // column and row of each face in the cross, ordered as in CubeFace::ALL
+    let cells = if vertical {
+        [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (1, 3)]
+    } else {
+        [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (3, 1)]
+    };
+
+    let mut faces = Vec::with_capacity(6);
+    for &(column, row) in &cells {
+        faces.push(image.crop(column * size, row * size, size, size)?);
+    }
+
+    if vertical {
+        faces[5].flip_vertical();
+        faces[5].flip_horizontal();
+    }
+
+    Ok(faces)
+}
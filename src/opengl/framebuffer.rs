@@ -0,0 +1,416 @@
+extern crate gl;
+
+use image::{Image, PixelFormat};
+use super::{Texture, TextureFormat};
+
+/// Storage for a framebuffer attachment that is rendered to but never sampled, e.g. the depth
+/// buffer of an offscreen pass or the targets of a multisampled framebuffer.
+#[allow(dead_code)]
+pub struct Renderbuffer {
+    id      : gl::types::GLuint,
+    width   : u32,
+    height  : u32,
+    format  : TextureFormat,
+    samples : u32,
+}
+
+#[allow(dead_code)]
+impl Renderbuffer {
+
+    /// Allocates a renderbuffer, with multisampling if samples is greater than 0.
+    pub fn new(width: u32, height: u32, format: TextureFormat, samples: u32) -> Renderbuffer {
+        let mut id = 0;
+        unsafe {
+            gl::GenRenderbuffers(1, &mut id);
+            gl::BindRenderbuffer(gl::RENDERBUFFER, id);
+            gl::RenderbufferStorageMultisample(gl::RENDERBUFFER, samples as i32, format.internal_format(),
+                                               width as i32, height as i32);
+            gl::BindRenderbuffer(gl::RENDERBUFFER, 0);
+        }
+
+        Renderbuffer {
+            id,
+            width,
+            height,
+            format,
+            samples,
+        }
+    }
+
+    pub fn id(&self) -> gl::types::GLuint {
+        self.id
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+}
+
+/// The storage behind an attachment of a Framebuffer.
+pub enum Attachment {
+    Texture(Texture),
+    Renderbuffer(Renderbuffer),
+}
+
+impl Attachment {
+
+    pub fn format(&self) -> TextureFormat {
+        match *self {
+            Attachment::Texture(ref texture)           => texture.format(),
+            Attachment::Renderbuffer(ref renderbuffer) => renderbuffer.format(),
+        }
+    }
+
+    /// The texture, if the attachment is backed by one.
+    pub fn texture(&self) -> Option<&Texture> {
+        match *self {
+            Attachment::Texture(ref texture) => Some(texture),
+            Attachment::Renderbuffer(_)      => None,
+        }
+    }
+
+    fn attach(&self, attachment_point: gl::types::GLenum) {
+        unsafe {
+            match *self {
+                Attachment::Texture(ref texture) =>
+                    gl::FramebufferTexture2D(gl::FRAMEBUFFER, attachment_point, gl::TEXTURE_2D, texture.id(), 0),
+                Attachment::Renderbuffer(ref renderbuffer) =>
+                    gl::FramebufferRenderbuffer(gl::FRAMEBUFFER, attachment_point, gl::RENDERBUFFER, renderbuffer.id()),
+            }
+        }
+    }
+}
+
+/// Describes a Framebuffer before it is created: its size, its color attachments in the order
+/// of the fragment shader outputs, an optional depth or depth/stencil attachment and the number
+/// of samples for multisampling.
+///
+/// Attachments are backed by Textures, to read the result in a later pass, or by renderbuffers,
+/// if they are only rendered to. Multisampled framebuffers only support renderbuffers; they are
+/// resolved into a single sampled framebuffer with `Framebuffer::resolve_into`.
+pub struct FramebufferBuilder {
+    width   : u32,
+    height  : u32,
+    samples : u32,
+    colors  : Vec<(TextureFormat, bool)>,
+    depth   : Option<(TextureFormat, bool)>,
+}
+
+impl FramebufferBuilder {
+
+    pub fn new(width: u32, height: u32) -> FramebufferBuilder {
+        FramebufferBuilder {
+            width,
+            height,
+            samples : 0,
+            colors  : Vec::new(),
+            depth   : None,
+        }
+    }
+
+    /// Adds a color attachment backed by a Texture.
+    pub fn color_texture(mut self, format: TextureFormat) -> FramebufferBuilder {
+        self.colors.push((format, true));
+        self
+    }
+
+    /// Adds a color attachment backed by a renderbuffer.
+    pub fn color_renderbuffer(mut self, format: TextureFormat) -> FramebufferBuilder {
+        self.colors.push((format, false));
+        self
+    }
+
+    /// Sets a depth or depth/stencil attachment backed by a Texture, e.g. for shadow maps.
+    pub fn depth_texture(mut self, format: TextureFormat) -> FramebufferBuilder {
+        self.depth = Some((format, true));
+        self
+    }
+
+    /// Sets a depth or depth/stencil attachment backed by a renderbuffer.
+    pub fn depth_renderbuffer(mut self, format: TextureFormat) -> FramebufferBuilder {
+        self.depth = Some((format, false));
+        self
+    }
+
+    /// Number of samples per pixel, 0 disables multisampling.
+    pub fn samples(mut self, samples: u32) -> FramebufferBuilder {
+        self.samples = samples;
+        self
+    }
+
+    /// Checks the description without touching OpenGL, `build` calls it first.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.width == 0 || self.height == 0 {
+            return Err(format!("invalid framebuffer size {} x {}", self.width, self.height));
+        }
+
+        if self.colors.is_empty() && self.depth.is_none() {
+            return Err("a framebuffer needs at least one attachment".to_string());
+        }
+
+        for (index, &(format, _)) in self.colors.iter().enumerate() {
+            if format.is_depth() {
+                return Err(format!("color attachment {} has the depth format {:?}", index, format));
+            }
+        }
+
+        if let Some((format, _)) = self.depth {
+            if !format.is_depth() {
+                return Err(format!("the depth attachment has the color format {:?}", format));
+            }
+        }
+
+        if self.samples > 0 && self.colors.iter().chain(self.depth.iter()).any(|&(_, texture)| texture) {
+            return Err("multisampled framebuffers only support renderbuffer attachments, \
+                        resolve them into a framebuffer with texture attachments instead".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Creates the framebuffer with all attachments and checks it for completeness.
+    pub fn build(self) -> Result<Framebuffer, String> {
+        self.validate()?;
+
+        let (width, height, samples) = (self.width, self.height, self.samples);
+
+        let mut max_color_attachments = 0;
+        let mut max_samples = 0;
+        unsafe {
+            gl::GetIntegerv(gl::MAX_COLOR_ATTACHMENTS, &mut max_color_attachments);
+            gl::GetIntegerv(gl::MAX_SAMPLES, &mut max_samples);
+        }
+
+        if self.colors.len() > max_color_attachments as usize {
+            return Err(format!("{} color attachments were requested, but only {} are supported",
+                               self.colors.len(), max_color_attachments));
+        }
+        if samples > max_samples as u32 {
+            return Err(format!("{} samples were requested, but only {} are supported", samples, max_samples));
+        }
+
+        let create = |(format, texture): (TextureFormat, bool)| -> Result<Attachment, String> {
+            if texture {
+                Ok(Attachment::Texture(Texture::with_format(width, height, format, None)?))
+            } else {
+                Ok(Attachment::Renderbuffer(Renderbuffer::new(width, height, format, samples)))
+            }
+        };
+
+        let colors = self.colors.iter().cloned().map(&create).collect::<Result<Vec<Attachment>, String>>()?;
+        let depth = match self.depth {
+            Some(depth) => Some(create(depth)?),
+            None        => None,
+        };
+
+        let mut id = 0;
+        unsafe {
+            let mut previous = 0;
+            gl::GetIntegerv(gl::FRAMEBUFFER_BINDING, &mut previous);
+
+            gl::GenFramebuffers(1, &mut id);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, id);
+
+            for (index, color) in colors.iter().enumerate() {
+                color.attach(gl::COLOR_ATTACHMENT0 + index as gl::types::GLenum);
+            }
+
+            if let Some(ref depth) = depth {
+                let point = if depth.format().has_stencil() { gl::DEPTH_STENCIL_ATTACHMENT } else { gl::DEPTH_ATTACHMENT };
+                depth.attach(point);
+            }
+
+            // fragment shader output n is written to color attachment n
+            if colors.is_empty() {
+                gl::DrawBuffer(gl::NONE);
+                gl::ReadBuffer(gl::NONE);
+            } else {
+                let buffers : Vec<gl::types::GLenum> = (0..colors.len()).map(|i| gl::COLOR_ATTACHMENT0 + i as gl::types::GLenum).collect();
+                gl::DrawBuffers(buffers.len() as i32, buffers.as_ptr());
+                gl::ReadBuffer(gl::COLOR_ATTACHMENT0);
+            }
+
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, previous as gl::types::GLuint);
+
+            if status != gl::FRAMEBUFFER_COMPLETE {
+                gl::DeleteFramebuffers(1, &id);
+                return Err(format!("framebuffer is incomplete: {}", status_message(status)));
+            }
+        }
+
+        Ok(Framebuffer {
+            id,
+            width,
+            height,
+            samples,
+            colors,
+            depth,
+        })
+    }
+}
+
+/// A framebuffer object (FBO), an offscreen render target used for post-processing, shadow maps,
+/// thumbnails and everything else that is not drawn directly to the window.
+#[allow(dead_code)]
+pub struct Framebuffer {
+    id      : gl::types::GLuint,
+    width   : u32,
+    height  : u32,
+    samples : u32,
+    colors  : Vec<Attachment>,
+    depth   : Option<Attachment>,
+}
+
+#[allow(dead_code)]
+impl Framebuffer {
+
+    pub fn id(&self) -> gl::types::GLuint {
+        self.id
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+
+    pub fn color_attachments(&self) -> &[Attachment] {
+        &self.colors
+    }
+
+    pub fn depth_attachment(&self) -> Option<&Attachment> {
+        self.depth.as_ref()
+    }
+
+    /// The texture of the color attachment with the given index, if it is backed by a texture.
+    pub fn color_texture(&self, index: usize) -> Option<&Texture> {
+        self.colors.get(index).and_then(|color| color.texture())
+    }
+
+    pub fn depth_texture(&self) -> Option<&Texture> {
+        self.depth.as_ref().and_then(|depth| depth.texture())
+    }
+
+    /// Reads a rectangle of the first color attachment into an Image with the top row first,
+    /// see `opengl::read_pixels`. Multisampled framebuffers have to be resolved first.
+    pub fn read_pixels(&self, x: i32, y: i32, width: u32, height: u32, format: PixelFormat) -> Result<Image, String> {
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.id);
+        }
+        let result = super::read_pixels(x, y, width, height, format);
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+        }
+        result
+    }
+
+    /// Directs all following draw calls into this framebuffer and sets the viewport to its size.
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.id);
+            gl::Viewport(0, 0, self.width as i32, self.height as i32);
+        }
+    }
+
+    /// Switches back to the default framebuffer of the window. The viewport has to be set to the
+    /// window size again by the caller.
+    pub fn unbind() {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+    }
+
+    /// Copies the given buffers (a combination of `gl::COLOR_BUFFER_BIT`, `gl::DEPTH_BUFFER_BIT`
+    /// and `gl::STENCIL_BUFFER_BIT`) into another framebuffer or, with None, into the default
+    /// framebuffer of the given size, scaling if the sizes differ. Color is copied from the first
+    /// attachment. Depth and stencil can only be copied with `gl::NEAREST` filtering.
+    pub fn blit_to(&self, target: Option<&Framebuffer>, target_size: (u32, u32),
+                   mask: gl::types::GLbitfield, filter: gl::types::GLenum) {
+        let target_id = target.map_or(0, |target| target.id);
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.id);
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, target_id);
+            gl::BlitFramebuffer(0, 0, self.width as i32, self.height as i32,
+                                0, 0, target_size.0 as i32, target_size.1 as i32, mask, filter);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+    }
+
+    /// Resolves a multisampled framebuffer into a single sampled one of the same size: every
+    /// color attachment into the color attachment with the same index, and depth if both have it.
+    pub fn resolve_into(&self, target: &Framebuffer) -> Result<(), String> {
+        if (self.width, self.height) != (target.width, target.height) {
+            return Err(format!("cannot resolve a {} x {} framebuffer into one of {} x {}",
+                               self.width, self.height, target.width, target.height));
+        }
+        if target.colors.len() < self.colors.len() {
+            return Err(format!("cannot resolve {} color attachments into {}", self.colors.len(), target.colors.len()));
+        }
+
+        let (width, height) = (self.width as i32, self.height as i32);
+
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.id);
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, target.id);
+
+            for index in 0..self.colors.len() {
+                let attachment = gl::COLOR_ATTACHMENT0 + index as gl::types::GLenum;
+                gl::ReadBuffer(attachment);
+                gl::DrawBuffer(attachment);
+                gl::BlitFramebuffer(0, 0, width, height, 0, 0, width, height, gl::COLOR_BUFFER_BIT, gl::NEAREST);
+            }
+
+            if self.depth.is_some() && target.depth.is_some() {
+                gl::BlitFramebuffer(0, 0, width, height, 0, 0, width, height, gl::DEPTH_BUFFER_BIT, gl::NEAREST);
+            }
+
+            // restore the read and draw buffers set up when the framebuffers were built
+            if !self.colors.is_empty() {
+                gl::ReadBuffer(gl::COLOR_ATTACHMENT0);
+            }
+            if !target.colors.is_empty() {
+                let buffers : Vec<gl::types::GLenum> = (0..target.colors.len()).map(|i| gl::COLOR_ATTACHMENT0 + i as gl::types::GLenum).collect();
+                gl::DrawBuffers(buffers.len() as i32, buffers.as_ptr());
+            }
+
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+
+        Ok(())
+    }
+}
+
+/// Explains a status returned by `glCheckFramebufferStatus`.
+pub fn status_message(status: gl::types::GLenum) -> &'static str {
+    match status {
+        gl::FRAMEBUFFER_COMPLETE                      => "complete",
+        gl::FRAMEBUFFER_UNDEFINED                     => "the default framebuffer does not exist",
+        gl::FRAMEBUFFER_INCOMPLETE_ATTACHMENT         => "an attachment is incomplete, e.g. it has a size of zero or a format that cannot be rendered to",
+        gl::FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT => "there are no attachments",
+        gl::FRAMEBUFFER_INCOMPLETE_DRAW_BUFFER        => "a draw buffer refers to a missing attachment",
+        gl::FRAMEBUFFER_INCOMPLETE_READ_BUFFER        => "the read buffer refers to a missing attachment",
+        gl::FRAMEBUFFER_UNSUPPORTED                   => "the combination of attachment formats is not supported by the driver",
+        gl::FRAMEBUFFER_INCOMPLETE_MULTISAMPLE        => "the attachments have different numbers of samples",
+        gl::FRAMEBUFFER_INCOMPLETE_LAYER_TARGETS      => "layered and non-layered attachments are mixed",
+        _                                             => "unknown framebuffer status",
+    }
+}
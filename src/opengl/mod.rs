@@ -3,9 +3,32 @@ extern crate gl;
 pub mod primitives;
 pub mod program;
 pub mod mesh;
+pub mod uniform;
+pub mod preprocessor;
+pub mod watcher;
+pub mod std140;
+pub mod uniform_buffer;
+pub mod sampler;
+pub mod cubemap;
+pub mod skybox;
+pub mod texture_array;
+pub mod texture_3d;
+pub mod streaming;
+pub mod framebuffer;
+pub mod bmfont;
+pub mod text;
+pub mod rich_text;
+pub mod sprite_batch;
+pub mod atlas;
+pub mod nine_slice;
+pub mod sdf;
+pub mod truetype;
 
 use std::mem;
 use std::collections::HashMap;
+use image::{self, pnm, Image, PixelData, PixelFormat};
+use self::sampler::SamplerDesc;
+use self::bmfont::{FontDescription, Glyph};
 
 /// Type of a Vertex Attribute, used by Mesh to know which parts of the Vertex vectors hold the
 /// position, normal, UV, etc.
@@ -233,15 +256,28 @@ impl VertexBufferObject {
         }
     }
 
+    /// Draws the buffer as patches for tessellation shaders, each made of the given number of vertices.
+    pub fn draw_patches(&self, vertices_per_patch: i32) {
+        unsafe {
+            gl::PatchParameteri(gl::PATCH_VERTICES, vertices_per_patch);
+        }
+        self.draw_as(gl::PATCHES);
+    }
+
     pub fn draw(&self) {
+        self.draw_as(gl::TRIANGLES);
+    }
+
+    /// Draws the buffer with the given primitive mode, such as `gl::POINTS` for particles.
+    pub fn draw_as(&self, mode: gl::types::GLenum) {
         unsafe {
             if self.element_array_buffer_id.is_some() {
 
                 use std::ptr;
 
-                gl::DrawElements(gl::TRIANGLES, self.element_array_buffer_size, gl::UNSIGNED_SHORT, ptr::null());
+                gl::DrawElements(mode, self.element_array_buffer_size, gl::UNSIGNED_SHORT, ptr::null());
             } else {
-                gl::DrawArrays(gl::TRIANGLES, 0, self.vertex_count);
+                gl::DrawArrays(mode, 0, self.vertex_count);
             }
         }
     }
@@ -258,37 +294,236 @@ pub fn error() {
     }
 }
 
+/// Reads a rectangle of the framebuffer bound for reading, usually the default framebuffer of
+/// the window, into an Rgb or Rgba Image. x and y are the bottom left corner in window
+/// coordinates like for `glViewport`, but the rows of the result are flipped to the usual image
+/// order with the top row first, ready to be saved with `image::save`.
+pub fn read_pixels(x: i32, y: i32, width: u32, height: u32, format: PixelFormat) -> Result<Image, String> {
+    let gl_format = match format {
+        PixelFormat::Rgb  => gl::RGB,
+        PixelFormat::Rgba => gl::RGBA,
+        _                 => return Err(format!("pixels can only be read as Rgb or Rgba, not as {:?}", format)),
+    };
+
+    let mut pixels = vec![0u8; width as usize * height as usize * format.channels()];
+    unsafe {
+        gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+        gl::ReadPixels(x, y, width as i32, height as i32, gl_format, gl::UNSIGNED_BYTE, pixels.as_mut_ptr() as *mut _);
+        gl::PixelStorei(gl::PACK_ALIGNMENT, 4);
+    }
+
+    let mut image = Image::new(width, height, format, PixelData::U8(pixels))?;
+    image.flip_vertical();
+    Ok(image)
+}
+
+/// Saves the lower left `width` x `height` pixels of the framebuffer bound for reading, e.g. the
+/// whole window, to a PNG or PPM file, see `image::save`.
+pub fn save_screenshot(filename: &str, width: u32, height: u32) -> Result<(), String> {
+    let image = read_pixels(0, 0, width, height, PixelFormat::Rgb)?;
+    image::save(&image, filename)
+}
+
+/// Internal format of a Texture, i.e. how OpenGL stores the texels, along with the layout of the
+/// pixel data that is uploaded for it.
+///
+/// The 8 bit formats take one byte per channel. The floating point formats are uploaded as 32 bit
+/// floats, even if they are stored with 16 bits. Depth formats are uploaded as unsigned shorts
+/// (Depth16), unsigned ints (Depth24), floats (Depth32f) or packed with the stencil index, but
+/// usually they are not uploaded at all and only serve as render targets.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TextureFormat {
+    R8,
+    Rg8,
+    Rgb8,
+    Rgba8,
+    Srgb8Alpha8,
+    R16f,
+    Rgba16f,
+    Rgba32f,
+    Depth16,
+    Depth24,
+    Depth32f,
+    Depth24Stencil8,
+    Depth32fStencil8,
+}
+
+impl TextureFormat {
+
+    /// The sized internal format passed to `glTexImage2D`.
+    pub fn internal_format(self) -> gl::types::GLenum {
+        match self {
+            TextureFormat::R8               => gl::R8,
+            TextureFormat::Rg8              => gl::RG8,
+            TextureFormat::Rgb8             => gl::RGB8,
+            TextureFormat::Rgba8            => gl::RGBA8,
+            TextureFormat::Srgb8Alpha8      => gl::SRGB8_ALPHA8,
+            TextureFormat::R16f             => gl::R16F,
+            TextureFormat::Rgba16f          => gl::RGBA16F,
+            TextureFormat::Rgba32f          => gl::RGBA32F,
+            TextureFormat::Depth16          => gl::DEPTH_COMPONENT16,
+            TextureFormat::Depth24          => gl::DEPTH_COMPONENT24,
+            TextureFormat::Depth32f         => gl::DEPTH_COMPONENT32F,
+            TextureFormat::Depth24Stencil8  => gl::DEPTH24_STENCIL8,
+            TextureFormat::Depth32fStencil8 => gl::DEPTH32F_STENCIL8,
+        }
+    }
+
+    /// The format of the uploaded pixel data, e.g. `gl::RGBA`.
+    pub fn pixel_format(self) -> gl::types::GLenum {
+        match self {
+            TextureFormat::R8 | TextureFormat::R16f => gl::RED,
+            TextureFormat::Rg8                      => gl::RG,
+            TextureFormat::Rgb8                     => gl::RGB,
+            TextureFormat::Rgba8 | TextureFormat::Srgb8Alpha8 |
+            TextureFormat::Rgba16f | TextureFormat::Rgba32f => gl::RGBA,
+            TextureFormat::Depth16 | TextureFormat::Depth24 | TextureFormat::Depth32f => gl::DEPTH_COMPONENT,
+            TextureFormat::Depth24Stencil8 | TextureFormat::Depth32fStencil8 => gl::DEPTH_STENCIL,
+        }
+    }
+
+    /// The type of the components of the uploaded pixel data, e.g. `gl::UNSIGNED_BYTE`.
+    pub fn pixel_type(self) -> gl::types::GLenum {
+        match self {
+            TextureFormat::R8 | TextureFormat::Rg8 | TextureFormat::Rgb8 |
+            TextureFormat::Rgba8 | TextureFormat::Srgb8Alpha8 => gl::UNSIGNED_BYTE,
+            TextureFormat::R16f | TextureFormat::Rgba16f |
+            TextureFormat::Rgba32f | TextureFormat::Depth32f => gl::FLOAT,
+            TextureFormat::Depth16          => gl::UNSIGNED_SHORT,
+            TextureFormat::Depth24          => gl::UNSIGNED_INT,
+            TextureFormat::Depth24Stencil8  => gl::UNSIGNED_INT_24_8,
+            TextureFormat::Depth32fStencil8 => gl::FLOAT_32_UNSIGNED_INT_24_8_REV,
+        }
+    }
+
+    /// Number of channels of the uploaded pixel data.
+    pub fn channels(self) -> usize {
+        match self {
+            TextureFormat::R8 | TextureFormat::R16f => 1,
+            TextureFormat::Rg8                      => 2,
+            TextureFormat::Rgb8                     => 3,
+            TextureFormat::Rgba8 | TextureFormat::Srgb8Alpha8 |
+            TextureFormat::Rgba16f | TextureFormat::Rgba32f => 4,
+            TextureFormat::Depth16 | TextureFormat::Depth24 | TextureFormat::Depth32f => 1,
+            TextureFormat::Depth24Stencil8 | TextureFormat::Depth32fStencil8 => 2,
+        }
+    }
+
+    /// Size of one pixel of the uploaded data in bytes.
+    pub fn bytes_per_pixel(self) -> usize {
+        match self.pixel_type() {
+            gl::UNSIGNED_BYTE  => self.channels(),
+            gl::UNSIGNED_SHORT => 2,
+            gl::FLOAT          => 4 * self.channels(),
+            gl::FLOAT_32_UNSIGNED_INT_24_8_REV => 8,
+            _                  => 4,
+        }
+    }
+
+    /// Size of one row of the uploaded data in bytes, without any padding.
+    pub fn row_bytes(self, width: u32) -> usize {
+        width as usize * self.bytes_per_pixel()
+    }
+
+    /// The largest `UNPACK_ALIGNMENT` (or `PACK_ALIGNMENT`) that tightly packed rows of the given
+    /// width satisfy. OpenGL assumes 4 by default, which breaks e.g. RGB8 textures with an odd width.
+    pub fn row_alignment(self, width: u32) -> i32 {
+        let row_bytes = self.row_bytes(width);
+        [8, 4, 2].iter().cloned().find(|alignment| row_bytes.is_multiple_of(*alignment)).unwrap_or(1) as i32
+    }
+
+    pub fn is_depth(self) -> bool {
+        self.pixel_format() == gl::DEPTH_COMPONENT || self.has_stencil()
+    }
+
+    pub fn has_stencil(self) -> bool {
+        self.pixel_format() == gl::DEPTH_STENCIL
+    }
+
+    pub fn is_float(self) -> bool {
+        self.pixel_type() == gl::FLOAT && !self.is_depth()
+    }
+
+    /// The 8 bit format matching the channels of an Image.
+    pub fn from_pixel_format(format: PixelFormat) -> TextureFormat {
+        match format {
+            PixelFormat::Gray      => TextureFormat::R8,
+            PixelFormat::GrayAlpha => TextureFormat::Rg8,
+            PixelFormat::Rgb       => TextureFormat::Rgb8,
+            PixelFormat::Rgba      => TextureFormat::Rgba8,
+        }
+    }
+}
+
 /// Texture 2d wrapper
 #[allow(dead_code)]
 pub struct Texture {
     id     : gl::types::GLuint,
     width  : u32,
     height : u32,
+    format : TextureFormat,
 }
 
 #[allow(dead_code)]
 impl Texture {
+    /// Creates an RGB8 texture from tightly packed RGB bytes.
     pub fn new(width:u32, height:u32, rgb_raster: Vec<u8>) -> Texture {
+        match Texture::with_format(width, height, TextureFormat::Rgb8, Some(&rgb_raster)) {
+            Ok(texture) => texture,
+            Err(why)    => panic!("{}", why),
+        }
+    }
+
+    /// Creates a texture with the given internal format. The pixels are tightly packed rows laid
+    /// out as described by the format, starting with the bottom row. Without pixels the texture
+    /// memory is only allocated, e.g. to render into it.
+    pub fn with_format(width: u32, height: u32, format: TextureFormat, pixels: Option<&[u8]>) -> Result<Texture, String> {
+        if width == 0 || height == 0 {
+            return Err(format!("invalid texture size {} x {}", width, height));
+        }
+
+        let size = format.row_bytes(width) * height as usize;
+        if let Some(pixels) = pixels {
+            if pixels.len() != size {
+                return Err(format!("a {} x {} texture with format {:?} needs {} bytes of pixel data, but got {}",
+                                   width, height, format, size, pixels.len()));
+            }
+        }
+
         unsafe {
-            let mut vb = mem::uninitialized();
+            let mut vb = 0;
             gl::GenTextures(1, &mut vb);
             gl::BindTexture(gl::TEXTURE_2D, vb);
-            gl::TexImage2D(gl::TEXTURE_2D, 0, gl::RGB as i32, width as i32, height as i32, 0, gl::RGB, gl::UNSIGNED_BYTE, rgb_raster.as_ptr() as *const _);
-            //glTexImage2D(GL_TEXTURE_2D, 0, GL_RGBA, width, height, 0, GL_RGBA, GL_UNSIGNED_BYTE, buf);
 
-            Texture::parameters(gl::LINEAR, gl::LINEAR, gl::CLAMP_TO_EDGE, gl::CLAMP_TO_EDGE);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, format.row_alignment(width));
+            gl::TexImage2D(gl::TEXTURE_2D, 0, format.internal_format() as i32, width as i32, height as i32, 0,
+                           format.pixel_format(), format.pixel_type(),
+                           pixels.map_or(std::ptr::null(), |p| p.as_ptr()) as *const _);
+            // restore the default, other code might rely on it
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+
+            SamplerDesc::default().apply(gl::TEXTURE_2D);
 
             // unbind texture
             gl::BindTexture(gl::TEXTURE_2D, 0);
 
-            Texture {
+            Ok(Texture {
                 id    : vb,
-                width : width,
-                height: height,
-            }
+                width,
+                height,
+                format,
+            })
         }
     }
 
+    pub fn id(&self) -> gl::types::GLuint {
+        self.id
+    }
+
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
     pub fn width(&self) -> u32 {
         self.width
     }
@@ -305,6 +540,119 @@ impl Texture {
         self.height as f32
     }
 
+    /// Replaces a rectangle of the texture, e.g. a painted area or a new glyph, with tightly
+    /// packed pixels laid out as described by the format of the texture. x and y count from the
+    /// bottom left like the texture coordinates.
+    pub fn update_region(&self, x: u32, y: u32, width: u32, height: u32, pixels: &[u8]) -> Result<(), String> {
+        self.check_region(x, y, width, height, pixels.len())?;
+
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, self.format.row_alignment(width));
+            gl::TexSubImage2D(gl::TEXTURE_2D, 0, x as i32, y as i32, width as i32, height as i32,
+                              self.format.pixel_format(), self.format.pixel_type(), pixels.as_ptr() as *const _);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the rectangle lies within the texture and that `length` bytes fill it exactly.
+    fn check_region(&self, x: u32, y: u32, width: u32, height: u32, length: usize) -> Result<(), String> {
+        if x as u64 + width as u64 > self.width as u64 || y as u64 + height as u64 > self.height as u64 {
+            return Err(format!("region of {} x {} at {}, {} exceeds the {} x {} texture",
+                               width, height, x, y, self.width, self.height));
+        }
+
+        let size = self.format.row_bytes(width) * height as usize;
+        if length != size {
+            return Err(format!("a {} x {} region with format {:?} needs {} bytes of pixel data, but got {}",
+                               width, height, self.format, size, length));
+        }
+
+        Ok(())
+    }
+
+    /// Reads the base level of the texture back into main memory, tightly packed and laid out
+    /// as described by the format, starting with the bottom row.
+    pub fn read_texels(&self) -> Vec<u8> {
+        let mut texels = vec![0u8; self.format.row_bytes(self.width) * self.height as usize];
+
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
+            gl::PixelStorei(gl::PACK_ALIGNMENT, self.format.row_alignment(self.width));
+            gl::GetTexImage(gl::TEXTURE_2D, 0, self.format.pixel_format(), self.format.pixel_type(),
+                            texels.as_mut_ptr() as *mut _);
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 4);
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+
+        texels
+    }
+
+    /// Sets how this texture is sampled, unless a Sampler is bound to the texture unit. Mipmap
+    /// filters need mipmaps, see `generate_mipmaps` and `upload_mipmaps`.
+    pub fn set_sampler(&self, desc: &SamplerDesc) {
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
+            desc.apply(gl::TEXTURE_2D);
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+    }
+
+    /// Lets the driver compute all mipmap levels from the base level.
+    pub fn generate_mipmaps(&self) {
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
+            gl::GenerateMipmap(gl::TEXTURE_2D);
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+    }
+
+    /// Uploads mipmap levels computed on the CPU, e.g. by `Image::mip_chain`, as levels 1, 2, and so
+    /// on. The levels have to match the size and channels of the texture.
+    pub fn upload_mipmaps(&self, levels: &[Image]) -> Result<(), String> {
+        let mut uploads = Vec::with_capacity(levels.len());
+
+        for (i, level) in levels.iter().enumerate() {
+            let width  = (self.width >> (i + 1)).max(1);
+            let height = (self.height >> (i + 1)).max(1);
+            if (level.width(), level.height()) != (width, height) {
+                return Err(format!("mipmap level {} has to be {} x {}, but is {} x {}",
+                                   i + 1, width, height, level.width(), level.height()));
+            }
+
+            let (format, pixels) = texture_pixels(level);
+            if format.pixel_format() != self.format.pixel_format() || format.pixel_type() != self.format.pixel_type() {
+                return Err(format!("mipmap level {} in {:?} does not match the texture format {:?}",
+                                   i + 1, format, self.format));
+            }
+            uploads.push(pixels);
+        }
+
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
+
+            for (i, pixels) in uploads.iter().enumerate() {
+                let width  = (self.width >> (i + 1)).max(1);
+                let height = (self.height >> (i + 1)).max(1);
+
+                gl::PixelStorei(gl::UNPACK_ALIGNMENT, self.format.row_alignment(width));
+                gl::TexImage2D(gl::TEXTURE_2D, (i + 1) as i32, self.format.internal_format() as i32,
+                               width as i32, height as i32, 0,
+                               self.format.pixel_format(), self.format.pixel_type(), pixels.as_ptr() as *const _);
+            }
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAX_LEVEL, levels.len() as i32);
+
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+
+        Ok(())
+    }
+
+    /// Sets filters and wrap modes of the currently bound texture, see `set_sampler` for more options.
     pub fn parameters(mag_filter: gl::types::GLenum,
                       min_filter: gl::types::GLenum,
                       wrap_s    : gl::types::GLenum,
@@ -330,113 +678,170 @@ impl Texture {
         }
     }
 
-    fn parse_ascii_line(buffer : &Vec<u8>, index: usize) -> (String, usize) {
-        let mut i = index;
-        let mut string = String::new();
+    /// Uploads an Image. The first row of the image ends up at the bottom of the texture, so
+    /// images with the top row first, as decoded from files, need to be flipped before.
+    ///
+    /// 8 and 16 bit images become R8, RG8, RGB8 or RGBA8 textures, floating point images RGBA32F.
+    /// Gray images are swizzled such that they are sampled as gray instead of red.
+    pub fn from_image(image: &Image) -> Texture {
+        let (format, pixels) = texture_pixels(image);
+        let result = Texture::with_format(image.width(), image.height(), format, Some(&pixels));
+
+        let texture = match result {
+            Ok(texture) => texture,
+            Err(why)    => panic!("{}", why),
+        };
 
-        while buffer[i] != 10 {
-            string.push( buffer[i] as char );
-            i += 1;
+        let swizzle = match texture.format {
+            TextureFormat::R8  => Some([gl::RED, gl::RED, gl::RED, gl::ONE]),
+            TextureFormat::Rg8 => Some([gl::RED, gl::RED, gl::RED, gl::GREEN]),
+            _                  => None,
+        };
+        if let Some(swizzle) = swizzle {
+            let swizzle = swizzle.map(|s| s as gl::types::GLint);
+            unsafe {
+                gl::BindTexture(gl::TEXTURE_2D, texture.id);
+                gl::TexParameteriv(gl::TEXTURE_2D, gl::TEXTURE_SWIZZLE_RGBA, swizzle.as_ptr());
+                gl::BindTexture(gl::TEXTURE_2D, 0);
+            }
         }
 
-        (string, i)
+        texture
     }
 
-    pub fn from_pnm_file(filename: &str) -> Texture {
-
-        use std::error::Error;
-        use std::fs::File;
-        use std::io::BufReader;
-        use std::io::prelude::*;
-        use std::path::Path;
-
-        // Create a path to the desired file
-        let path = Path::new(filename);
-        let display = path.display();
-
-        // Open the path in read-only mode, returns `io::Result<File>`
-        let file = match File::open(&path) {
-            // The `description` method of `io::Error` returns a string that
-            // describes the error
-            Err(why) => panic!("couldn't open {}: {}", display,
-                               why.description()),
-            Ok(file) => file,
-        };
-
-        let mut buf_reader = BufReader::new(file);
-        let mut buffer     = Vec::new();
-        let result         = buf_reader.read_to_end(&mut buffer);
-
-        if result.is_err() {
-            panic!("could not read file {}, because of {}", filename, result.err().unwrap());
-        }
+    /// Loads a texture from a PNG, BMP, TGA or Netpbm file, detected by the magic bytes of the file.
+    pub fn from_file(filename: &str) -> Result<Texture, String> {
+        let mut image = image::load(filename)?;
 
-        let (magic_number_as_string, i) = Texture::parse_ascii_line(&buffer, 0);
-        let mut index = i + 1; // jump over carriage return
+        // textures have their origin at the bottom left, so we have to flip the y axis of the image
+        image.flip_vertical();
 
-        assert_eq!(magic_number_as_string, "P6", "magic number in file {} is expected to be P6, but appears to be {}", filename, magic_number_as_string);
-
-        // skip commentary line, ASCII
-        let (_, i) = Texture::parse_ascii_line(&buffer, index);
-        index = i + 1;
+        Ok(Texture::from_image(&image))
+    }
 
-        // read size as ASCII
-        let (size_string, i) = Texture::parse_ascii_line(&buffer, index);
-        index = i + 1;
+    /// Loads a texture from any of the Netpbm formats (PBM, PGM, PPM or PAM).
+    pub fn from_pnm_file(filename: &str) -> Texture {
+        let mut image = match pnm::load_image(filename) {
+            Ok(image) => image,
+            Err(why)  => panic!("{}", why),
+        };
 
-        // the size string comes in the format "256 256" and this needs to be split in to
-        // in order to parse it into width and height
-        let split_vec : Vec<&str> = size_string.split(" ").collect();
+        // textures have their origin at the bottom left, so we have to flip the y axis of the image
+        image.flip_vertical();
 
-        // width and height of the image to be loaded in pixels
-        let width  = split_vec[0].parse::<usize>().unwrap();
-        let height = split_vec[1].parse::<usize>().unwrap();
+        Texture::from_image(&image)
+    }
+}
 
-        let (max_value_string, i) = Texture::parse_ascii_line(&buffer, index);
-        index = i + 1;
+/// The pixels of an Image laid out for upload, along with the matching texture format: 8 bit
+/// formats for 8 and 16 bit images and RGBA32F for floating point images.
+fn texture_pixels(image: &Image) -> (TextureFormat, Vec<u8>) {
+    match *image.data() {
+        PixelData::F32(_) => match image.convert(PixelFormat::Rgba).into_data() {
+            PixelData::F32(data) => (TextureFormat::Rgba32f, data.iter().flat_map(|f| f.to_ne_bytes()).collect()),
+            _                    => unreachable!("Image::convert keeps the component type"),
+        },
+        _ => match image.to_u8().into_data() {
+            PixelData::U8(data) => (TextureFormat::from_pixel_format(image.format()), data),
+            _                   => unreachable!("Image::to_u8 returns 8 bit data"),
+        },
+    }
+}
 
-        assert_eq!(max_value_string, "255", "while parsing the PNM file, I expect the 'max value' to be 255, but it turned out to be {}", max_value_string);
+/// Concatenates the pixels of equally sized images, such that each image becomes one layer of a
+/// TextureArray or one slice of a Texture3D. Returns width, height, format and the pixels.
+fn layer_pixels(images: &[Image]) -> Result<(u32, u32, TextureFormat, Vec<u8>), String> {
+    let first = match images.first() {
+        Some(first) => first,
+        None        => return Err("at least one layer is needed".to_string()),
+    };
+
+    let (width, height) = (first.width(), first.height());
+    let format = texture_pixels(first).0;
+    let mut pixels = Vec::with_capacity(format.row_bytes(width) * height as usize * images.len());
+
+    for (layer, image) in images.iter().enumerate() {
+        if image.width() != width || image.height() != height {
+            return Err(format!("layer {} is {} x {}, but all layers have to be {} x {}",
+                               layer, image.width(), image.height(), width, height));
+        }
 
-        let size_in_bytes = width * height * 3;
+        let (layer_format, layer_pixels) = texture_pixels(image);
+        if layer_format != format {
+            return Err(format!("layer {} is {:?}, but the first layer is {:?}", layer, layer_format, format));
+        }
+        pixels.extend_from_slice(&layer_pixels);
+    }
 
-        let mut rgb_image : Vec<u8> = Vec::with_capacity( size_in_bytes );
+    Ok((width, height, format, pixels))
+}
 
-        unsafe { rgb_image.set_len(size_in_bytes); }
+/// Creates a texture for `gl::TEXTURE_2D_ARRAY` or `gl::TEXTURE_3D` with the given number of
+/// layers, optionally filled with the tightly packed pixels of all layers.
+fn create_layered_texture(target: gl::types::GLenum, width: u32, height: u32, depth: u32,
+                          format: TextureFormat, pixels: Option<&[u8]>) -> Result<gl::types::GLuint, String> {
+    if width == 0 || height == 0 || depth == 0 {
+        return Err(format!("invalid texture size {} x {} x {}", width, height, depth));
+    }
 
-        // tracks x and y of source image while reading
-        let mut source_x : usize = 0;
-        let mut source_y : usize = 0;
+    let size = format.row_bytes(width) * height as usize * depth as usize;
+    if let Some(pixels) = pixels {
+        if pixels.len() != size {
+            return Err(format!("a {} x {} x {} texture with format {:?} needs {} bytes of pixel data, but got {}",
+                               width, height, depth, format, size, pixels.len()));
+        }
+    }
 
-        // the index in the target buffer where to write, includes the vertical flip
-        //let mut write_index : usize = 0;
+    let mut id = 0;
+    unsafe {
+        gl::GenTextures(1, &mut id);
+        gl::BindTexture(target, id);
 
-        while index < buffer.len() {
+        gl::PixelStorei(gl::UNPACK_ALIGNMENT, format.row_alignment(width));
+        gl::TexImage3D(target, 0, format.internal_format() as i32, width as i32, height as i32, depth as i32, 0,
+                       format.pixel_format(), format.pixel_type(),
+                       pixels.map_or(std::ptr::null(), |p| p.as_ptr()) as *const _);
+        gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
 
-            //println!("writing buffer, width: {}, height: {}, source_x: {}, source_y: {}, index: {}, size_in_bytes: {}", width, height, source_x, source_y, index, size_in_bytes);
+        SamplerDesc::default().apply(target);
 
-            // we have to flip the y axis of the image
-            rgb_image[ width*(height-source_y-1)*3 + source_x*3 + 0] = buffer[index + 0];
-            rgb_image[ width*(height-source_y-1)*3 + source_x*3 + 1] = buffer[index + 1];
-            rgb_image[ width*(height-source_y-1)*3 + source_x*3 + 2] = buffer[index + 2];
-            index += 3;
+        gl::BindTexture(target, 0);
+    }
 
-            source_x += 1;
+    Ok(id)
+}
 
-            if source_x >= width {
-                source_x  = 0;
-                source_y += 1;
-            }
+/// Replaces one layer of a texture created by `create_layered_texture`.
+fn upload_layer(target: gl::types::GLenum, id: gl::types::GLuint, size: (u32, u32, u32),
+                format: TextureFormat, layer: u32, pixels: &[u8]) -> Result<(), String> {
+    let (width, height, depth) = size;
+    if layer >= depth {
+        return Err(format!("layer {} is out of range, the texture has {} layers", layer, depth));
+    }
 
-        }
+    let layer_size = format.row_bytes(width) * height as usize;
+    if pixels.len() != layer_size {
+        return Err(format!("a {} x {} layer with format {:?} needs {} bytes of pixel data, but got {}",
+                           width, height, format, layer_size, pixels.len()));
+    }
 
-        Texture::new(width as u32, height as u32, rgb_image)
+    unsafe {
+        gl::BindTexture(target, id);
+        gl::PixelStorei(gl::UNPACK_ALIGNMENT, format.row_alignment(width));
+        gl::TexSubImage3D(target, 0, 0, 0, layer as i32, width as i32, height as i32, 1,
+                          format.pixel_format(), format.pixel_type(), pixels.as_ptr() as *const _);
+        gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+        gl::BindTexture(target, 0);
     }
+
+    Ok(())
 }
 
 /// A Sprite is a sub-area on a Texture. Primarily used for Font Bitmaps and GUI-elements.
-/// Textures have their origin at the bottom left, such that Sprites are positioned accordingly.
+/// Sprites are positioned in pixels of the image with y counted from the top, as in BMFont files,
+/// while Textures have their origin at the bottom left, such that uvs flip y accordingly.
 #[allow(dead_code)]
-#[derive(Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct Sprite {
 
     x      : u32,
@@ -493,153 +898,226 @@ impl Sprite {
     }
 }
 
+/// A bitmap font made of a BMFont description and one texture per page.
 #[allow(dead_code)]
 pub struct Font {
-    tex : Texture,
-    map : HashMap<char, Sprite>,
+    pages       : Vec<Texture>,
+    description : FontDescription,
+    fallback    : Option<u32>,
 }
 
 #[allow(dead_code)]
 impl Font {
 
+    /// World units per pixel of the font.
+    const SCALE : f32 = 0.005;
+
+    /// Creates a single page font from the given texture and BMFont descriptor file. Panics if
+    /// the descriptor can not be loaded.
     pub fn new(tex: Texture, fnt_file: &str) -> Font {
-        Font {
-            tex : tex,
-            map : Font::load_fnt_file(fnt_file),
+        let description = FontDescription::load(fnt_file).unwrap_or_else(|why| panic!("{}", why));
+        Font::from_description(description, vec![tex]).unwrap_or_else(|why| panic!("{}", why))
+    }
+
+    /// Creates a font from a description and the textures of its pages, in page order.
+    pub fn from_description(description: FontDescription, pages: Vec<Texture>) -> Result<Font, String> {
+        if pages.is_empty() {
+            return Err("a font needs at least one page".to_string());
+        }
+
+        if let Some(glyph) = description.glyphs().values().find(|glyph| glyph.page as usize >= pages.len()) {
+            return Err(format!("glyph {} is on page {} but the font has only {} pages", glyph.id, glyph.page, pages.len()));
+        }
+
+        // BMFont exports a box for missing characters as glyph 0
+        let fallback = description.glyph(0).map(|glyph| glyph.id);
+
+        Ok(Font {
+            pages,
+            description,
+            fallback,
+        })
+    }
+
+    /// Loads a BMFont descriptor file along with its pages, which are looked up relative to the
+    /// descriptor.
+    pub fn load(fnt_file: &str) -> Result<Font, String> {
+        use std::path::Path;
+
+        let description = FontDescription::load(fnt_file)?;
+        let directory = Path::new(fnt_file).parent().unwrap_or_else(|| Path::new(""));
+
+        let mut pages = Vec::new();
+        for page in description.pages() {
+            let path = directory.join(page);
+            pages.push(Texture::from_file(&path.to_string_lossy())?);
         }
+
+        Font::from_description(description, pages)
     }
 
+    /// Builds a mesh of the text with one textured quad per glyph, on all pages. The text starts
+    /// at the origin and goes down line by line, the baseline of the last line rests at y = 0.
+    /// Characters the font does not have are drawn with the fallback glyph.
     pub fn mesh(&self, text:&str) -> mesh::Mesh {
-        let mut x : f32 = 0.0;
-        let mut y : f32 = 0.0;
-        let scale : f32 = 0.005;
-        let mut vertex_count = 0;
+        let mut mesh = Font::empty_mesh();
 
-        let array = AttribArrayBuilder::new()
-            .push("position", 3, AttribType::Position)
-            .push("uv", 2, AttribType::Uv)
-            .build();
+        self.layout(text, |_, positions, uvs| Font::push_quad(&mut mesh, positions, uvs));
+
+        mesh.translate(0.0, -self.last_baseline(text), 0.0);
+
+        mesh
+    }
 
-        let mut mesh = mesh::Mesh::empty(array);
+    /// Builds one mesh per page like `mesh`, such that each can be drawn with its page texture.
+    pub fn page_meshes(&self, text: &str) -> Vec<mesh::Mesh> {
+        let mut meshes : Vec<mesh::Mesh> = self.pages.iter().map(|_| Font::empty_mesh()).collect();
+
+        self.layout(text, |page, positions, uvs| Font::push_quad(&mut meshes[page], positions, uvs));
+
+        let baseline = self.last_baseline(text);
+        for mesh in &mut meshes {
+            mesh.translate(0.0, -baseline, 0.0);
+        }
+
+        meshes
+    }
+
+    /// Places the glyphs of the text, calling `quad` with the page, the corner positions and
+    /// the corner uvs of each visible glyph.
+    fn layout<F>(&self, text: &str, mut quad: F) where F: FnMut(usize, [f32; 4], [f32; 4]) {
+        let scale       = Font::SCALE;
+        let common      = self.description.common();
+        let line_height = common.line_height as f32 * scale;
+
+        let mut x : f32 = 0.0;
+        let mut baseline : f32 = 0.0;
+        let mut previous : Option<u32> = None;
 
         for character in text.chars() {
 
-            if character == ' ' {
-                let sprite = self.map.get(&'a').unwrap();
-                x += sprite.width_as_f32() * scale;
-                continue;
-            } else if character == '\n' {
-                let sprite = self.map.get(&'a').unwrap();
-                y -= sprite.height_as_f32() * scale;
+            if character == '\n' {
                 x = 0.0;
+                baseline -= line_height;
+                previous = None;
                 continue;
             }
 
-            let sprite = self.map.get(&character).unwrap();
+            let glyph = match self.glyph(character) {
+                Some(glyph) => glyph,
+                None        => continue,
+            };
+            let id = glyph.id;
+
+            if let Some(previous) = previous {
+                x += self.description.kerning(previous, id) as f32 * scale;
+            }
+            previous = Some(id);
+
+            if glyph.width > 0 && glyph.height > 0 {
+                let left = x + glyph.xoffset as f32 * scale;
+                let top  = baseline + (common.base as f32 - glyph.yoffset as f32) * scale;
+
+                quad(glyph.page as usize,
+                     [left, top - glyph.height as f32 * scale, left + glyph.width as f32 * scale, top],
+                     self.uvs(glyph));
+            }
+
+            x += glyph.xadvance as f32 * scale;
+        }
+    }
 
-            let uv_x = sprite.x_as_f32() / self.tex.width_as_f32();
-            let uv_y = (self.tex.height()-sprite.y()-sprite.height()) as f32 / self.tex.height_as_f32();
+    /// The uvs of the glyph on its page as left, bottom, right and top.
+    fn uvs(&self, glyph: &Glyph) -> [f32; 4] {
+        let tex = &self.pages[glyph.page as usize];
 
-            let uv_width  = sprite.width_as_f32()  / self.tex.width_as_f32();
-            let uv_height = sprite.height_as_f32() / self.tex.height_as_f32();
+        let uv_x = glyph.x as f32 / tex.width_as_f32();
+        let uv_y = tex.height() as f32 - glyph.y as f32 - glyph.height as f32;
+        let uv_y = uv_y / tex.height_as_f32();
 
-            let height = sprite.height_as_f32() * scale;
-            let width  = sprite.width_as_f32()  * scale;
+        let uv_width  = glyph.width  as f32 / tex.width_as_f32();
+        let uv_height = glyph.height as f32 / tex.height_as_f32();
 
-          //  println!("{}, {}, {}", x, uv_x, uv_y);
+        [uv_x, uv_y, uv_x + uv_width, uv_y + uv_height]
+    }
 
-            mesh.push_vertices( vec![
-                vec![x+0.0,   y+0.0,    0.0,  uv_x,            uv_y],
-                vec![x+width, y+0.0,    0.0,  uv_x + uv_width, uv_y],
-                vec![x+width, y+height, 0.0,  uv_x + uv_width, uv_y + uv_height],
-                vec![x+0.0,   y+height, 0.0,  uv_x,            uv_y + uv_height],
-            ]);
+    fn last_baseline(&self, text: &str) -> f32 {
+        let lines = text.matches('\n').count() as f32;
+        -lines * self.description.common().line_height as f32 * Font::SCALE
+    }
 
-            x += width;
-            vertex_count += 4;
+    fn empty_mesh() -> mesh::Mesh {
+        let array = AttribArrayBuilder::new()
+            .push("position", 3, AttribType::Position)
+            .push("uv", 2, AttribType::Uv)
+            .build();
 
-            mesh.push_faces( vec![
-                mesh::Face::new(vertex_count - 4, vertex_count - 3, vertex_count - 2),
-                mesh::Face::new(vertex_count - 2, vertex_count - 1, vertex_count - 4),
-            ]);
+        mesh::Mesh::empty(array)
+    }
 
-        }
+    /// Pushes a quad given as left, bottom, right and top, for both positions and uvs.
+    fn push_quad(mesh: &mut mesh::Mesh, positions: [f32; 4], uvs: [f32; 4]) {
+        let [left, bottom, right, top] = positions;
+        let [uv_left, uv_bottom, uv_right, uv_top] = uvs;
 
-        mesh.translate(0.0, -y, 0.0);
+        let vertex_count = mesh.vertex_count();
 
-        mesh
+        mesh.push_vertices( vec![
+            vec![left,  bottom, 0.0,  uv_left,  uv_bottom],
+            vec![right, bottom, 0.0,  uv_right, uv_bottom],
+            vec![right, top,    0.0,  uv_right, uv_top],
+            vec![left,  top,    0.0,  uv_left,  uv_top],
+        ]);
+
+        mesh.push_faces( vec![
+            mesh::Face::new(vertex_count,     vertex_count + 1, vertex_count + 2),
+            mesh::Face::new(vertex_count + 2, vertex_count + 3, vertex_count),
+        ]);
     }
 
-    pub fn load_fnt_file(filename: &str) -> HashMap<char, Sprite>{
-        use std::error::Error;
-        use std::fs::File;
-        use std::io::prelude::*;
-        use std::path::Path;
+    /// Loads the sprites of all visible glyphs of a BMFont descriptor file. Panics if the file
+    /// can not be loaded.
+    pub fn load_fnt_file(filename: &str) -> HashMap<char, Sprite> {
+        let description = FontDescription::load(filename).unwrap_or_else(|why| panic!("{}", why));
 
-        // Create a path to the desired file
-        let path    = Path::new(filename);
-        let display = path.display();
+        description.glyphs().values()
+            .filter(|glyph| glyph.width > 0)
+            .filter_map(|glyph| ::std::char::from_u32(glyph.id).map(|character| (character, glyph.sprite())))
+            .collect()
+    }
 
-        // Open the path in read-only mode, returns `io::Result<File>`
-        let mut file = match File::open(&path) {
-            // The `description` method of `io::Error` returns a string that
-            // describes the error
-            Err(why) => panic!("couldn't open {}: {}", display, why.description()),
-            Ok(file) => file,
-        };
+    /// Sets the glyph drawn for characters the font does not have, `None` skips them. Defaults
+    /// to glyph 0 if the font has one.
+    pub fn with_fallback(mut self, fallback: Option<u32>) -> Font {
+        self.fallback = fallback;
+        self
+    }
 
-        // Read the file contents into a string, returns `io::Result<usize>`
-        let mut content = String::new();
-        match file.read_to_string(&mut content) {
-            Err(why) => panic!("couldn't read {}: {}", display, why.description()),
-            Ok(_)    => (),//print!("{} contains:\n{}", display, content),
-        }
-
-        let mut map : HashMap<char, Sprite> = HashMap::new();
-
-        for line in content.lines() {
-
-            if line.starts_with("char ") {
-                let mut id     : u8 = 0;
-                let mut x      : u32 = 0;
-                let mut y      : u32 = 0;
-                let mut width  : u32 = 0;
-                let mut height : u32 = 0;
-
-                //println!("line! {}", line);
-                let sss : String = line.chars().skip("char ".len()).take(line.len()-"char ".len()).collect();
-                //println!("sub line [{}]", sss);
-
-                for s in sss.split_whitespace() {
-                    //println!("s as in as [{}]", s);
-                    let key_value_pair: Vec<&str> = s.split("=").collect();
-                    let key   = key_value_pair[0];
-                    let value = key_value_pair[1];
-                    //println!("grummel {} -> {}", key, value);
-                    match key {
-                        "id"     => id     = value.parse::<u8>().unwrap(),
-                        "x"      => x      = value.parse::<u32>().unwrap(),
-                        "y"      => y      = value.parse::<u32>().unwrap(),
-                        "width"  => width  = value.parse::<u32>().unwrap(),
-                        "height" => height = value.parse::<u32>().unwrap(),
-                        _        => {},
-                    }
-                }
-
-                if width == 0 {
-                    continue;
-                }
-
-                //println!("buiding sprite outt of {}, {}, {}, {}, {}", x, y, width, height, id as char);
-                let sprite = Sprite::new(x, y, width, height);
-                map.insert(id as char, sprite);
-            }
+    pub fn fallback(&self) -> Option<u32> {
+        self.fallback
+    }
+
+    /// The glyph drawn for the character, which is the fallback glyph for missing characters.
+    /// Carriage returns have no glyph.
+    pub fn glyph(&self, character: char) -> Option<&Glyph> {
+        if character == '\r' {
+            return None;
         }
 
-        map
+        self.description.glyph(character as u32)
+            .or_else(|| self.fallback.and_then(|fallback| self.description.glyph(fallback)))
     }
 
+    /// The texture of the first page.
     pub fn tex(&self) -> &Texture {
-        &self.tex
+        &self.pages[0]
+    }
+
+    pub fn pages(&self) -> &[Texture] {
+        &self.pages
+    }
+
+    pub fn description(&self) -> &FontDescription {
+        &self.description
     }
 }
@@ -0,0 +1,114 @@
+use super::mesh::Mesh;
+use super::{Font, Sprite};
+
+/// The widths of the borders of a NineSlice in pixels of its Sprite.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Insets {
+    pub left   : u32,
+    pub top    : u32,
+    pub right  : u32,
+    pub bottom : u32,
+}
+
+impl Insets {
+
+    pub fn new(left: u32, top: u32, right: u32, bottom: u32) -> Insets {
+        Insets { left, top, right, bottom }
+    }
+
+    /// Insets of the same width on all sides.
+    pub fn uniform(inset: u32) -> Insets {
+        Insets::new(inset, inset, inset, inset)
+    }
+}
+
+/// A Sprite split by its insets into corners, edges and center, to draw panels and buttons of
+/// any size from it. The corners keep their size, the edges stretch along the border and the
+/// center stretches in both directions.
+#[allow(dead_code)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct NineSlice {
+    sprite       : Sprite,
+    insets       : Insets,
+    border_scale : f32,
+}
+
+#[allow(dead_code)]
+impl NineSlice {
+
+    /// Fails if the insets overlap, i.e. are wider or taller than the sprite.
+    pub fn new(sprite: Sprite, insets: Insets) -> Result<NineSlice, String> {
+        if insets.left as u64 + insets.right as u64 > sprite.width() as u64
+            || insets.top as u64 + insets.bottom as u64 > sprite.height() as u64 {
+            return Err(format!("the insets {:?} do not fit into the sprite of {} x {} pixels",
+                               insets, sprite.width(), sprite.height()));
+        }
+
+        Ok(NineSlice {
+            sprite,
+            insets,
+            border_scale : 1.0,
+        })
+    }
+
+    /// The size of a pixel of the sprite's borders in output units, 1 for pixel coordinates.
+    pub fn border_scale(mut self, border_scale: f32) -> NineSlice {
+        self.border_scale = border_scale;
+        self
+    }
+
+    pub fn sprite(&self) -> &Sprite {
+        &self.sprite
+    }
+
+    pub fn insets(&self) -> &Insets {
+        &self.insets
+    }
+
+    /// Builds nine quads with position and uv covering the given size, row by row from the top
+    /// left, with the top left corner at the origin and y going down to -height like a
+    /// TextLayout. Borders wider than the size shrink proportionally to fit, which keeps empty
+    /// quads in the middle. The uvs are relative to a texture of the given width and height,
+    /// e.g. `(texture.width(), texture.height())`.
+    pub fn mesh(&self, texture_size: (u32, u32), width: f32, height: f32) -> Mesh {
+        let insets = &self.insets;
+        let (left, right) = fit(insets.left as f32 * self.border_scale, insets.right as f32 * self.border_scale, width);
+        let (top, bottom) = fit(insets.top as f32 * self.border_scale, insets.bottom as f32 * self.border_scale, height);
+
+        let xs = [0.0, left, width - right, width];
+        let ys = [0.0, -top, -(height - bottom), -height];
+
+        // sprites count from the top, textures from the bottom, and sprites reaching beyond
+        // the texture simply get uvs outside of 0 to 1
+        let sprite = &self.sprite;
+        let (texture_width, texture_height) = (texture_size.0 as f32, texture_size.1 as f32);
+        let (x, y) = (sprite.x_as_f32(), sprite.y_as_f32());
+        let (x_end, y_end) = (x + sprite.width_as_f32(), y + sprite.height_as_f32());
+        let us = [x, x + insets.left as f32, x_end - insets.right as f32, x_end]
+            .map(|u| u / texture_width);
+        let vs = [y, y + insets.top as f32, y_end - insets.bottom as f32, y_end]
+            .map(|v| (texture_height - v) / texture_height);
+
+        let mut mesh = Font::empty_mesh();
+        for row in 0..3 {
+            for column in 0..3 {
+                Font::push_quad(&mut mesh,
+                                [xs[column], ys[row + 1], xs[column + 1], ys[row]],
+                                [us[column], vs[row + 1], us[column + 1], vs[row]]);
+            }
+        }
+
+        mesh
+    }
+}
+
+/// Shrinks two opposite borders proportionally if they do not fit into the size together.
+fn fit(first: f32, second: f32, size: f32) -> (f32, f32) {
+    let total = first + second;
+    if total <= size || total <= 0.0 {
+        (first, second)
+    } else {
+        let scale = size.max(0.0) / total;
+        (first * scale, second * scale)
+    }
+}
@@ -0,0 +1,318 @@
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+
+/// A small GLSL preprocessor that runs before the code is handed to the driver.
+///
+/// It resolves `#include "file"` directives, first relative to the including file and then along
+/// the search paths, and injects `#define`s right after the `#version` directive (which GLSL
+/// requires to be the first statement). Every line of the result remembers the file and line it
+/// originates from, such that compile errors can be reported against the original sources.
+///
+/// Only `#include` is handled, and without looking at the surrounding code: includes on lines
+/// starting with `//` are skipped, but includes inside `/* */` comments or inactive `#if`
+/// blocks are still expanded, so the files they name have to exist.
+pub struct Preprocessor {
+    search_paths : Vec<PathBuf>,
+    defines      : Vec<(String, String)>,
+}
+
+impl Default for Preprocessor {
+    fn default() -> Preprocessor {
+        Preprocessor::new()
+    }
+}
+
+impl Preprocessor {
+
+    pub fn new() -> Preprocessor {
+        Preprocessor {
+            search_paths : Vec::new(),
+            defines      : Vec::new(),
+        }
+    }
+
+    /// Adds a directory in which `#include`d files are looked up.
+    pub fn search_path<P: AsRef<Path>>(mut self, path: P) -> Preprocessor {
+        self.search_paths.push(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Injects `#define name value` into every processed shader.
+    pub fn define(mut self, name: &str, value: &str) -> Preprocessor {
+        self.defines.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Injects `#define name` without a value, e.g. for variants such as `HAS_NORMAL_MAP`.
+    pub fn flag(self, name: &str) -> Preprocessor {
+        self.define(name, "")
+    }
+
+    /// Loads and processes the shader in the given file.
+    pub fn process_file<P: AsRef<Path>>(&self, path: P) -> Result<ShaderSource, String> {
+        self.process_file_tracking(path, &mut Vec::new())
+    }
+
+    /// Same as `process_file`, but also adds every file it reads to `files` unless it is in
+    /// there already. Unlike `ShaderSource::files`, this includes the files read before an
+    /// error, such that a broken include can be watched until it is fixed.
+    pub fn process_file_tracking<P: AsRef<Path>>(&self, path: P, files: &mut Vec<PathBuf>) -> Result<ShaderSource, String> {
+        let path = path.as_ref();
+        let mut read = vec![path.to_path_buf()];
+
+        let result = read_file(path)
+            .and_then(|code| self.process_with_origin(&path.display().to_string(), &code, path.parent(), &mut read));
+
+        for file in &read {
+            if !files.contains(file) {
+                files.push(file.clone());
+            }
+        }
+
+        let mut source = result?;
+        source.files = read;
+        Ok(source)
+    }
+
+    /// Processes shader code that is not backed by a file, such as code embedded into the binary.
+    /// `name` is used in error messages. Trailing NUL characters are ignored.
+    pub fn process(&self, name: &str, code: &str) -> Result<ShaderSource, String> {
+        let mut files = Vec::new();
+        let mut source = self.process_with_origin(name, code, None, &mut files)?;
+        source.files = files;
+        Ok(source)
+    }
+
+    fn process_with_origin(&self, name: &str, code: &str, directory: Option<&Path>,
+                           files: &mut Vec<PathBuf>) -> Result<ShaderSource, String> {
+        let mut body = ShaderSource::empty(name);
+        let mut stack : Vec<PathBuf> = Vec::new();
+
+        self.expand(name, code.trim_end_matches('\0'), directory, &mut stack, files, &mut body)?;
+
+        // the #version directive needs to stay in front, so the defines go right after it
+        let version_index = body.lines.iter().position(|line| line.trim_start().starts_with("#version"));
+
+        let mut source = ShaderSource::empty(name);
+
+        let insert_at = match version_index {
+            Some(index) => index + 1,
+            None        => 0,
+        };
+
+        for i in 0..insert_at {
+            source.push(&body.lines[i], &body.origins[i].0, body.origins[i].1);
+        }
+
+        for (define_name, value) in &self.defines {
+            let line = if value.is_empty() {
+                format!("#define {}", define_name)
+            } else {
+                format!("#define {} {}", define_name, value)
+            };
+            source.push(&line, "<define>", 0);
+        }
+
+        for i in insert_at..body.lines.len() {
+            source.push(&body.lines[i], &body.origins[i].0, body.origins[i].1);
+        }
+
+        Ok(source)
+    }
+
+    fn expand(&self, name: &str, code: &str, directory: Option<&Path>,
+              stack: &mut Vec<PathBuf>, files: &mut Vec<PathBuf>, out: &mut ShaderSource) -> Result<(), String> {
+
+        for (index, line) in code.lines().enumerate() {
+            let trimmed = line.trim_start();
+
+            if !trimmed.starts_with("#include") {
+                out.push(line, name, index + 1);
+                continue;
+            }
+
+            let include = parse_include(trimmed)
+                .ok_or_else(|| format!("{}:{}: malformed #include directive '{}'", name, index + 1, trimmed))?;
+
+            let path = self.resolve(include, directory)
+                .ok_or_else(|| format!("{}:{}: could not find included file '{}'", name, index + 1, include))?;
+
+            if stack.contains(&path) {
+                return Err(format!("{}:{}: recursive #include of '{}'", name, index + 1, path.display()));
+            }
+
+            if !files.contains(&path) {
+                files.push(path.clone());
+            }
+
+            let included_code = read_file(&path)?;
+
+            stack.push(path.clone());
+            self.expand(&path.display().to_string(), &included_code, path.parent(), stack, files, out)?;
+            stack.pop();
+        }
+
+        Ok(())
+    }
+
+    fn resolve(&self, include: &str, directory: Option<&Path>) -> Option<PathBuf> {
+        let local = directory.map(|d| d.join(include));
+
+        local.into_iter()
+            .chain(self.search_paths.iter().map(|p| p.join(include)))
+            .find(|candidate| candidate.is_file())
+    }
+}
+
+/// Extracts the file name from `#include "file"` or `#include <file>`.
+fn parse_include(line: &str) -> Option<&str> {
+    let rest = line["#include".len()..].trim();
+
+    let (open, close) = match rest.chars().next() {
+        Some('"') => ('"', '"'),
+        Some('<') => ('<', '>'),
+        _         => return None,
+    };
+
+    let rest = &rest[open.len_utf8()..];
+    rest.find(close).map(|end| &rest[..end])
+}
+
+fn read_file(path: &Path) -> Result<String, String> {
+    let mut file = File::open(path).map_err(|why| format!("couldn't open {}: {}", path.display(), why))?;
+    let mut content = String::new();
+    file.read_to_string(&mut content).map_err(|why| format!("couldn't read {}: {}", path.display(), why))?;
+    Ok(content)
+}
+
+/// Preprocessed shader code along with the origin (file and line) of every line.
+#[derive(Clone, Debug)]
+pub struct ShaderSource {
+    name    : String,
+    lines   : Vec<String>,
+    origins : Vec<(String, usize)>,
+    files   : Vec<PathBuf>,
+}
+
+impl ShaderSource {
+
+    fn empty(name: &str) -> ShaderSource {
+        ShaderSource {
+            name    : name.to_string(),
+            lines   : Vec::new(),
+            origins : Vec::new(),
+            files   : Vec::new(),
+        }
+    }
+
+    /// Wraps code without any preprocessing, such that each line maps onto itself.
+    pub fn from_code(name: &str, code: &str) -> ShaderSource {
+        let mut source = ShaderSource::empty(name);
+        for (index, line) in code.trim_end_matches('\0').lines().enumerate() {
+            source.push(line, name, index + 1);
+        }
+        source
+    }
+
+    fn push(&mut self, line: &str, file: &str, line_number: usize) {
+        self.lines.push(line.to_string());
+        self.origins.push((file.to_string(), line_number));
+    }
+
+    /// Name of the file or of the embedded source the shader was processed from.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// All files the shader was assembled from, i.e. the main file (if any) and every included one.
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// The processed code as one string.
+    pub fn code(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    /// The processed code as NUL-terminated bytes, ready to be passed to `glShaderSource`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.code().into_bytes();
+        bytes.push(b'\n');
+        bytes.push(0);
+        bytes
+    }
+
+    /// Maps a 1-based line number of the processed code to its original file and line.
+    pub fn origin(&self, line: usize) -> Option<(&str, usize)> {
+        if line == 0 {
+            return None;
+        }
+        self.origins.get(line - 1).map(|(file, number)| (file.as_str(), *number))
+    }
+
+    /// Rewrites the line references in a driver's info log, such as `0:12(5):` (Mesa, AMD, Intel)
+    /// or `0(12) :` (NVIDIA), into `file:line` of the original sources.
+    pub fn map_log(&self, log: &str) -> String {
+        let mut mapped = String::with_capacity(log.len());
+
+        for line in log.lines() {
+            match find_line_reference(line) {
+                Some((start, end, number)) => match self.origin(number) {
+                    Some((file, original)) => {
+                        mapped.push_str(&line[..start]);
+                        mapped.push_str(&format!("{}:{}", file, original));
+                        mapped.push_str(&line[end..]);
+                    },
+                    None => mapped.push_str(line),
+                },
+                None => mapped.push_str(line),
+            }
+            mapped.push('\n');
+        }
+
+        mapped
+    }
+}
+
+/// Finds the first `<string>:<line>` or `<string>(<line>)` reference in a log line and returns
+/// its byte range along with the line number.
+fn find_line_reference(line: &str) -> Option<(usize, usize, usize)> {
+    let bytes = line.as_bytes();
+    let digits_end = |from: usize| {
+        let mut i = from;
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            i += 1;
+        }
+        i
+    };
+
+    let mut start = 0;
+    while start < bytes.len() {
+        let at_word_start = start == 0 || !bytes[start - 1].is_ascii_alphanumeric();
+
+        if at_word_start && bytes[start].is_ascii_digit() {
+            let string_end = digits_end(start);
+
+            if string_end + 1 < bytes.len() && (bytes[string_end] == b':' || bytes[string_end] == b'(') {
+                let line_start = string_end + 1;
+                let line_end   = digits_end(line_start);
+
+                if line_end > line_start {
+                    let closed = bytes[string_end] == b':' || (line_end < bytes.len() && bytes[line_end] == b')');
+
+                    if closed {
+                        let end = if bytes[string_end] == b'(' { line_end + 1 } else { line_end };
+                        let number = line[line_start..line_end].parse::<usize>().ok()?;
+                        return Some((start, end, number));
+                    }
+                }
+            }
+            start = string_end;
+        } else {
+            start += 1;
+        }
+    }
+
+    None
+}
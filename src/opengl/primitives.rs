@@ -16,8 +16,8 @@ pub fn cube() -> Mesh {
 
     let int_vertices = vec![
         vec![0, 0, 0,  0, 0, -1,  1, 0, 0,  0, 0],
-        vec![0, 0, 0,  0, 0, -1,  1, 0, 0,  0, 0],
-        vec![0, 0, 0,  0, 0, -1,  1, 0, 0,  0, 0],
+        vec![0, 1, 0,  0, 0, -1,  1, 0, 0,  1, 0],
+        vec![1, 1, 0,  0, 0, -1,  1, 0, 0,  1, 1],
         vec![1, 0, 0,  0, 0, -1,  1, 0, 0,  0, 1],
 
         vec![0, 0, 1, 0, 0, 1, 1, 0, 1, 0, 0],
@@ -3,72 +3,280 @@ extern crate glutin;
 //extern crate libc;
 
 use std::ptr;
-use std::mem;
+use std::collections::HashMap;
 use linalg::{Matrix4, Vector4, Matrix3};
+use super::uniform::{self, Uniform};
+use super::preprocessor::{Preprocessor, ShaderSource};
+use std::path::Path;
 use std;
 
+/// Description of an active uniform variable as reported by the driver after linking.
+#[derive(Clone, Debug)]
+pub struct UniformInfo {
+    location : gl::types::GLint,
+    gl_type  : gl::types::GLenum,
+    size     : usize,
+}
+
+impl UniformInfo {
+
+    pub fn location(&self) -> gl::types::GLint {
+        self.location
+    }
+
+    /// GLSL type of the uniform, such as `gl::FLOAT_VEC3`.
+    pub fn gl_type(&self) -> gl::types::GLenum {
+        self.gl_type
+    }
+
+    /// Number of array elements, 1 for uniforms that are not arrays.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
 #[allow(dead_code)]
 pub struct Program {
-    id : gl::types::GLuint,
+    id       : gl::types::GLuint,
+    uniforms : HashMap<String, UniformInfo>,
 }
 
 #[allow(dead_code)]
 impl Program {
+    /// Compiles and links a Program from two NUL-terminated shaders.
+    ///
+    /// Panics with the driver's log if a shader does not compile or the Program does not link.
+    /// Use `ProgramBuilder` or `from_sources` to get these errors as `Err` instead.
     pub fn new(vertex_shader_code: &[u8], fragment_shader_code: &[u8]) -> Program {
         let vertex_shader   = Program::create_shader(vertex_shader_code,   gl::VERTEX_SHADER);
         let fragment_shader = Program::create_shader(fragment_shader_code, gl::FRAGMENT_SHADER);
+
+        match Program::link(&[vertex_shader, fragment_shader]) {
+            Ok(program) => program,
+            Err(msg)    => panic!("error while linking the program.\n\
+                ========[ error message ]========\n\
+                {}\n", msg),
+        }
+    }
+
+    /// Compiles and links a Program from preprocessed shader sources. In contrast to `new`, errors
+    /// are returned instead of panicking, and the line numbers in compile errors refer to the
+    /// original files the shader was assembled from.
+    pub fn from_sources(vertex_shader: &ShaderSource, fragment_shader: &ShaderSource) -> Result<Program, String> {
+        ProgramBuilder::new()
+            .source(ShaderStage::Vertex,   vertex_shader.clone())
+            .source(ShaderStage::Fragment, fragment_shader.clone())
+            .build()
+    }
+
+    /// Loads, preprocesses, compiles and links a Program from the two given shader files.
+    pub fn from_files<P: AsRef<Path>>(vertex_shader_path: P, fragment_shader_path: P) -> Result<Program, String> {
+        let preprocessor = Preprocessor::new();
+        let vertex_shader   = preprocessor.process_file(vertex_shader_path)?;
+        let fragment_shader = preprocessor.process_file(fragment_shader_path)?;
+
+        Program::from_sources(&vertex_shader, &fragment_shader)
+    }
+
+    fn compile_source(source: &ShaderSource, shader_type: gl::types::GLenum) -> Result<gl::types::GLuint, String> {
+        Program::compile_shader(&source.to_bytes(), shader_type).map_err(|log| {
+            format!("error while compiling shader '{}'.\n{}", source.name(), source.map_log(&log))
+        })
+    }
+
+    /// Links the given compiled shaders into a Program and makes it the currently used one.
+    /// The shaders are flagged for deletion, so they are released together with the Program.
+    fn link(shaders: &[gl::types::GLuint]) -> Result<Program, String> {
         unsafe {
             let program = gl::CreateProgram();
-            gl::AttachShader(program, vertex_shader);
-            gl::AttachShader(program, fragment_shader);
+            for shader in shaders {
+                gl::AttachShader(program, *shader);
+            }
             gl::LinkProgram(program);
-            gl::UseProgram(program);
 
-            Program {
-                id: program
+            for shader in shaders {
+                gl::DetachShader(program, *shader);
+                gl::DeleteShader(*shader);
+            }
+
+            let mut success = 0;
+            gl::GetProgramiv(program, gl::LINK_STATUS, &mut success);
+            if success == gl::FALSE as gl::types::GLint {
+                let mut error_log_size = 0;
+                gl::GetProgramiv(program, gl::INFO_LOG_LENGTH, &mut error_log_size);
+
+                let mut error_log: Vec<u8> = vec![0; error_log_size.max(1) as usize];
+                gl::GetProgramInfoLog(program, error_log_size, &mut error_log_size,
+                                      error_log.as_mut_ptr() as *mut gl::types::GLchar);
+                error_log.truncate(error_log_size.max(0) as usize);
+                gl::DeleteProgram(program);
+
+                return Err(String::from_utf8_lossy(&error_log).into_owned());
             }
+
+            gl::UseProgram(program);
+
+            Ok(Program {
+                id       : program,
+                uniforms : Program::reflect_uniforms(program),
+            })
         }
     }
 
     fn create_shader(code: &[u8], shader_type: gl::types::GLenum) -> gl::types::GLuint {
+        match Program::compile_shader(code, shader_type) {
+            Ok(shader) => shader,
+            Err(msg)   => {
+                let code_as_utf8 = std::str::from_utf8(code).unwrap();
+                panic!("error while compiling one of the shaders.\n\
+                        ========[ error message ]========\n\
+                        {}\
+                        ========[ code ]========\n\
+                        {}\n\
+                        ", msg, code_as_utf8)
+            }
+        }
+    }
+
+    /// Compiles a single NUL-terminated shader, returning the info log of the driver on failure.
+    fn compile_shader(code: &[u8], shader_type: gl::types::GLenum) -> Result<gl::types::GLuint, String> {
         unsafe {
             let vs = gl::CreateShader(shader_type);
             gl::ShaderSource(vs, 1, [code.as_ptr() as *const _].as_ptr(), ptr::null());
             gl::CompileShader(vs);
-            let mut success = mem::uninitialized();
+            let mut success = 0;
             gl::GetShaderiv(vs, gl::COMPILE_STATUS, &mut success);
             if success == gl::FALSE as gl::types::GLint {
-                //let s: [u8, ..code.len()] = code;
+                let mut error_log_size = 0;
+                gl::GetShaderiv(vs, gl::INFO_LOG_LENGTH, &mut error_log_size);
 
+                let mut error_log: Vec<u8> = vec![0; error_log_size.max(1) as usize];
+                gl::GetShaderInfoLog(vs, error_log_size, &mut error_log_size,
+                                     error_log.as_mut_ptr() as *mut gl::types::GLchar);
+                error_log.truncate(error_log_size.max(0) as usize);
+                gl::DeleteShader(vs);
 
-                let mut error_log_size = mem::uninitialized();
-                gl::GetShaderiv(vs, gl::INFO_LOG_LENGTH, &mut error_log_size);
+                return match String::from_utf8(error_log) {
+                    Ok(msg) => Err(msg),
+                    Err(_)  => Err("Could not convert the log message to UTF-8".to_string()),
+                };
+            }
+            Ok(vs)
+        }
+    }
 
-                if error_log_size > 0 {
-                    let mut error_log: Vec<u8> = Vec::with_capacity(error_log_size as usize);
-                    gl::GetShaderInfoLog(vs, error_log_size, &mut error_log_size,
-                                         error_log.as_mut_ptr() as *mut gl::types::GLchar);
-                    error_log.set_len(error_log_size as usize);
-                    let code_as_utf8 = std::str::from_utf8(code).unwrap();
+    /// Queries all active uniforms of a linked program. Array uniforms are reported by the driver
+    /// as `name[0]` and are registered under their plain name.
+    fn reflect_uniforms(program: gl::types::GLuint) -> HashMap<String, UniformInfo> {
+        let mut uniforms = HashMap::new();
+        unsafe {
+            let mut count = 0;
+            gl::GetProgramiv(program, gl::ACTIVE_UNIFORMS, &mut count);
 
-                    // maybe use std::slice::from_raw_parts(ptr, len);
-                    // like here: https://doc.rust-lang.org/std/primitive.str.html
+            let mut max_name_length = 0;
+            gl::GetProgramiv(program, gl::ACTIVE_UNIFORM_MAX_LENGTH, &mut max_name_length);
 
+            for index in 0..count.max(0) as gl::types::GLuint {
+                let mut name : Vec<u8> = vec![0; max_name_length.max(1) as usize];
+                let mut length  = 0;
+                let mut size    = 0;
+                let mut gl_type = 0;
 
-                    match String::from_utf8(error_log) {
-                        Ok(msg) => panic!("error while compiling one of the shaders.\n\
-                        ========[ error message ]========\n\
-                        {}\
-                        ========[ code ]========\n\
-                        {}\n\
-                        ", msg, code_as_utf8),
-                        Err(_) => panic!("Could not convert the log message to UTF-8")
-                    }
+                gl::GetActiveUniform(program, index, max_name_length, &mut length, &mut size,
+                                     &mut gl_type, name.as_mut_ptr() as *mut gl::types::GLchar);
+                name.truncate(length.max(0) as usize);
+
+                let mut name = String::from_utf8_lossy(&name).into_owned();
+                if name.ends_with("[0]") {
+                    let plain_length = name.len() - "[0]".len();
+                    name.truncate(plain_length);
                 }
+
+                let mut name_with_delimiter = name.clone();
+                name_with_delimiter.push('\0');
+                let location = gl::GetUniformLocation(program, name_with_delimiter.as_ptr() as *const _);
+
+                // uniforms inside of uniform blocks have no location
+                if location < 0 {
+                    continue;
+                }
+
+                uniforms.insert(name, UniformInfo {
+                    location,
+                    gl_type,
+                    size : size.max(1) as usize,
+                });
             }
-            //println!("success {:?}", success);
-            return vs;
         }
+        uniforms
+    }
+
+    /// Returns the reflected active uniforms of this Program by name.
+    pub fn uniforms(&self) -> &HashMap<String, UniformInfo> {
+        &self.uniforms
+    }
+
+    /// Sets the uniform with the given name to the given value. The value is checked against
+    /// the type and array size the driver reported for the uniform, such that for example a
+    /// `Matrix3` cannot accidentally be written into a `mat4`. The Program needs to be in use.
+    pub fn set<U: Uniform + ?Sized>(&self, name: &str, value: &U) -> Result<(), String> {
+        let info = match self.uniforms.get(name) {
+            Some(info) => info,
+            None       => return Err(format!("could not find active uniform with name '{}' in Program", name)),
+        };
+
+        if !value.matches(info.gl_type) {
+            return Err(format!("uniform '{}' is of type {}, which does not match the given value",
+                               name, uniform::type_name(info.gl_type)));
+        }
+
+        if value.count() > info.size {
+            return Err(format!("uniform '{}' holds {} element(s), but {} were given",
+                               name, info.size, value.count()));
+        }
+
+        value.upload(info.location);
+
+        Ok(())
+    }
+
+    /// Attaches the uniform block with the given name to a binding point, from which it then
+    /// reads the UniformBuffer bound there with `UniformBuffer::bind_base`.
+    pub fn bind_uniform_block(&self, name: &str, binding: u32) -> Result<(), String> {
+        let mut name_with_delimiter = name.to_string();
+        name_with_delimiter.push('\0');
+        unsafe {
+            let index = gl::GetUniformBlockIndex(self.id, name_with_delimiter.as_ptr() as *const _);
+
+            if index == gl::INVALID_INDEX {
+                return Err(format!("could not find uniform block with name '{}' in Program", name));
+            }
+
+            gl::UniformBlockBinding(self.id, index, binding);
+        }
+        Ok(())
+    }
+
+    /// Runs this compute Program with the given number of work groups in each dimension.
+    /// Use `memory_barrier` before reading the results in subsequent draw or dispatch calls.
+    pub fn dispatch_compute(&self, groups_x: u32, groups_y: u32, groups_z: u32) {
+        unsafe {
+            gl::UseProgram(self.id);
+            gl::DispatchCompute(groups_x, groups_y, groups_z);
+        }
+    }
+
+    /// Returns the local work group size declared in a compute shader via `layout(local_size_x = ...)`.
+    pub fn compute_work_group_size(&self) -> (u32, u32, u32) {
+        let mut size : [gl::types::GLint; 3] = [0; 3];
+        unsafe {
+            gl::GetProgramiv(self.id, gl::COMPUTE_WORK_GROUP_SIZE, size.as_mut_ptr());
+        }
+        (size[0] as u32, size[1] as u32, size[2] as u32)
+    }
+
+    pub fn id(&self) -> gl::types::GLuint {
+        self.id
     }
 
     pub fn use_program(&self) {
@@ -92,7 +300,7 @@ impl Program {
         }
     }
 
-    pub fn uniform_location(&self, name: &str) -> Result<u8, String> {
+    pub fn uniform_location(&self, name: &str) -> Result<gl::types::GLint, String> {
         // adding delimiter. That is actually dangerous if the delimiter is already appended
         let mut name_with_delimiter = name.to_string();
         name_with_delimiter.push('\0');
@@ -103,7 +311,7 @@ impl Program {
                 return Err(format!("could not find uniform location with name '{}' in currently used Program", name));
             }
 
-            return Ok(location as u8);
+            return Ok(location);
         }
     }
 
@@ -112,10 +320,10 @@ impl Program {
         self.uniform_matrix4fv(location, matrix, transpose);
     }
 
-    pub fn uniform_matrix4fv(&self, location: u8, matrix: &Matrix4, transpose: bool) {
+    pub fn uniform_matrix4fv(&self, location: gl::types::GLint, matrix: &Matrix4, transpose: bool) {
         unsafe {
             gl::UniformMatrix4fv(
-                location  as gl::types::GLint,
+                location,
                 1         as gl::types::GLsizei,
                 transpose as gl::types::GLboolean,
                 matrix.as_ptr()
@@ -128,10 +336,10 @@ impl Program {
         self.uniform_matrix3fv(location, matrix, transpose);
     }
 
-    pub fn uniform_matrix3fv(&self, location: u8, matrix: &Matrix3, transpose: bool) {
+    pub fn uniform_matrix3fv(&self, location: gl::types::GLint, matrix: &Matrix3, transpose: bool) {
         unsafe {
             gl::UniformMatrix3fv(
-                location  as gl::types::GLint,
+                location,
                 1         as gl::types::GLsizei,
                 transpose as gl::types::GLboolean,
                 matrix.as_ptr()
@@ -145,14 +353,14 @@ impl Program {
     }
 
 
-    pub fn uniform_vector4(&self, location: u8, vec: &Vector4) {
+    pub fn uniform_vector4(&self, location: gl::types::GLint, vec: &Vector4) {
         self.uniform_4f(location, vec.x, vec.y, vec.z, vec.w);
     }
 
-    pub fn uniform_4f(&self, location: u8, x: f32, y: f32, z: f32, w: f32) {
+    pub fn uniform_4f(&self, location: gl::types::GLint, x: f32, y: f32, z: f32, w: f32) {
         unsafe {
             gl::Uniform4f(
-                location  as gl::types::GLint,
+                location,
                 x as gl::types::GLfloat,
                 y as gl::types::GLfloat,
                 z as gl::types::GLfloat,
@@ -161,10 +369,10 @@ impl Program {
         }
     }
 
-    pub fn uniform_3f(&self, location: u8, x: f32, y: f32, z: f32) {
+    pub fn uniform_3f(&self, location: gl::types::GLint, x: f32, y: f32, z: f32) {
         unsafe {
             gl::Uniform3f(
-                location  as gl::types::GLint,
+                location,
                 x as gl::types::GLfloat,
                 y as gl::types::GLfloat,
                 z as gl::types::GLfloat,
@@ -177,9 +385,9 @@ impl Program {
         self.uniform_1i(location, value);
     }
 
-    pub fn uniform_1i(&self, location: u8, value: i32) {
+    pub fn uniform_1i(&self, location: gl::types::GLint, value: i32) {
         unsafe {
-            gl::Uniform1i(location as gl::types::GLint, value);
+            gl::Uniform1i(location, value);
         }
     }
 
@@ -191,4 +399,150 @@ glUniform1i(uniform.location(), value);
 */
     // glUniformMatrix4fv(uniform.location(), transpose, floatBufferCache16);
 
-}
\ No newline at end of file
+}
+
+/// Orders memory accesses of shaders, e.g. writes of a compute shader to a buffer before the buffer
+/// is used as vertex input. `barriers` is a combination of the `gl::*_BARRIER_BIT` flags such as
+/// `gl::SHADER_STORAGE_BARRIER_BIT | gl::VERTEX_ATTRIB_ARRAY_BARRIER_BIT`.
+pub fn memory_barrier(barriers: gl::types::GLbitfield) {
+    unsafe {
+        gl::MemoryBarrier(barriers);
+    }
+}
+
+/// The programmable stages of the OpenGL pipeline a shader can be written for.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ShaderStage {
+    Vertex,
+    TessControl,
+    TessEvaluation,
+    Geometry,
+    Fragment,
+    Compute,
+}
+
+impl ShaderStage {
+
+    pub fn gl_enum(self) -> gl::types::GLenum {
+        match self {
+            ShaderStage::Vertex         => gl::VERTEX_SHADER,
+            ShaderStage::TessControl    => gl::TESS_CONTROL_SHADER,
+            ShaderStage::TessEvaluation => gl::TESS_EVALUATION_SHADER,
+            ShaderStage::Geometry       => gl::GEOMETRY_SHADER,
+            ShaderStage::Fragment       => gl::FRAGMENT_SHADER,
+            ShaderStage::Compute        => gl::COMPUTE_SHADER,
+        }
+    }
+}
+
+/// Builds a Program out of an arbitrary combination of shader stages, e.g. vertex, geometry and
+/// fragment shader for wireframe rendering, or a single compute shader.
+pub struct ProgramBuilder {
+    stages : Vec<(ShaderStage, ShaderSource)>,
+}
+
+impl Default for ProgramBuilder {
+    fn default() -> ProgramBuilder {
+        ProgramBuilder::new()
+    }
+}
+
+impl ProgramBuilder {
+
+    pub fn new() -> ProgramBuilder {
+        ProgramBuilder {
+            stages : Vec::new(),
+        }
+    }
+
+    pub fn vertex(self, code: &[u8]) -> ProgramBuilder {
+        self.stage(ShaderStage::Vertex, code)
+    }
+
+    pub fn tess_control(self, code: &[u8]) -> ProgramBuilder {
+        self.stage(ShaderStage::TessControl, code)
+    }
+
+    pub fn tess_evaluation(self, code: &[u8]) -> ProgramBuilder {
+        self.stage(ShaderStage::TessEvaluation, code)
+    }
+
+    pub fn geometry(self, code: &[u8]) -> ProgramBuilder {
+        self.stage(ShaderStage::Geometry, code)
+    }
+
+    pub fn fragment(self, code: &[u8]) -> ProgramBuilder {
+        self.stage(ShaderStage::Fragment, code)
+    }
+
+    pub fn compute(self, code: &[u8]) -> ProgramBuilder {
+        self.stage(ShaderStage::Compute, code)
+    }
+
+    /// Adds shader code, embedded in the same way as for `Program::new`, for the given stage.
+    pub fn stage(self, stage: ShaderStage, code: &[u8]) -> ProgramBuilder {
+        let name = format!("{:?} shader", stage);
+        let code = String::from_utf8_lossy(code);
+        self.source(stage, ShaderSource::from_code(&name, &code))
+    }
+
+    /// Adds preprocessed shader code for the given stage.
+    pub fn source(mut self, stage: ShaderStage, source: ShaderSource) -> ProgramBuilder {
+        self.stages.push((stage, source));
+        self
+    }
+
+    fn has(&self, stage: ShaderStage) -> bool {
+        self.stages.iter().any(|&(s, _)| s == stage)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.stages.is_empty() {
+            return Err("a Program needs at least one shader stage".to_string());
+        }
+
+        for (i, &(stage, _)) in self.stages.iter().enumerate() {
+            if self.stages[..i].iter().any(|&(s, _)| s == stage) {
+                return Err(format!("the {:?} stage was given more than once", stage));
+            }
+        }
+
+        if self.has(ShaderStage::Compute) {
+            if self.stages.len() > 1 {
+                return Err("a compute shader cannot be combined with other shader stages".to_string());
+            }
+            return Ok(());
+        }
+
+        if !self.has(ShaderStage::Vertex) {
+            return Err("a Program for rendering needs a vertex shader".to_string());
+        }
+
+        if self.has(ShaderStage::TessControl) && !self.has(ShaderStage::TessEvaluation) {
+            return Err("a tessellation control shader requires a tessellation evaluation shader".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Compiles all stages and links them into a Program, which is in use afterwards.
+    pub fn build(self) -> Result<Program, String> {
+        self.validate()?;
+
+        let mut shaders : Vec<gl::types::GLuint> = Vec::with_capacity(self.stages.len());
+
+        for &(stage, ref source) in &self.stages {
+            match Program::compile_source(source, stage.gl_enum()) {
+                Ok(shader) => shaders.push(shader),
+                Err(msg)   => {
+                    for shader in shaders {
+                        unsafe { gl::DeleteShader(shader); }
+                    }
+                    return Err(msg);
+                }
+            }
+        }
+
+        Program::link(&shaders)
+    }
+}
@@ -0,0 +1,236 @@
+/// The look of a span of rich text.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SpanStyle {
+    color     : [f32; 4],
+    scale     : f32,
+    underline : bool,
+    strike    : bool,
+    font      : usize,
+}
+
+impl Default for SpanStyle {
+    fn default() -> SpanStyle {
+        SpanStyle::new()
+    }
+}
+
+#[allow(dead_code)]
+impl SpanStyle {
+
+    /// Opaque white text at the size of the layout, in the first font.
+    pub fn new() -> SpanStyle {
+        SpanStyle {
+            color     : [1.0, 1.0, 1.0, 1.0],
+            scale     : 1.0,
+            underline : false,
+            strike    : false,
+            font      : 0,
+        }
+    }
+
+    pub fn color(mut self, color: [f32; 4]) -> SpanStyle {
+        self.color = color;
+        self
+    }
+
+    /// Scales the glyphs relative to the size of the layout.
+    pub fn scale(mut self, scale: f32) -> SpanStyle {
+        self.scale = scale;
+        self
+    }
+
+    pub fn underline(mut self, underline: bool) -> SpanStyle {
+        self.underline = underline;
+        self
+    }
+
+    pub fn strike(mut self, strike: bool) -> SpanStyle {
+        self.strike = strike;
+        self
+    }
+
+    /// Selects a font by its index in the fonts given to the layout.
+    pub fn font(mut self, font: usize) -> SpanStyle {
+        self.font = font;
+        self
+    }
+
+    pub fn get_color(&self) -> [f32; 4] {
+        self.color
+    }
+
+    pub fn get_scale(&self) -> f32 {
+        self.scale
+    }
+
+    pub fn is_underlined(&self) -> bool {
+        self.underline
+    }
+
+    pub fn is_struck(&self) -> bool {
+        self.strike
+    }
+
+    pub fn get_font(&self) -> usize {
+        self.font
+    }
+}
+
+/// A piece of text drawn in a single style.
+#[allow(dead_code)]
+#[derive(Clone, PartialEq, Debug)]
+pub struct Span {
+    text  : String,
+    style : SpanStyle,
+}
+
+#[allow(dead_code)]
+impl Span {
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn style(&self) -> &SpanStyle {
+        &self.style
+    }
+}
+
+/// Text made of spans with their own color, scale, decoration and font, to be laid out by
+/// `TextLayout::rich_meshes`.
+#[allow(dead_code)]
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct RichText {
+    spans : Vec<Span>,
+}
+
+#[allow(dead_code)]
+impl RichText {
+
+    pub fn new() -> RichText {
+        RichText {
+            spans : Vec::new(),
+        }
+    }
+
+    /// Appends a span, merging it into the last one if the style is the same.
+    pub fn push(mut self, text: &str, style: SpanStyle) -> RichText {
+        self.push_span(text, style);
+        self
+    }
+
+    fn push_span(&mut self, text: &str, style: SpanStyle) {
+        if text.is_empty() {
+            return;
+        }
+
+        match self.spans.last_mut() {
+            Some(ref mut last) if last.style == style => last.text.push_str(text),
+            _ => self.spans.push(Span { text: text.to_string(), style }),
+        }
+    }
+
+    /// Parses markup with the default SpanStyle as base.
+    pub fn parse(markup: &str) -> Result<RichText, String> {
+        RichText::parse_with_style(markup, SpanStyle::new())
+    }
+
+    /// Parses markup made of text and the tags
+    ///
+    /// - `[color=#rrggbb]`, also as `#rgb` or `#rrggbbaa`
+    /// - `[scale=1.5]`, relative to the enclosing scale
+    /// - `[u]` for underlined and `[s]` for struck through text
+    /// - `[font=1]` selecting a font by index
+    ///
+    /// each closed by its name, e.g. `[/color]`. Tags nest and have to be closed in reverse
+    /// order. `[[` stands for a literal `[`.
+    pub fn parse_with_style(markup: &str, base: SpanStyle) -> Result<RichText, String> {
+        let mut text  = RichText::new();
+        let mut stack : Vec<(&str, SpanStyle)> = Vec::new();
+        let mut style = base;
+        let mut rest  = markup;
+
+        while let Some(start) = rest.find('[') {
+            text.push_span(&rest[..start], style);
+            rest = &rest[start..];
+
+            if rest.starts_with("[[") {
+                text.push_span("[", style);
+                rest = &rest[2..];
+                continue;
+            }
+
+            let end = rest.find(']').ok_or_else(|| format!("unterminated tag '{}'", rest))?;
+            let tag = &rest[1..end];
+            rest = &rest[end + 1..];
+
+            if let Some(name) = tag.strip_prefix('/') {
+                match stack.pop() {
+                    Some((open, previous)) if open == name => style = previous,
+                    Some((open, _)) => return Err(format!("[/{}] closes [{}]", name, open)),
+                    None            => return Err(format!("[/{}] closes no tag", name)),
+                }
+                continue;
+            }
+
+            let (name, value) = match tag.find('=') {
+                Some(equals) => (&tag[..equals], Some(tag[equals + 1..].trim())),
+                None         => (tag, None),
+            };
+
+            let previous = style;
+            style = match (name, value) {
+                ("color", Some(value)) => style.color(parse_color(value)?),
+                ("scale", Some(value)) => {
+                    let scale = value.parse::<f32>().map_err(|_| format!("invalid scale '{}'", value))?;
+                    style.scale(style.scale * scale)
+                },
+                ("font", Some(value))  => style.font(value.parse::<usize>().map_err(|_| format!("invalid font '{}'", value))?),
+                ("u", None)            => style.underline(true),
+                ("s", None)            => style.strike(true),
+                _                      => return Err(format!("unknown tag [{}]", tag)),
+            };
+            stack.push((name, previous));
+        }
+
+        text.push_span(rest, style);
+
+        if let Some((open, _)) = stack.last() {
+            return Err(format!("[{}] is not closed", open));
+        }
+
+        Ok(text)
+    }
+
+    pub fn spans(&self) -> &[Span] {
+        &self.spans
+    }
+
+    /// The text of all spans without styles.
+    pub fn plain_text(&self) -> String {
+        self.spans.iter().map(|span| span.text.as_str()).collect()
+    }
+}
+
+/// Parses a color given as `#rgb`, `#rrggbb` or `#rrggbbaa`.
+pub fn parse_color(value: &str) -> Result<[f32; 4], String> {
+    let invalid = || format!("invalid color '{}'", value);
+
+    let digits = value.strip_prefix('#').ok_or_else(invalid)?;
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+
+    let channel = |index: usize, length: usize| {
+        let channel = u8::from_str_radix(&digits[index * length..(index + 1) * length], 16).unwrap();
+        let channel = if length == 1 { channel * 17 } else { channel };
+        channel as f32 / 255.0
+    };
+
+    match digits.len() {
+        3 => Ok([channel(0, 1), channel(1, 1), channel(2, 1), 1.0]),
+        6 => Ok([channel(0, 2), channel(1, 2), channel(2, 2), 1.0]),
+        8 => Ok([channel(0, 2), channel(1, 2), channel(2, 2), channel(3, 2)]),
+        _ => Err(invalid()),
+    }
+}
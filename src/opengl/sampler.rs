@@ -0,0 +1,330 @@
+extern crate gl;
+
+use std::ffi::CStr;
+
+use self::gl::types::{GLenum, GLint, GLuint};
+
+/// `GL_TEXTURE_MAX_ANISOTROPY`, core since OpenGL 4.6 and available as
+/// `GL_EXT_texture_filter_anisotropic` almost everywhere before, but not part of the 4.5 bindings.
+const TEXTURE_MAX_ANISOTROPY : GLenum = 0x84FE;
+const MAX_TEXTURE_MAX_ANISOTROPY : GLenum = 0x84FF;
+
+/// How texels are filtered within a mipmap level.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Filter {
+    Nearest,
+    Linear,
+}
+
+/// How mipmap levels are selected. `None` samples the base level only, which is the only choice
+/// that works for textures without mipmaps.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MipmapFilter {
+    None,
+    Nearest,
+    Linear,
+}
+
+/// What happens to texture coordinates outside of 0 to 1.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Wrap {
+    Repeat,
+    MirroredRepeat,
+    ClampToEdge,
+    ClampToBorder,
+    MirrorClampToEdge,
+}
+
+impl Wrap {
+
+    pub fn gl_enum(self) -> GLenum {
+        match self {
+            Wrap::Repeat            => gl::REPEAT,
+            Wrap::MirroredRepeat    => gl::MIRRORED_REPEAT,
+            Wrap::ClampToEdge       => gl::CLAMP_TO_EDGE,
+            Wrap::ClampToBorder     => gl::CLAMP_TO_BORDER,
+            Wrap::MirrorClampToEdge => gl::MIRROR_CLAMP_TO_EDGE,
+        }
+    }
+}
+
+/// Comparison of depth textures against the reference value when sampled with a shadow sampler.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CompareFunc {
+    Never,
+    Less,
+    LessEqual,
+    Equal,
+    NotEqual,
+    GreaterEqual,
+    Greater,
+    Always,
+}
+
+impl CompareFunc {
+
+    pub fn gl_enum(self) -> GLenum {
+        match self {
+            CompareFunc::Never        => gl::NEVER,
+            CompareFunc::Less         => gl::LESS,
+            CompareFunc::LessEqual    => gl::LEQUAL,
+            CompareFunc::Equal        => gl::EQUAL,
+            CompareFunc::NotEqual     => gl::NOTEQUAL,
+            CompareFunc::GreaterEqual => gl::GEQUAL,
+            CompareFunc::Greater      => gl::GREATER,
+            CompareFunc::Always       => gl::ALWAYS,
+        }
+    }
+}
+
+/// Describes how a texture is sampled: filtering, wrapping, anisotropy, border color and depth
+/// comparison. The defaults are linear filtering without mipmaps and clamping to the edge.
+///
+/// A SamplerDesc is either applied to a Texture with `Texture::set_sampler`, which changes how
+/// the texture is sampled everywhere, or turned into a Sampler object that is bound to a texture
+/// unit and overrides the texture's own settings, e.g. to sample the same texture in two ways.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SamplerDesc {
+    min_filter     : Filter,
+    mag_filter     : Filter,
+    mipmap_filter  : MipmapFilter,
+    wrap_s         : Wrap,
+    wrap_t         : Wrap,
+    wrap_r         : Wrap,
+    max_anisotropy : f32,
+    border_color   : [f32; 4],
+    compare        : Option<CompareFunc>,
+}
+
+impl Default for SamplerDesc {
+    fn default() -> SamplerDesc {
+        SamplerDesc::new()
+    }
+}
+
+impl SamplerDesc {
+
+    pub fn new() -> SamplerDesc {
+        SamplerDesc {
+            min_filter     : Filter::Linear,
+            mag_filter     : Filter::Linear,
+            mipmap_filter  : MipmapFilter::None,
+            wrap_s         : Wrap::ClampToEdge,
+            wrap_t         : Wrap::ClampToEdge,
+            wrap_r         : Wrap::ClampToEdge,
+            max_anisotropy : 1.0,
+            border_color   : [0.0, 0.0, 0.0, 0.0],
+            compare        : None,
+        }
+    }
+
+    /// Sets both the minification and the magnification filter.
+    pub fn filter(self, filter: Filter) -> SamplerDesc {
+        self.min_filter(filter).mag_filter(filter)
+    }
+
+    pub fn min_filter(mut self, filter: Filter) -> SamplerDesc {
+        self.min_filter = filter;
+        self
+    }
+
+    pub fn mag_filter(mut self, filter: Filter) -> SamplerDesc {
+        self.mag_filter = filter;
+        self
+    }
+
+    pub fn mipmap_filter(mut self, filter: MipmapFilter) -> SamplerDesc {
+        self.mipmap_filter = filter;
+        self
+    }
+
+    /// Sets the wrap mode of all three texture coordinates.
+    pub fn wrap(self, wrap: Wrap) -> SamplerDesc {
+        self.wrap_s(wrap).wrap_t(wrap).wrap_r(wrap)
+    }
+
+    pub fn wrap_s(mut self, wrap: Wrap) -> SamplerDesc {
+        self.wrap_s = wrap;
+        self
+    }
+
+    pub fn wrap_t(mut self, wrap: Wrap) -> SamplerDesc {
+        self.wrap_t = wrap;
+        self
+    }
+
+    pub fn wrap_r(mut self, wrap: Wrap) -> SamplerDesc {
+        self.wrap_r = wrap;
+        self
+    }
+
+    /// Maximum degree of anisotropic filtering, 1 disables it. Values above what the driver
+    /// supports are clamped when the description is applied, and drivers without anisotropic
+    /// filtering ignore it.
+    pub fn anisotropy(mut self, max_anisotropy: f32) -> SamplerDesc {
+        self.max_anisotropy = max_anisotropy.max(1.0);
+        self
+    }
+
+    /// Color returned for coordinates outside the texture with `Wrap::ClampToBorder`.
+    pub fn border_color(mut self, color: [f32; 4]) -> SamplerDesc {
+        self.border_color = color;
+        self
+    }
+
+    /// Turns depth textures into shadow maps: sampling them with a shadow sampler compares the
+    /// depth against the reference coordinate and returns the result instead of the depth.
+    pub fn compare(mut self, func: CompareFunc) -> SamplerDesc {
+        self.compare = Some(func);
+        self
+    }
+
+    /// Whether sampling with this description reads mipmap levels, i.e. the texture needs them.
+    pub fn uses_mipmaps(&self) -> bool {
+        self.mipmap_filter != MipmapFilter::None
+    }
+
+    /// The value of `GL_TEXTURE_MIN_FILTER`, which combines the minification and the mipmap filter.
+    pub fn gl_min_filter(&self) -> GLenum {
+        match (self.min_filter, self.mipmap_filter) {
+            (Filter::Nearest, MipmapFilter::None)    => gl::NEAREST,
+            (Filter::Linear,  MipmapFilter::None)    => gl::LINEAR,
+            (Filter::Nearest, MipmapFilter::Nearest) => gl::NEAREST_MIPMAP_NEAREST,
+            (Filter::Linear,  MipmapFilter::Nearest) => gl::LINEAR_MIPMAP_NEAREST,
+            (Filter::Nearest, MipmapFilter::Linear)  => gl::NEAREST_MIPMAP_LINEAR,
+            (Filter::Linear,  MipmapFilter::Linear)  => gl::LINEAR_MIPMAP_LINEAR,
+        }
+    }
+
+    /// The value of `GL_TEXTURE_MAG_FILTER`.
+    pub fn gl_mag_filter(&self) -> GLenum {
+        match self.mag_filter {
+            Filter::Nearest => gl::NEAREST,
+            Filter::Linear  => gl::LINEAR,
+        }
+    }
+
+    /// All integer parameters as pairs of parameter name and value.
+    fn int_parameters(&self) -> Vec<(GLenum, GLint)> {
+        let compare_mode = if self.compare.is_some() { gl::COMPARE_REF_TO_TEXTURE } else { gl::NONE };
+
+        let mut parameters = vec![
+            (gl::TEXTURE_MIN_FILTER,   self.gl_min_filter() as GLint),
+            (gl::TEXTURE_MAG_FILTER,   self.gl_mag_filter() as GLint),
+            (gl::TEXTURE_WRAP_S,       self.wrap_s.gl_enum() as GLint),
+            (gl::TEXTURE_WRAP_T,       self.wrap_t.gl_enum() as GLint),
+            (gl::TEXTURE_WRAP_R,       self.wrap_r.gl_enum() as GLint),
+            (gl::TEXTURE_COMPARE_MODE, compare_mode as GLint),
+        ];
+
+        if let Some(func) = self.compare {
+            parameters.push((gl::TEXTURE_COMPARE_FUNC, func.gl_enum() as GLint));
+        }
+
+        parameters
+    }
+
+    /// The anisotropy clamped to the maximum supported by the driver, or None if anisotropic
+    /// filtering is off or not supported, in which case the parameter must not be set.
+    fn clamped_anisotropy(&self) -> Option<f32> {
+        if self.max_anisotropy <= 1.0 || !anisotropy_supported() {
+            return None;
+        }
+
+        let mut max = 1.0;
+        unsafe {
+            gl::GetFloatv(MAX_TEXTURE_MAX_ANISOTROPY, &mut max);
+        }
+        Some(self.max_anisotropy.min(max.max(1.0)))
+    }
+
+    /// Applies the description to the texture bound to the given target, e.g. `gl::TEXTURE_2D`.
+    pub fn apply(&self, target: GLenum) {
+        unsafe {
+            for (name, value) in self.int_parameters() {
+                gl::TexParameteri(target, name, value);
+            }
+            gl::TexParameterfv(target, gl::TEXTURE_BORDER_COLOR, self.border_color.as_ptr());
+            if let Some(anisotropy) = self.clamped_anisotropy() {
+                gl::TexParameterf(target, TEXTURE_MAX_ANISOTROPY, anisotropy);
+            }
+        }
+    }
+}
+
+/// True if the context is OpenGL 4.6 or provides one of the anisotropic filtering extensions.
+fn anisotropy_supported() -> bool {
+    let (mut major, mut minor, mut count) = (0, 0, 0);
+    unsafe {
+        gl::GetIntegerv(gl::MAJOR_VERSION, &mut major);
+        gl::GetIntegerv(gl::MINOR_VERSION, &mut minor);
+        gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut count);
+    }
+
+    if (major, minor) >= (4, 6) {
+        return true;
+    }
+
+    (0..count.max(0) as GLuint).any(|index| {
+        let name = unsafe { gl::GetStringi(gl::EXTENSIONS, index) };
+        if name.is_null() {
+            return false;
+        }
+        let name = unsafe { CStr::from_ptr(name as *const _) }.to_bytes();
+        name == b"GL_EXT_texture_filter_anisotropic" || name == b"GL_ARB_texture_filter_anisotropic"
+    })
+}
+
+/// An OpenGL sampler object. While bound to a texture unit, it replaces the sampling parameters
+/// of whatever texture is bound to that unit.
+#[allow(dead_code)]
+pub struct Sampler {
+    id   : GLuint,
+    desc : SamplerDesc,
+}
+
+#[allow(dead_code)]
+impl Sampler {
+
+    pub fn new(desc: &SamplerDesc) -> Sampler {
+        let mut id = 0;
+        unsafe {
+            gl::GenSamplers(1, &mut id);
+
+            for (name, value) in desc.int_parameters() {
+                gl::SamplerParameteri(id, name, value);
+            }
+            gl::SamplerParameterfv(id, gl::TEXTURE_BORDER_COLOR, desc.border_color.as_ptr());
+            if let Some(anisotropy) = desc.clamped_anisotropy() {
+                gl::SamplerParameterf(id, TEXTURE_MAX_ANISOTROPY, anisotropy);
+            }
+        }
+
+        Sampler {
+            id,
+            desc : *desc,
+        }
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    pub fn desc(&self) -> &SamplerDesc {
+        &self.desc
+    }
+
+    /// Binds the sampler to the texture unit with the given index, i.e. 0 for `gl::TEXTURE0`.
+    pub fn bind(&self, unit: u32) {
+        unsafe {
+            gl::BindSampler(unit, self.id);
+        }
+    }
+
+    /// Removes any sampler from the texture unit, such that the texture's own parameters apply again.
+    pub fn unbind(unit: u32) {
+        unsafe {
+            gl::BindSampler(unit, 0);
+        }
+    }
+}
@@ -0,0 +1,411 @@
+extern crate gl;
+
+use std::path::Path;
+
+use image::{self, Image, PixelData, PixelFormat};
+use linalg::Matrix4;
+use super::{Font, Texture};
+use super::bmfont::{DistanceField, FieldType, FontDescription};
+use super::program::{Program, ProgramBuilder};
+use super::sampler::SamplerDesc;
+use super::uniform::TextureUnit;
+
+const VERTEX_SHADER_SRC : &[u8] = b"
+#version 100
+precision highp float;
+uniform mat4 projection;
+uniform mat4 modelView;
+
+attribute vec3 position;
+attribute vec2 uv;
+
+varying vec2 v_uv;
+
+void main() {
+    gl_Position = projection * modelView * vec4(position, 1.0);
+    v_uv = uv;
+}
+\0";
+
+const FRAGMENT_SHADER_SRC : &[u8] = b"
+#version 100
+#extension GL_OES_standard_derivatives : enable
+precision highp float;
+uniform sampler2D atlas;
+uniform vec2 atlas_size;
+uniform bool multi_channel;
+uniform float distance_range;
+
+uniform vec4 color;
+uniform float outline_width;
+uniform vec4 outline_color;
+uniform float glow_width;
+uniform vec4 glow_color;
+uniform vec2 shadow_offset;
+uniform float shadow_softness;
+uniform vec4 shadow_color;
+
+varying vec2 v_uv;
+
+float median(float r, float g, float b) {
+    return max(min(r, g), min(max(r, g), b));
+}
+
+// signed distance to the outline in pixels of the atlas, positive inside the glyph
+float distance_at(vec2 uv) {
+    vec4 texel = texture2D(atlas, uv);
+    float value = multi_channel ? median(texel.r, texel.g, texel.b) : texel.r;
+    return (value - 0.5) * distance_range;
+}
+
+vec4 premultiplied(vec4 color, float coverage) {
+    float alpha = color.a * coverage;
+    return vec4(color.rgb * alpha, alpha);
+}
+
+vec4 over(vec4 top, vec4 bottom) {
+    return top + bottom * (1.0 - top.a);
+}
+
+void main() {
+    float dist = distance_at(v_uv);
+    // the width of a screen pixel in atlas pixels keeps edges sharp at any scale
+    float smoothing = max(fwidth(dist), 0.0001);
+
+    float fill    = clamp(dist / smoothing + 0.5, 0.0, 1.0);
+    float outline = clamp((dist + outline_width) / smoothing + 0.5, 0.0, 1.0);
+    float glow    = glow_width > 0.0 ? smoothstep(-outline_width - glow_width, -outline_width, dist) : 0.0;
+
+    float shadow_distance = distance_at(v_uv - shadow_offset / atlas_size) + outline_width;
+    float shadow = clamp(shadow_distance / (shadow_softness + smoothing) + 0.5, 0.0, 1.0);
+
+    vec4 text   = premultiplied(mix(outline_color, color, fill), outline);
+    vec4 result = over(text, over(premultiplied(glow_color, glow), premultiplied(shadow_color, shadow)));
+
+    gl_FragColor = vec4(result.rgb / max(result.a, 0.0001), result.a);
+}
+\0";
+
+/// Computes a signed distance field of a bitmap, such as the page of a bitmap font. The
+/// coverage is taken from the alpha channel, or from the first channel if there is none.
+///
+/// The result is a Gray image of the same size, with 0.5 on the outlines. Values grow
+/// towards 1 inside and drop towards 0 outside, covering `distance_range` pixels from 0 to 1.
+/// The range should not exceed twice the spacing between glyphs, lest they bleed into each
+/// other.
+pub fn distance_field(image: &Image, distance_range: u32) -> Result<Image, String> {
+    if distance_range == 0 {
+        return Err("the distance range must be at least one pixel".to_string());
+    }
+
+    let width  = image.width() as usize;
+    let height = image.height() as usize;
+
+    let floats = image.to_f32();
+    let data = match *floats.data() {
+        PixelData::F32(ref data) => data,
+        _                        => unreachable!(),
+    };
+
+    let channels = image.format().channels();
+    let channel = if image.format().has_alpha() { channels - 1 } else { 0 };
+    let inside : Vec<bool> = (0..width * height).map(|i| data[i * channels + channel] >= 0.5).collect();
+
+    let to_inside  = squared_distances(width, height, |i| inside[i]);
+    let to_outside = squared_distances(width, height, |i| !inside[i]);
+
+    let values = (0..width * height).map(|i| {
+        // the outline runs between the centers of an inside and an outside pixel
+        let distance = if inside[i] {
+            to_outside[i].sqrt() - 0.5
+        } else {
+            0.5 - to_inside[i].sqrt()
+        };
+        let value = 0.5 + distance / distance_range as f32;
+        (value.clamp(0.0, 1.0) * 255.0).round() as u8
+    }).collect();
+
+    Image::new(image.width(), image.height(), PixelFormat::Gray, PixelData::U8(values))
+}
+
+/// The squared euclidean distance of each pixel to the closest feature pixel, after
+/// Felzenszwalb and Huttenlocher: a one dimensional transform over all columns followed by one
+/// over all rows.
+fn squared_distances<F>(width: usize, height: usize, feature: F) -> Vec<f32> where F: Fn(usize) -> bool {
+    // larger than any distance within the image but far from overflowing
+    let infinity = ((width + height) * (width + height)) as f32 * 4.0;
+
+    let mut grid : Vec<f32> = (0..width * height).map(|i| if feature(i) { 0.0 } else { infinity }).collect();
+
+    let size = width.max(height);
+    let mut line   = vec![0.0; size];
+    let mut result = vec![0.0; size];
+    let mut hull   = vec![0; size];
+    let mut bounds = vec![0.0; size + 1];
+
+    for x in 0..width {
+        for y in 0..height {
+            line[y] = grid[y * width + x];
+        }
+        transform_line(&line[..height], &mut result[..height], &mut hull, &mut bounds);
+        for y in 0..height {
+            grid[y * width + x] = result[y];
+        }
+    }
+
+    for y in 0..height {
+        line[..width].copy_from_slice(&grid[y * width..(y + 1) * width]);
+        transform_line(&line[..width], &mut result[..width], &mut hull, &mut bounds);
+        grid[y * width..(y + 1) * width].copy_from_slice(&result[..width]);
+    }
+
+    grid
+}
+
+/// The one dimensional distance transform of sampled function `f`, the lower envelope of the
+/// parabolas rooted at each sample.
+fn transform_line(f: &[f32], result: &mut [f32], hull: &mut [usize], bounds: &mut [f32]) {
+    let n = f.len();
+    if n == 0 {
+        return;
+    }
+
+    let intersection = |q: usize, p: usize| ((f[q] + (q * q) as f32) - (f[p] + (p * p) as f32)) / (2.0 * q as f32 - 2.0 * p as f32);
+
+    let mut k = 0;
+    hull[0] = 0;
+    bounds[0] = f32::NEG_INFINITY;
+    bounds[1] = f32::INFINITY;
+
+    for q in 1..n {
+        let mut s = intersection(q, hull[k]);
+        while s <= bounds[k] {
+            k -= 1;
+            s = intersection(q, hull[k]);
+        }
+        k += 1;
+        hull[k] = q;
+        bounds[k] = s;
+        bounds[k + 1] = f32::INFINITY;
+    }
+
+    k = 0;
+    for (q, value) in result.iter_mut().enumerate() {
+        while bounds[k + 1] < q as f32 {
+            k += 1;
+        }
+        let distance = q as f32 - hull[k] as f32;
+        *value = distance * distance + f[hull[k]];
+    }
+}
+
+/// A Font whose pages hold signed distance fields instead of coverage, which stays sharp at
+/// any scale when drawn with the SdfShader.
+#[allow(dead_code)]
+pub struct SdfFont {
+    font  : Font,
+    field : DistanceField,
+}
+
+#[allow(dead_code)]
+impl SdfFont {
+
+    pub fn new(font: Font, field: DistanceField) -> SdfFont {
+        SdfFont {
+            font,
+            field,
+        }
+    }
+
+    /// Loads a distance field font made by a generator such as msdf-bmfont-xml, which tells the
+    /// field type and range in the `distanceField` block of the descriptor.
+    pub fn load(fnt_file: &str) -> Result<SdfFont, String> {
+        let font = Font::load(fnt_file)?;
+
+        let field = match font.description().distance_field() {
+            Some(field) => *field,
+            None        => return Err(format!("{} is not a distance field font", fnt_file)),
+        };
+
+        for page in font.pages() {
+            page.set_sampler(&SamplerDesc::new());
+        }
+
+        Ok(SdfFont::new(font, field))
+    }
+
+    /// Turns a bitmap font into a distance field font by computing a distance field of each page
+    /// on the CPU. Pages are images with the top row first, as loaded from files.
+    pub fn from_bitmap(mut description: FontDescription, pages: &[Image], distance_range: u32) -> Result<SdfFont, String> {
+        let mut textures = Vec::new();
+
+        for page in pages {
+            let mut field = distance_field(page, distance_range)?;
+            field.flip_vertical();
+
+            let texture = Texture::from_image(&field);
+            texture.set_sampler(&SamplerDesc::new());
+            textures.push(texture);
+        }
+
+        let field = DistanceField {
+            field_type : FieldType::Sdf,
+            distance_range,
+        };
+        description.set_distance_field(Some(field));
+
+        Ok(SdfFont::new(Font::from_description(description, textures)?, field))
+    }
+
+    /// Loads a bitmap font along with its pages and turns it into a distance field font.
+    pub fn from_bitmap_file(fnt_file: &str, distance_range: u32) -> Result<SdfFont, String> {
+        let description = FontDescription::load(fnt_file)?;
+        let directory = Path::new(fnt_file).parent().unwrap_or_else(|| Path::new(""));
+
+        let mut pages = Vec::new();
+        for page in description.pages() {
+            pages.push(image::load(directory.join(page))?);
+        }
+
+        SdfFont::from_bitmap(description, &pages, distance_range)
+    }
+
+    pub fn font(&self) -> &Font {
+        &self.font
+    }
+
+    pub fn field(&self) -> &DistanceField {
+        &self.field
+    }
+}
+
+/// The look of text drawn by the SdfShader. Widths and offsets are in pixels of the atlas and
+/// limited to half the distance range of the font.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SdfStyle {
+    color           : [f32; 4],
+    outline_width   : f32,
+    outline_color   : [f32; 4],
+    glow_width      : f32,
+    glow_color      : [f32; 4],
+    shadow_offset   : [f32; 2],
+    shadow_softness : f32,
+    shadow_color    : [f32; 4],
+}
+
+impl Default for SdfStyle {
+    fn default() -> SdfStyle {
+        SdfStyle::new()
+    }
+}
+
+#[allow(dead_code)]
+impl SdfStyle {
+
+    /// White text without outline, glow and shadow.
+    pub fn new() -> SdfStyle {
+        SdfStyle {
+            color           : [1.0, 1.0, 1.0, 1.0],
+            outline_width   : 0.0,
+            outline_color   : [0.0, 0.0, 0.0, 0.0],
+            glow_width      : 0.0,
+            glow_color      : [0.0, 0.0, 0.0, 0.0],
+            shadow_offset   : [0.0, 0.0],
+            shadow_softness : 0.0,
+            shadow_color    : [0.0, 0.0, 0.0, 0.0],
+        }
+    }
+
+    pub fn color(mut self, color: [f32; 4]) -> SdfStyle {
+        self.color = color;
+        self
+    }
+
+    /// Draws an outline of the given width around the glyphs.
+    pub fn outline(mut self, width: f32, color: [f32; 4]) -> SdfStyle {
+        self.outline_width = width;
+        self.outline_color = color;
+        self
+    }
+
+    /// Draws a glow fading out over the given width around the glyphs and their outline.
+    pub fn glow(mut self, width: f32, color: [f32; 4]) -> SdfStyle {
+        self.glow_width = width;
+        self.glow_color = color;
+        self
+    }
+
+    /// Draws a shadow behind the glyphs, moved by the offset with x to the right and y up, and
+    /// blurred over the softness.
+    pub fn shadow(mut self, offset: [f32; 2], softness: f32, color: [f32; 4]) -> SdfStyle {
+        self.shadow_offset   = offset;
+        self.shadow_softness = softness;
+        self.shadow_color    = color;
+        self
+    }
+}
+
+/// The built-in shader for distance field text, for meshes made by `Font::mesh` or a
+/// TextLayout. Draw with blending `gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA`.
+#[allow(dead_code)]
+pub struct SdfShader {
+    program : Program,
+}
+
+#[allow(dead_code)]
+impl SdfShader {
+
+    pub fn new() -> Result<SdfShader, String> {
+        let program = ProgramBuilder::new().vertex(VERTEX_SHADER_SRC).fragment(FRAGMENT_SHADER_SRC).build()?;
+        let shader = SdfShader {
+            program,
+        };
+
+        shader.set_style(&SdfStyle::new())?;
+
+        Ok(shader)
+    }
+
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    pub fn set_matrices(&self, projection: &Matrix4, model_view: &Matrix4) -> Result<(), String> {
+        self.program.use_program();
+        self.program.set("projection", projection)?;
+        self.program.set("modelView", model_view)
+    }
+
+    pub fn set_style(&self, style: &SdfStyle) -> Result<(), String> {
+        self.program.use_program();
+        self.program.set("color", &style.color)?;
+        self.program.set("outline_width", &style.outline_width)?;
+        self.program.set("outline_color", &style.outline_color)?;
+        self.program.set("glow_width", &style.glow_width)?;
+        self.program.set("glow_color", &style.glow_color)?;
+        self.program.set("shadow_offset", &style.shadow_offset)?;
+        self.program.set("shadow_softness", &style.shadow_softness)?;
+        self.program.set("shadow_color", &style.shadow_color)
+    }
+
+    /// Binds a page of the font to the given texture unit and sets up the shader to sample it.
+    pub fn set_page(&self, font: &SdfFont, page: usize, unit: u32) -> Result<(), String> {
+        let texture = font.font().pages().get(page).ok_or_else(|| format!("the font has no page {}", page))?;
+        self.set_atlas(texture, font.field(), unit)
+    }
+
+    /// Binds a distance field texture to the given texture unit and sets up the shader to
+    /// sample it.
+    pub fn set_atlas(&self, texture: &Texture, field: &DistanceField, unit: u32) -> Result<(), String> {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + unit);
+        }
+        texture.bind();
+
+        self.program.use_program();
+        self.program.set("atlas", &TextureUnit(unit))?;
+        self.program.set("atlas_size", &[texture.width_as_f32(), texture.height_as_f32()])?;
+        self.program.set("multi_channel", &field.field_type.is_multi_channel())?;
+        self.program.set("distance_range", &(field.distance_range as f32))
+    }
+}
@@ -0,0 +1,134 @@
+extern crate gl;
+
+use linalg::Matrix4;
+use super::VertexBufferObject;
+use super::cubemap::CubeMap;
+use super::primitives;
+use super::program::{Program, ProgramBuilder};
+use super::uniform::TextureUnit;
+
+const VERTEX_SHADER_SRC : &[u8] = b"
+#version 100
+precision highp float;
+uniform mat4 projection;
+uniform mat4 view;
+
+attribute vec3 position;
+
+varying vec3 v_direction;
+
+void main() {
+    v_direction = position;
+    vec4 clip = projection * view * vec4(position, 1.0);
+    // w as z ends up as a depth of 1.0, behind everything else
+    gl_Position = clip.xyww;
+}
+\0";
+
+const FRAGMENT_SHADER_SRC : &[u8] = b"
+#version 100
+precision highp float;
+uniform samplerCube skybox;
+
+varying vec3 v_direction;
+
+void main() {
+    gl_FragColor = textureCube(skybox, v_direction);
+}
+\0";
+
+/// Renders a CubeMap as the background of a scene.
+///
+/// The skybox is a unit cube around the camera drawn with the translation removed from the view
+/// matrix, such that it never comes closer when the camera moves. Its depth is fixed to the far
+/// plane, so it may be drawn first or, to save fill rate, after all opaque geometry.
+#[allow(dead_code)]
+pub struct Skybox {
+    program      : Program,
+    vbo          : VertexBufferObject,
+    vertex_array : gl::types::GLuint,
+}
+
+#[allow(dead_code)]
+impl Skybox {
+
+    pub fn new() -> Result<Skybox, String> {
+        let program = ProgramBuilder::new().vertex(VERTEX_SHADER_SRC).fragment(FRAGMENT_SHADER_SRC).build()?;
+
+        // the cube is centered around the origin already
+        let mesh = primitives::cube();
+
+        let vbo = mesh.to_array_buffer_vbo();
+        let location = program.attrib_location("position")?;
+
+        // a vertex array of its own keeps the attribute setup of the caller untouched
+        let mut vertex_array = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut vertex_array);
+            gl::BindVertexArray(vertex_array);
+            vbo.bind();
+            mesh.attribs().by_name("position")?.attrib_array_pointer(location);
+            gl::BindVertexArray(0);
+        }
+
+        Ok(Skybox {
+            program,
+            vbo,
+            vertex_array,
+        })
+    }
+
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    /// Draws the cube map with the given projection and view matrix. The translation of the
+    /// view matrix is stripped here, so the regular camera matrix can be passed. The cube map
+    /// is bound to the given texture unit. Program, vertex array, depth state and seamless cube
+    /// map filtering are restored afterwards.
+    pub fn draw(&self, cubemap: &CubeMap, projection: &Matrix4, view: &Matrix4, unit: u32) -> Result<(), String> {
+        let mut rotation = Matrix4::new();
+        rotation.copy(view);
+        rotation.strip_translation();
+
+        unsafe {
+            let mut previous_program = 0;
+            let mut previous_vertex_array = 0;
+            let mut previous_depth_func = 0;
+            let mut previous_depth_mask = 0;
+            gl::GetIntegerv(gl::CURRENT_PROGRAM, &mut previous_program);
+            gl::GetIntegerv(gl::VERTEX_ARRAY_BINDING, &mut previous_vertex_array);
+            gl::GetIntegerv(gl::DEPTH_FUNC, &mut previous_depth_func);
+            gl::GetBooleanv(gl::DEPTH_WRITEMASK, &mut previous_depth_mask);
+            let previous_seamless = gl::IsEnabled(gl::TEXTURE_CUBE_MAP_SEAMLESS);
+
+            self.program.use_program();
+            let result = self.program.set("projection", projection)
+                .and_then(|_| self.program.set("view", &rotation))
+                .and_then(|_| self.program.set("skybox", &TextureUnit(unit)));
+
+            if result.is_ok() {
+                gl::ActiveTexture(gl::TEXTURE0 + unit);
+                cubemap.bind();
+
+                // the cube is seen from the inside and lies exactly on the far plane
+                gl::DepthFunc(gl::LEQUAL);
+                gl::DepthMask(gl::FALSE);
+                gl::Enable(gl::TEXTURE_CUBE_MAP_SEAMLESS);
+
+                gl::BindVertexArray(self.vertex_array);
+                self.vbo.draw();
+            }
+
+            gl::BindVertexArray(previous_vertex_array as gl::types::GLuint);
+            gl::DepthFunc(previous_depth_func as gl::types::GLenum);
+            gl::DepthMask(previous_depth_mask);
+            if previous_seamless == gl::FALSE {
+                gl::Disable(gl::TEXTURE_CUBE_MAP_SEAMLESS);
+            }
+            gl::UseProgram(previous_program as gl::types::GLuint);
+
+            result
+        }
+    }
+}
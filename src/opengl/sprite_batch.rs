@@ -0,0 +1,326 @@
+extern crate gl;
+
+use std::mem;
+use std::ptr;
+
+use linalg::Matrix4;
+use super::{AttribArrayBuilder, AttribType, Sprite, Texture};
+use super::program::{Program, ProgramBuilder};
+use super::uniform::TextureUnit;
+
+const VERTEX_SHADER_SRC : &[u8] = b"
+#version 100
+precision highp float;
+uniform mat4 projection;
+
+attribute vec3 position;
+attribute vec2 uv;
+attribute vec4 color;
+
+varying vec2 v_uv;
+varying vec4 v_color;
+
+void main() {
+    gl_Position = projection * vec4(position, 1.0);
+    v_uv = uv;
+    v_color = color;
+}
+\0";
+
+const FRAGMENT_SHADER_SRC : &[u8] = b"
+#version 100
+precision highp float;
+uniform sampler2D atlas;
+
+varying vec2 v_uv;
+varying vec4 v_color;
+
+void main() {
+    gl_FragColor = texture2D(atlas, v_uv) * v_color;
+}
+\0";
+
+/// The floats of a vertex: position, uv and color.
+const VERTEX_SIZE : usize = 3 + 2 + 4;
+
+/// The floats of the four vertices of a quad.
+const QUAD_SIZE : usize = 4 * VERTEX_SIZE;
+
+/// How to draw a Sprite of a texture: where, turned and scaled around its origin, tinted and
+/// at which depth.
+#[allow(dead_code)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SpriteDraw {
+    sprite   : Sprite,
+    position : [f32; 2],
+    origin   : [f32; 2],
+    rotation : f32,
+    scale    : [f32; 2],
+    color    : [f32; 4],
+    depth    : f32,
+}
+
+#[allow(dead_code)]
+impl SpriteDraw {
+
+    /// Draws the sprite in its size in pixels with its top left corner at the origin, untinted
+    /// and in front.
+    pub fn new(sprite: Sprite) -> SpriteDraw {
+        SpriteDraw {
+            sprite,
+            position : [0.0, 0.0],
+            origin   : [0.0, 0.0],
+            rotation : 0.0,
+            scale    : [1.0, 1.0],
+            color    : [1.0, 1.0, 1.0, 1.0],
+            depth    : 0.0,
+        }
+    }
+
+    /// The position of the origin in pixels, from the top left of the viewport.
+    pub fn position(mut self, x: f32, y: f32) -> SpriteDraw {
+        self.position = [x, y];
+        self
+    }
+
+    /// The point the sprite is placed, rotated and scaled around, relative to its size, i.e.
+    /// `origin(0.5, 0.5)` is the center.
+    pub fn origin(mut self, x: f32, y: f32) -> SpriteDraw {
+        self.origin = [x, y];
+        self
+    }
+
+    /// The rotation in radians, clockwise on screen.
+    pub fn rotation(mut self, rotation: f32) -> SpriteDraw {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn scale(mut self, x: f32, y: f32) -> SpriteDraw {
+        self.scale = [x, y];
+        self
+    }
+
+    /// The color the texels are multiplied with.
+    pub fn color(mut self, color: [f32; 4]) -> SpriteDraw {
+        self.color = color;
+        self
+    }
+
+    /// The depth from 0 in front to 1 in the back, which takes effect with depth testing.
+    pub fn depth(mut self, depth: f32) -> SpriteDraw {
+        self.depth = depth;
+        self
+    }
+
+    /// The vertices as position, uv and color, clockwise on screen from the top left.
+    fn vertices(&self, texture: &Texture) -> [f32; QUAD_SIZE] {
+        let sprite = &self.sprite;
+
+        let width  = sprite.width_as_f32() * self.scale[0];
+        let height = sprite.height_as_f32() * self.scale[1];
+        let left   = -self.origin[0] * width;
+        let top    = -self.origin[1] * height;
+
+        let (sin, cos) = self.rotation.sin_cos();
+        let corner = |x: f32, y: f32| [
+            self.position[0] + x * cos - y * sin,
+            self.position[1] + x * sin + y * cos,
+        ];
+
+        // the texture has its origin at the bottom left, the sprite at the top left
+        let uv_left   = sprite.x_as_f32() / texture.width_as_f32();
+        let uv_right  = (sprite.x_as_f32() + sprite.width_as_f32()) / texture.width_as_f32();
+        let uv_top    = (texture.height_as_f32() - sprite.y_as_f32()) / texture.height_as_f32();
+        let uv_bottom = (texture.height_as_f32() - sprite.y_as_f32() - sprite.height_as_f32()) / texture.height_as_f32();
+
+        let corners = [
+            (corner(left,         top),          [uv_left,  uv_top]),
+            (corner(left + width, top),          [uv_right, uv_top]),
+            (corner(left + width, top + height), [uv_right, uv_bottom]),
+            (corner(left,         top + height), [uv_left,  uv_bottom]),
+        ];
+
+        let [r, g, b, a] = self.color;
+        let mut vertices = [0.0; QUAD_SIZE];
+        for (vertex, &(position, uv)) in vertices.chunks_mut(VERTEX_SIZE).zip(&corners) {
+            vertex.copy_from_slice(&[position[0], position[1], -self.depth, uv[0], uv[1], r, g, b, a]);
+        }
+        vertices
+    }
+}
+
+/// Draws many textured quads in pixel coordinates with few draw calls.
+///
+/// Sprites are collected by `draw` and drawn by `flush`, which uploads all of them into a single
+/// dynamic vertex buffer and issues one draw call per texture. Sprites are grouped by texture
+/// for that, keeping their order within each texture, so overlapping translucent sprites of
+/// different textures need distinct depths or separate flushes.
+///
+/// The projection maps pixels with the origin at the top left of the viewport and y going down.
+/// Blending and depth testing are left to the caller, e.g. blending with
+/// `gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA` for translucent sprites.
+#[allow(dead_code)]
+pub struct SpriteBatch {
+    program       : Program,
+    vertex_array  : gl::types::GLuint,
+    vertex_buffer : gl::types::GLuint,
+    index_buffer  : gl::types::GLuint,
+    /// The number of quads the buffers have room for.
+    capacity      : usize,
+    projection    : Matrix4,
+    /// The texture and vertices of each quad drawn since the last flush.
+    quads         : Vec<(gl::types::GLuint, [f32; QUAD_SIZE])>,
+}
+
+#[allow(dead_code)]
+impl SpriteBatch {
+
+    /// Creates a batch for a viewport of the given size in pixels.
+    pub fn new(width: u32, height: u32) -> Result<SpriteBatch, String> {
+        let program = ProgramBuilder::new().vertex(VERTEX_SHADER_SRC).fragment(FRAGMENT_SHADER_SRC).build()?;
+
+        let attribs = AttribArrayBuilder::new()
+            .push("position", 3, AttribType::Position)
+            .push("uv", 2, AttribType::Uv)
+            .push("color", 4, AttribType::ColorRgba)
+            .build();
+
+        let mut vertex_array  = 0;
+        let mut vertex_buffer = 0;
+        let mut index_buffer  = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut vertex_array);
+            gl::GenBuffers(1, &mut vertex_buffer);
+            gl::GenBuffers(1, &mut index_buffer);
+
+            // the attribute pointers and the index buffer are part of the vertex array
+            gl::BindVertexArray(vertex_array);
+            gl::BindBuffer(gl::ARRAY_BUFFER, vertex_buffer);
+            for name in &["position", "uv", "color"] {
+                attribs.by_name(name)?.attrib_array_pointer(program.attrib_location(name)?);
+            }
+            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, index_buffer);
+            gl::BindVertexArray(0);
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+        }
+
+        let mut batch = SpriteBatch {
+            program,
+            vertex_array,
+            vertex_buffer,
+            index_buffer,
+            capacity   : 0,
+            projection : Matrix4::new(),
+            quads      : Vec::new(),
+        };
+
+        batch.resize(width, height);
+        batch.reserve(64);
+
+        Ok(batch)
+    }
+
+    /// Adapts the projection to a new viewport size in pixels.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.projection.orthographic(0.0, width as f32, height as f32, 0.0, 0.0, 1.0);
+    }
+
+    pub fn projection(&self) -> &Matrix4 {
+        &self.projection
+    }
+
+    /// Queues a sprite of the texture to be drawn by the next flush.
+    pub fn draw(&mut self, texture: &Texture, sprite: &SpriteDraw) {
+        self.quads.push((texture.id(), sprite.vertices(texture)));
+    }
+
+    /// The number of sprites queued since the last flush.
+    pub fn len(&self) -> usize {
+        self.quads.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.quads.is_empty()
+    }
+
+    /// Drops the queued sprites without drawing them.
+    pub fn clear(&mut self) {
+        self.quads.clear();
+    }
+
+    /// Draws the queued sprites into the bound framebuffer, using texture unit 0, and returns
+    /// the number of draw calls, which is the number of distinct textures.
+    pub fn flush(&mut self) -> Result<usize, String> {
+        if self.quads.is_empty() {
+            return Ok(0);
+        }
+
+        self.quads.sort_by_key(|&(texture, _)| texture);
+        let quad_count = self.quads.len();
+        self.reserve(quad_count);
+
+        let mut vertices = Vec::with_capacity(quad_count * QUAD_SIZE);
+        for (_, quad) in &self.quads {
+            vertices.extend_from_slice(quad);
+        }
+
+        self.program.use_program();
+        self.program.set("projection", &self.projection)?;
+        self.program.set("atlas", &TextureUnit(0))?;
+
+        let mut draw_calls = 0;
+        unsafe {
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vertex_buffer);
+            // orphaning the old storage keeps the driver from waiting for the last flush
+            gl::BufferData(gl::ARRAY_BUFFER, (self.capacity * QUAD_SIZE * mem::size_of::<f32>()) as gl::types::GLsizeiptr,
+                           ptr::null(), gl::DYNAMIC_DRAW);
+            gl::BufferSubData(gl::ARRAY_BUFFER, 0, (vertices.len() * mem::size_of::<f32>()) as gl::types::GLsizeiptr,
+                              vertices.as_ptr() as *const _);
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+
+            gl::BindVertexArray(self.vertex_array);
+            gl::ActiveTexture(gl::TEXTURE0);
+
+            let mut start = 0;
+            while start < quad_count {
+                let texture = self.quads[start].0;
+                let count = self.quads[start..].iter().take_while(|&&(other, _)| other == texture).count();
+
+                gl::BindTexture(gl::TEXTURE_2D, texture);
+                gl::DrawElements(gl::TRIANGLES, (count * 6) as gl::types::GLsizei, gl::UNSIGNED_INT,
+                                 (start * 6 * mem::size_of::<u32>()) as *const () as *const _);
+
+                draw_calls += 1;
+                start += count;
+            }
+
+            gl::BindVertexArray(0);
+        }
+
+        self.quads.clear();
+
+        Ok(draw_calls)
+    }
+
+    /// Grows the buffers to hold at least the given number of quads.
+    fn reserve(&mut self, quads: usize) {
+        if quads <= self.capacity {
+            return;
+        }
+        self.capacity = quads.next_power_of_two();
+
+        let mut indices : Vec<u32> = Vec::with_capacity(self.capacity * 6);
+        for quad in 0..self.capacity as u32 {
+            let first = quad * 4;
+            indices.extend_from_slice(&[first, first + 1, first + 2, first + 2, first + 3, first]);
+        }
+
+        unsafe {
+            gl::BindVertexArray(self.vertex_array);
+            gl::BufferData(gl::ELEMENT_ARRAY_BUFFER, (indices.len() * mem::size_of::<u32>()) as gl::types::GLsizeiptr,
+                           indices.as_ptr() as *const _, gl::STATIC_DRAW);
+            gl::BindVertexArray(0);
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use linalg::{Matrix3, Matrix4, Vector3, Vector4};
+use std::slice;
+
+/// Packs values into a byte buffer following the std140 layout rules of GLSL uniform blocks.
+///
+/// The members have to be pushed in the order in which they are declared in the block. Every
+/// `push_*` method pads the buffer up to the alignment of the value and returns the offset the
+/// value was written to, which is handy to double-check against `glGetActiveUniformsiv`.
+///
+/// ```glsl
+/// layout(std140) uniform Camera {
+///     mat4 projection;    // offset   0
+///     mat4 view;          // offset  64
+///     vec3 lightDir;      // offset 128
+///     float time;         // offset 140
+/// };
+/// ```
+pub struct Std140Writer {
+    bytes : Vec<u8>,
+}
+
+impl Default for Std140Writer {
+    fn default() -> Std140Writer {
+        Std140Writer::new()
+    }
+}
+
+impl Std140Writer {
+
+    pub fn new() -> Std140Writer {
+        Std140Writer {
+            bytes : Vec::new(),
+        }
+    }
+
+    /// The packed bytes, padded to a multiple of 16 as required for the size of a block.
+    pub fn bytes(&self) -> Vec<u8> {
+        let mut bytes = self.bytes.clone();
+        let padded = round_up(bytes.len(), 16);
+        bytes.resize(padded, 0);
+        bytes
+    }
+
+    /// Current end of the written data, i.e. the offset the next value would be written to if
+    /// it needed no padding.
+    pub fn offset(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Starts over without releasing the memory, e.g. to write the next frame.
+    pub fn clear(&mut self) {
+        self.bytes.clear();
+    }
+
+    fn align(&mut self, alignment: usize) -> usize {
+        let offset = round_up(self.bytes.len(), alignment);
+        self.bytes.resize(offset, 0);
+        offset
+    }
+
+    fn write_floats(&mut self, floats: &[f32]) {
+        for f in floats {
+            self.bytes.extend_from_slice(&f.to_bits().to_le_bytes());
+        }
+    }
+
+    pub fn push_f32(&mut self, value: f32) -> usize {
+        let offset = self.align(4);
+        self.write_floats(&[value]);
+        offset
+    }
+
+    pub fn push_i32(&mut self, value: i32) -> usize {
+        let offset = self.align(4);
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+        offset
+    }
+
+    pub fn push_u32(&mut self, value: u32) -> usize {
+        let offset = self.align(4);
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+        offset
+    }
+
+    /// GLSL `bool`s occupy four bytes in std140.
+    pub fn push_bool(&mut self, value: bool) -> usize {
+        self.push_u32(value as u32)
+    }
+
+    pub fn push_vec2(&mut self, value: [f32; 2]) -> usize {
+        let offset = self.align(8);
+        self.write_floats(&value);
+        offset
+    }
+
+    /// A vec3 is aligned like a vec4, but only occupies 12 bytes, so a following scalar is
+    /// packed into its fourth component.
+    pub fn push_vec3(&mut self, value: &Vector3) -> usize {
+        let offset = self.align(16);
+        self.write_floats(&[value.x, value.y, value.z]);
+        offset
+    }
+
+    pub fn push_vec4(&mut self, value: &Vector4) -> usize {
+        let offset = self.align(16);
+        self.write_floats(&[value.x, value.y, value.z, value.w]);
+        offset
+    }
+
+    /// A mat3 is stored as an array of three vec3 columns, each padded to 16 bytes.
+    pub fn push_mat3(&mut self, value: &Matrix3) -> usize {
+        let offset = self.align(16);
+        let floats = unsafe { slice::from_raw_parts(value.as_ptr(), 9) };
+        for column in floats.chunks(3) {
+            self.align(16);
+            self.write_floats(column);
+        }
+        self.align(16);
+        offset
+    }
+
+    pub fn push_mat4(&mut self, value: &Matrix4) -> usize {
+        let offset = self.align(16);
+        let floats = unsafe { slice::from_raw_parts(value.as_ptr(), 16) };
+        self.write_floats(floats);
+        offset
+    }
+
+    /// Arrays of scalars have an element stride of 16 bytes in std140.
+    pub fn push_f32_array(&mut self, values: &[f32]) -> usize {
+        let offset = self.align(16);
+        for value in values {
+            self.align(16);
+            self.write_floats(&[*value]);
+        }
+        self.align(16);
+        offset
+    }
+
+    pub fn push_i32_array(&mut self, values: &[i32]) -> usize {
+        let offset = self.align(16);
+        for value in values {
+            self.align(16);
+            self.bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        self.align(16);
+        offset
+    }
+
+    pub fn push_vec3_array(&mut self, values: &[Vector3]) -> usize {
+        let offset = self.align(16);
+        for value in values {
+            self.push_vec3(value);
+        }
+        self.align(16);
+        offset
+    }
+
+    pub fn push_vec4_array(&mut self, values: &[Vector4]) -> usize {
+        let offset = self.align(16);
+        for value in values {
+            self.push_vec4(value);
+        }
+        offset
+    }
+
+    pub fn push_mat4_array(&mut self, values: &[Matrix4]) -> usize {
+        let offset = self.align(16);
+        for value in values {
+            self.push_mat4(value);
+        }
+        offset
+    }
+
+    /// Starts a struct member. Structs are aligned to 16 bytes; push the members of the struct
+    /// afterwards and finish it with `end_struct`.
+    pub fn begin_struct(&mut self) -> usize {
+        self.align(16)
+    }
+
+    /// Finishes a struct member by padding its size up to a multiple of 16 bytes.
+    pub fn end_struct(&mut self) {
+        self.align(16);
+    }
+}
+
+fn round_up(value: usize, alignment: usize) -> usize {
+    value.div_ceil(alignment) * alignment
+}
@@ -0,0 +1,91 @@
+extern crate gl;
+
+use std::ptr;
+
+use super::{Texture, TextureFormat};
+
+/// A Texture whose content is replaced frequently, e.g. by video frames or a CPU rasterizer.
+///
+/// New content is written into one of two pixel buffer objects (PBO) and copied from there into
+/// the texture by the driver, asynchronously to the application. The buffers take turns, so
+/// while the graphics card still copies the last frame out of one buffer, the next frame is
+/// written into the other one instead of waiting for the copy to finish.
+#[allow(dead_code)]
+pub struct StreamingTexture {
+    texture : Texture,
+    buffers : [gl::types::GLuint; 2],
+    current : usize,
+    size    : usize,
+}
+
+#[allow(dead_code)]
+impl StreamingTexture {
+
+    pub fn new(width: u32, height: u32, format: TextureFormat) -> Result<StreamingTexture, String> {
+        let texture = Texture::with_format(width, height, format, None)?;
+        let size = format.row_bytes(width) * height as usize;
+
+        let mut buffers = [0; 2];
+        unsafe {
+            gl::GenBuffers(2, buffers.as_mut_ptr());
+            for buffer in &buffers {
+                gl::BindBuffer(gl::PIXEL_UNPACK_BUFFER, *buffer);
+                gl::BufferData(gl::PIXEL_UNPACK_BUFFER, size as gl::types::GLsizeiptr, ptr::null(), gl::STREAM_DRAW);
+            }
+            gl::BindBuffer(gl::PIXEL_UNPACK_BUFFER, 0);
+        }
+
+        Ok(StreamingTexture {
+            texture,
+            buffers,
+            current : 0,
+            size,
+        })
+    }
+
+    /// The texture to draw with, it always shows the content of the last `update`.
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    /// Replaces the whole content with tightly packed pixels laid out as described by the
+    /// format, starting with the bottom row.
+    pub fn update(&mut self, pixels: &[u8]) -> Result<(), String> {
+        if pixels.len() != self.size {
+            return Err(format!("a streaming texture of {} x {} with format {:?} needs {} bytes of pixel data, but got {}",
+                               self.texture.width(), self.texture.height(), self.texture.format(), self.size, pixels.len()));
+        }
+
+        let format = self.texture.format();
+
+        unsafe {
+            gl::BindBuffer(gl::PIXEL_UNPACK_BUFFER, self.buffers[self.current]);
+
+            // orphaning the old storage lets the driver hand out fresh memory if the last copy
+            // out of this buffer has not finished yet
+            gl::BufferData(gl::PIXEL_UNPACK_BUFFER, self.size as gl::types::GLsizeiptr, ptr::null(), gl::STREAM_DRAW);
+            let target = gl::MapBufferRange(gl::PIXEL_UNPACK_BUFFER, 0, self.size as gl::types::GLsizeiptr,
+                                            gl::MAP_WRITE_BIT | gl::MAP_INVALIDATE_BUFFER_BIT) as *mut u8;
+            if target.is_null() {
+                gl::BindBuffer(gl::PIXEL_UNPACK_BUFFER, 0);
+                return Err("could not map the pixel buffer of the streaming texture".to_string());
+            }
+            ptr::copy_nonoverlapping(pixels.as_ptr(), target, self.size);
+            gl::UnmapBuffer(gl::PIXEL_UNPACK_BUFFER);
+
+            // with a bound unpack buffer, the data pointer is an offset into that buffer
+            gl::BindTexture(gl::TEXTURE_2D, self.texture.id());
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, format.row_alignment(self.texture.width()));
+            gl::TexSubImage2D(gl::TEXTURE_2D, 0, 0, 0, self.texture.width() as i32, self.texture.height() as i32,
+                              format.pixel_format(), format.pixel_type(), ptr::null());
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+
+            gl::BindBuffer(gl::PIXEL_UNPACK_BUFFER, 0);
+        }
+
+        self.current = 1 - self.current;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,628 @@
+use super::mesh::{Face, Mesh};
+use super::{AttribArrayBuilder, AttribType, Font};
+use super::rich_text::RichText;
+
+/// How large a TextLayout draws the glyphs of a font.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TextSize {
+    /// The size of the font in output units, usually pixels of an orthographic projection. A
+    /// 32 pixel font laid out with `Pixels(16.0)` is drawn at half its size.
+    Pixels(f32),
+    /// The size of one pixel of the font in world units, like the 0.005 of `Font::mesh`.
+    World(f32),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+    /// Stretches the spaces of each line to fill the width, except for the last line of a
+    /// paragraph.
+    Justify,
+}
+
+/// Arranges text in lines of glyphs, with word wrapping, alignment and line spacing.
+///
+/// The text starts at the origin and goes down, i.e. the top of the first line is at y = 0 and
+/// the following lines have negative y.
+#[allow(dead_code)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TextLayout {
+    size         : TextSize,
+    max_width    : Option<f32>,
+    align        : Align,
+    line_spacing : f32,
+}
+
+/// The area of a single character of the laid out text, spanning the advance of the glyph and
+/// the height of the line. Meant for placing carets and selections.
+#[allow(dead_code)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct GlyphRect {
+    index     : usize,
+    character : char,
+    line      : usize,
+    x         : f32,
+    y         : f32,
+    width     : f32,
+    height    : f32,
+}
+
+#[allow(dead_code)]
+impl GlyphRect {
+
+    /// The index of the character within the chars of the text.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn character(&self) -> char {
+        self.character
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The left edge.
+    pub fn x(&self) -> f32 {
+        self.x
+    }
+
+    /// The top edge.
+    pub fn y(&self) -> f32 {
+        self.y
+    }
+
+    pub fn width(&self) -> f32 {
+        self.width
+    }
+
+    pub fn height(&self) -> f32 {
+        self.height
+    }
+}
+
+/// A line of the laid out text.
+#[allow(dead_code)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TextLine {
+    start  : usize,
+    end    : usize,
+    x      : f32,
+    y      : f32,
+    width  : f32,
+    height : f32,
+}
+
+#[allow(dead_code)]
+impl TextLine {
+
+    /// The index of the first char of the line within the text.
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// The index after the last char of the line. Spaces at a wrap belong to no line.
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    /// The left edge of the first glyph after alignment.
+    pub fn x(&self) -> f32 {
+        self.x
+    }
+
+    /// The top edge.
+    pub fn y(&self) -> f32 {
+        self.y
+    }
+
+    /// The width without trailing spaces.
+    pub fn width(&self) -> f32 {
+        self.width
+    }
+
+    pub fn height(&self) -> f32 {
+        self.height
+    }
+}
+
+/// The extent of laid out text along with its lines and characters.
+#[allow(dead_code)]
+#[derive(Clone, PartialEq, Debug)]
+pub struct TextMetrics {
+    width  : f32,
+    height : f32,
+    lines  : Vec<TextLine>,
+    glyphs : Vec<GlyphRect>,
+}
+
+#[allow(dead_code)]
+impl TextMetrics {
+
+    /// The width of the widest line.
+    pub fn width(&self) -> f32 {
+        self.width
+    }
+
+    /// The distance from the top of the first line to the bottom of the last line.
+    pub fn height(&self) -> f32 {
+        self.height
+    }
+
+    pub fn lines(&self) -> &[TextLine] {
+        &self.lines
+    }
+
+    /// The rectangles of all characters, in text order. Line breaks and spaces dropped at a wrap
+    /// have none.
+    pub fn glyphs(&self) -> &[GlyphRect] {
+        &self.glyphs
+    }
+}
+
+impl Default for TextLayout {
+    fn default() -> TextLayout {
+        TextLayout::new()
+    }
+}
+
+/// A character to be laid out, along with the font and style of its span.
+#[derive(Clone, Copy, Debug)]
+struct Item {
+    character : char,
+    font      : usize,
+    /// Output units per pixel of the font.
+    scale     : f32,
+    color     : [f32; 4],
+    underline : bool,
+    strike    : bool,
+    span      : usize,
+}
+
+/// A quad to be drawn from a page of a font, with positions and uvs both as left, bottom, right
+/// and top.
+#[derive(Clone, Copy, Debug)]
+struct Quad {
+    font      : usize,
+    page      : usize,
+    positions : [f32; 4],
+    uvs       : [f32; 4],
+    color     : [f32; 4],
+}
+
+#[allow(dead_code)]
+impl TextLayout {
+
+    /// A layout in world units like `Font::mesh`, left aligned and without wrapping.
+    pub fn new() -> TextLayout {
+        TextLayout {
+            size         : TextSize::World(0.005),
+            max_width    : None,
+            align        : Align::Left,
+            line_spacing : 1.0,
+        }
+    }
+
+    pub fn size(mut self, size: TextSize) -> TextLayout {
+        self.size = size;
+        self
+    }
+
+    /// Wraps lines at spaces such that they are no wider than the given width. Words wider than
+    /// that are broken between characters.
+    pub fn max_width(mut self, max_width: f32) -> TextLayout {
+        self.max_width = Some(max_width);
+        self
+    }
+
+    pub fn align(mut self, align: Align) -> TextLayout {
+        self.align = align;
+        self
+    }
+
+    /// The distance between lines as a multiple of the font's line height.
+    pub fn line_spacing(mut self, line_spacing: f32) -> TextLayout {
+        self.line_spacing = line_spacing;
+        self
+    }
+
+    /// Output units per pixel of the font.
+    pub fn scale(&self, font: &Font) -> f32 {
+        match self.size {
+            TextSize::Pixels(size) => {
+                let description = font.description();
+                let font_size = match description.info().size.abs() {
+                    0    => description.common().line_height,
+                    size => size as u32,
+                };
+                size / font_size.max(1) as f32
+            },
+            TextSize::World(scale) => scale,
+        }
+    }
+
+    pub fn measure(&self, font: &Font, text: &str) -> TextMetrics {
+        self.arrange(&[font], &self.plain_items(font, text)).0
+    }
+
+    /// Builds a mesh with one textured quad per visible glyph, on all pages of the font.
+    pub fn mesh(&self, font: &Font, text: &str) -> Mesh {
+        let mut mesh = Font::empty_mesh();
+        for quad in self.arrange(&[font], &self.plain_items(font, text)).1 {
+            Font::push_quad(&mut mesh, quad.positions, quad.uvs);
+        }
+        mesh
+    }
+
+    /// Builds one mesh per page of the font like `mesh`.
+    pub fn page_meshes(&self, font: &Font, text: &str) -> Vec<Mesh> {
+        let mut meshes : Vec<Mesh> = font.pages().iter().map(|_| Font::empty_mesh()).collect();
+        for quad in self.arrange(&[font], &self.plain_items(font, text)).1 {
+            Font::push_quad(&mut meshes[quad.page], quad.positions, quad.uvs);
+        }
+        meshes
+    }
+
+    /// Measures rich text, whose spans select their font by index into the given fonts.
+    pub fn measure_rich(&self, fonts: &[&Font], text: &RichText) -> Result<TextMetrics, String> {
+        let items = self.rich_items(fonts, text)?;
+        Ok(self.arrange(fonts, &items).0)
+    }
+
+    /// Builds the meshes of rich text, indexed by font and then by page of that font. Next to
+    /// position and uv, each vertex carries the color of its span as `AttribType::ColorRgba`.
+    ///
+    /// Underlines and strikethroughs are drawn with the `_` and `-` glyphs of the span's font,
+    /// stretched over the span. Fonts without these glyphs draw no decoration.
+    pub fn rich_meshes(&self, fonts: &[&Font], text: &RichText) -> Result<Vec<Vec<Mesh>>, String> {
+        let items = self.rich_items(fonts, text)?;
+
+        let mut meshes : Vec<Vec<Mesh>> = fonts.iter()
+            .map(|font| font.pages().iter().map(|_| colored_mesh()).collect())
+            .collect();
+
+        for quad in self.arrange(fonts, &items).1 {
+            push_colored_quad(&mut meshes[quad.font][quad.page], &quad);
+        }
+
+        Ok(meshes)
+    }
+
+    fn plain_items(&self, font: &Font, text: &str) -> Vec<Item> {
+        let scale = self.scale(font);
+
+        text.chars().map(|character| Item {
+            character,
+            font      : 0,
+            scale,
+            color     : [1.0; 4],
+            underline : false,
+            strike    : false,
+            span      : 0,
+        }).collect()
+    }
+
+    fn rich_items(&self, fonts: &[&Font], text: &RichText) -> Result<Vec<Item>, String> {
+        if fonts.is_empty() {
+            return Err("rich text needs at least one font".to_string());
+        }
+
+        let mut items = Vec::new();
+
+        for (index, span) in text.spans().iter().enumerate() {
+            let style = span.style();
+            let font = fonts.get(style.get_font())
+                .ok_or_else(|| format!("span {} uses font {}, but there are {} fonts", index, style.get_font(), fonts.len()))?;
+            let scale = self.scale(font) * style.get_scale();
+
+            items.extend(span.text().chars().map(|character| Item {
+                character,
+                font      : style.get_font(),
+                scale,
+                color     : style.get_color(),
+                underline : style.is_underlined(),
+                strike    : style.is_struck(),
+                span      : index,
+            }));
+        }
+
+        Ok(items)
+    }
+
+    /// The advance of the item after the previous one, including kerning within the same font.
+    fn advance(fonts: &[&Font], previous: Option<&Item>, item: &Item) -> f32 {
+        let font = fonts[item.font];
+        let glyph = match font.glyph(item.character) {
+            Some(glyph) => glyph,
+            None        => return 0.0,
+        };
+
+        let kerning = previous.filter(|previous| previous.font == item.font)
+            .and_then(|previous| font.glyph(previous.character))
+            .map(|previous| font.description().kerning(previous.id, glyph.id))
+            .unwrap_or(0);
+
+        (glyph.xadvance + kerning) as f32 * item.scale
+    }
+
+    /// Splits the items into lines as start index, end index and whether the line ends a
+    /// paragraph.
+    fn break_lines(&self, fonts: &[&Font], items: &[Item]) -> Vec<(usize, usize, bool)> {
+        let mut lines = Vec::new();
+
+        let mut start = 0;
+        let mut index = 0;
+        let mut x = 0.0;
+        let mut previous = None;
+        let mut last_space = None;
+
+        while index < items.len() {
+            let character = items[index].character;
+
+            if character == '\n' {
+                lines.push((start, index, true));
+                index += 1;
+                start = index;
+                x = 0.0;
+                previous = None;
+                last_space = None;
+                continue;
+            }
+
+            let advance = TextLayout::advance(fonts, previous, &items[index]);
+
+            if let Some(max_width) = self.max_width {
+                if character != ' ' && index > start && x + advance > max_width {
+                    // break at the last space, or within the word if it fills the whole line
+                    let (end, next) = match last_space {
+                        Some(space) => (space, space + 1),
+                        None        => (index, index),
+                    };
+                    lines.push((start, end, false));
+
+                    start = next;
+                    while start < items.len() && items[start].character == ' ' {
+                        start += 1;
+                    }
+                    index = start;
+                    x = 0.0;
+                    previous = None;
+                    last_space = None;
+                    continue;
+                }
+            }
+
+            // a wrap drops the whole run of spaces
+            if character == ' ' && (index == start || items[index - 1].character != ' ') {
+                last_space = Some(index);
+            }
+
+            x += advance;
+            previous = Some(&items[index]);
+            index += 1;
+        }
+
+        lines.push((start, items.len(), true));
+        lines
+    }
+
+    /// The line height and base of a line in output units, the largest of all its items. An
+    /// empty line takes them from its line break, or from the first font if there is none.
+    fn line_extent(&self, fonts: &[&Font], items: &[Item], start: usize, end: usize) -> (f32, f32) {
+        let extent = |font: &Font, scale: f32| {
+            let common = font.description().common();
+            (common.line_height as f32 * scale, common.base as f32 * scale)
+        };
+
+        if start == end {
+            return match items.get(start).or_else(|| items.last()) {
+                Some(item) => extent(fonts[item.font], item.scale),
+                None       => extent(fonts[0], self.scale(fonts[0])),
+            };
+        }
+
+        items[start..end].iter()
+            .map(|item| extent(fonts[item.font], item.scale))
+            .fold((0.0, 0.0), |(height, base), (h, b)| (f32::max(height, h), f32::max(base, b)))
+    }
+
+    fn arrange(&self, fonts: &[&Font], items: &[Item]) -> (TextMetrics, Vec<Quad>) {
+        let breaks = self.break_lines(fonts, items);
+
+        // the width of each line without trailing spaces
+        let widths : Vec<f32> = breaks.iter().map(|&(start, end, _)| {
+            let mut previous = None;
+            let mut width = 0.0;
+            for item in &items[start..trimmed_end(items, start, end)] {
+                width += TextLayout::advance(fonts, previous, item);
+                previous = Some(item);
+            }
+            width
+        }).collect();
+
+        let widest = widths.iter().cloned().fold(0.0, f32::max);
+        let box_width = self.max_width.unwrap_or(widest);
+
+        let mut lines  = Vec::new();
+        let mut glyphs = Vec::new();
+        let mut quads  = Vec::new();
+        let mut top    = 0.0;
+
+        for (number, (&(start, end, last), &width)) in breaks.iter().zip(&widths).enumerate() {
+            let (line_height, line_base) = self.line_extent(fonts, items, start, end);
+            let trimmed = trimmed_end(items, start, end);
+
+            let spaces = items[start..trimmed].iter().filter(|item| item.character == ' ').count();
+            let (offset, stretch) = match self.align {
+                Align::Left                          => (0.0, 0.0),
+                Align::Center                        => ((box_width - width) * 0.5, 0.0),
+                Align::Right                         => (box_width - width, 0.0),
+                Align::Justify if last || spaces == 0 => (0.0, 0.0),
+                Align::Justify                       => (0.0, (box_width - width).max(0.0) / spaces as f32),
+            };
+
+            let mut x = offset;
+            let mut previous : Option<&Item> = None;
+            // the left and right edge of each item, for decorations
+            let mut extents = Vec::with_capacity(end - start);
+
+            for (index, item) in items.iter().enumerate().take(end).skip(start) {
+                let font = fonts[item.font];
+                let scale = item.scale;
+                let glyph = match font.glyph(item.character) {
+                    Some(glyph) => glyph,
+                    None        => {
+                        extents.push((x, x));
+                        continue;
+                    },
+                };
+
+                let kerning = previous.filter(|previous| previous.font == item.font)
+                    .and_then(|previous| font.glyph(previous.character))
+                    .map(|previous| font.description().kerning(previous.id, glyph.id))
+                    .unwrap_or(0);
+                x += kerning as f32 * scale;
+                previous = Some(item);
+
+                let mut advance = glyph.xadvance as f32 * scale;
+                if item.character == ' ' && index < trimmed {
+                    advance += stretch;
+                }
+
+                if glyph.width > 0 && glyph.height > 0 {
+                    // smaller glyphs drop to share the baseline with the largest of the line
+                    let drop    = line_base - font.description().common().base as f32 * scale;
+                    let left    = x + glyph.xoffset as f32 * scale;
+                    let top     = top - drop - glyph.yoffset as f32 * scale;
+                    let bottom  = top - glyph.height as f32 * scale;
+                    let right   = left + glyph.width as f32 * scale;
+                    quads.push(Quad {
+                        font      : item.font,
+                        page      : glyph.page as usize,
+                        positions : [left, bottom, right, top],
+                        uvs       : font.uvs(glyph),
+                        color     : item.color,
+                    });
+                }
+
+                glyphs.push(GlyphRect {
+                    index,
+                    character : item.character,
+                    line      : number,
+                    x,
+                    y         : top,
+                    width     : advance,
+                    height    : line_height,
+                });
+
+                extents.push((x, x + advance));
+                x += advance;
+            }
+
+            TextLayout::decorate(fonts, &items[start..trimmed], &extents, (top, line_base), &mut quads);
+
+            lines.push(TextLine {
+                start,
+                end,
+                x      : offset,
+                y      : top,
+                width  : if stretch > 0.0 { box_width } else { width },
+                height : line_height,
+            });
+
+            top -= line_height * self.line_spacing;
+        }
+
+        let last = lines.last().expect("a text has at least one line");
+        let metrics = TextMetrics {
+            width  : lines.iter().map(|line| line.width).fold(0.0, f32::max),
+            height : last.height - last.y,
+            lines,
+            glyphs,
+        };
+
+        (metrics, quads)
+    }
+
+    /// Adds underline and strikethrough quads for the runs of decorated items of a line, given
+    /// the horizontal extent of each item and the top and base of the line.
+    fn decorate(fonts: &[&Font], items: &[Item], extents: &[(f32, f32)], line: (f32, f32), quads: &mut Vec<Quad>) {
+        let (line_top, line_base) = line;
+        let mut start = 0;
+
+        while start < items.len() {
+            let span = items[start].span;
+            let end = start + items[start..].iter().take_while(|item| item.span == span).count();
+
+            let item = &items[start];
+            let decorations = [(item.underline, '_'), (item.strike, '-')];
+
+            for &(enabled, character) in &decorations {
+                if !enabled {
+                    continue;
+                }
+
+                let font = fonts[item.font];
+                let glyph = match font.description().glyph(character as u32) {
+                    Some(glyph) if glyph.width > 0 && glyph.height > 0 => glyph,
+                    _ => continue,
+                };
+
+                // stretch the middle column of the glyph from the start to the end of the run
+                let drop   = line_base - font.description().common().base as f32 * item.scale;
+                let top    = line_top - drop - glyph.yoffset as f32 * item.scale;
+                let bottom = top - glyph.height as f32 * item.scale;
+                let [uv_left, uv_bottom, uv_right, uv_top] = font.uvs(glyph);
+                let uv_middle = (uv_left + uv_right) * 0.5;
+
+                quads.push(Quad {
+                    font      : item.font,
+                    page      : glyph.page as usize,
+                    positions : [extents[start].0, bottom, extents[end - 1].1, top],
+                    uvs       : [uv_middle, uv_bottom, uv_middle, uv_top],
+                    color     : item.color,
+                });
+            }
+
+            start = end;
+        }
+    }
+}
+
+/// The end of the line from start to end without its trailing spaces.
+fn trimmed_end(items: &[Item], start: usize, end: usize) -> usize {
+    end - items[start..end].iter().rev().take_while(|item| item.character == ' ').count()
+}
+
+fn colored_mesh() -> Mesh {
+    let array = AttribArrayBuilder::new()
+        .push("position", 3, AttribType::Position)
+        .push("uv", 2, AttribType::Uv)
+        .push("color", 4, AttribType::ColorRgba)
+        .build();
+
+    Mesh::empty(array)
+}
+
+fn push_colored_quad(mesh: &mut Mesh, quad: &Quad) {
+    let [left, bottom, right, top] = quad.positions;
+    let [uv_left, uv_bottom, uv_right, uv_top] = quad.uvs;
+    let [r, g, b, a] = quad.color;
+
+    let vertex_count = mesh.vertex_count();
+
+    mesh.push_vertices( vec![
+        vec![left,  bottom, 0.0,  uv_left,  uv_bottom,  r, g, b, a],
+        vec![right, bottom, 0.0,  uv_right, uv_bottom,  r, g, b, a],
+        vec![right, top,    0.0,  uv_right, uv_top,     r, g, b, a],
+        vec![left,  top,    0.0,  uv_left,  uv_top,     r, g, b, a],
+    ]);
+
+    mesh.push_faces( vec![
+        Face::new(vertex_count,     vertex_count + 1, vertex_count + 2),
+        Face::new(vertex_count + 2, vertex_count + 3, vertex_count),
+    ]);
+}
@@ -0,0 +1,112 @@
+extern crate gl;
+
+use image::Image;
+use super::{create_layered_texture, layer_pixels, texture_pixels, upload_layer, TextureFormat};
+use super::sampler::SamplerDesc;
+
+/// Wrapper for `TEXTURE_3D`, a volume of texels sampled with three coordinates and filtered in
+/// all three directions, e.g. for volumetric data or color grading lookup tables.
+#[allow(dead_code)]
+pub struct Texture3D {
+    id     : gl::types::GLuint,
+    width  : u32,
+    height : u32,
+    depth  : u32,
+    format : TextureFormat,
+}
+
+#[allow(dead_code)]
+impl Texture3D {
+
+    /// Allocates a volume, to be filled with `upload_slice`.
+    pub fn new(width: u32, height: u32, depth: u32, format: TextureFormat) -> Result<Texture3D, String> {
+        Texture3D::create(width, height, depth, format, None)
+    }
+
+    /// Creates a volume from raw, tightly packed data laid out as described by the format. The
+    /// data holds `depth` slices, each made of `height` rows of `width` texels.
+    pub fn from_raw(width: u32, height: u32, depth: u32, format: TextureFormat, data: &[u8]) -> Result<Texture3D, String> {
+        Texture3D::create(width, height, depth, format, Some(data))
+    }
+
+    /// Creates a volume with one slice per image, the first image at depth 0.
+    pub fn from_images(images: &[Image]) -> Result<Texture3D, String> {
+        let (width, height, format, pixels) = layer_pixels(images)?;
+        Texture3D::create(width, height, images.len() as u32, format, Some(&pixels))
+    }
+
+    fn create(width: u32, height: u32, depth: u32, format: TextureFormat, data: Option<&[u8]>) -> Result<Texture3D, String> {
+        let id = create_layered_texture(gl::TEXTURE_3D, width, height, depth, format, data)?;
+
+        Ok(Texture3D {
+            id,
+            width,
+            height,
+            depth,
+            format,
+        })
+    }
+
+    pub fn id(&self) -> gl::types::GLuint {
+        self.id
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    /// Replaces the slice at the given depth with tightly packed pixels.
+    pub fn upload_slice(&self, z: u32, pixels: &[u8]) -> Result<(), String> {
+        upload_layer(gl::TEXTURE_3D, self.id, (self.width, self.height, self.depth), self.format, z, pixels)
+    }
+
+    /// Replaces the slice at the given depth with an image of the same size and format.
+    pub fn upload_slice_image(&self, z: u32, image: &Image) -> Result<(), String> {
+        let (format, pixels) = texture_pixels(image);
+        if format != self.format || image.width() != self.width || image.height() != self.height {
+            return Err(format!("a {} x {} image in {:?} does not fit into a {} x {} volume in {:?}",
+                               image.width(), image.height(), format, self.width, self.height, self.format));
+        }
+        self.upload_slice(z, &pixels)
+    }
+
+    pub fn set_sampler(&self, desc: &SamplerDesc) {
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_3D, self.id);
+            desc.apply(gl::TEXTURE_3D);
+            gl::BindTexture(gl::TEXTURE_3D, 0);
+        }
+    }
+
+    pub fn generate_mipmaps(&self) {
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_3D, self.id);
+            gl::GenerateMipmap(gl::TEXTURE_3D);
+            gl::BindTexture(gl::TEXTURE_3D, 0);
+        }
+    }
+
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_3D, self.id);
+        }
+    }
+
+    pub fn unbind() {
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_3D, 0);
+        }
+    }
+}
@@ -0,0 +1,135 @@
+extern crate gl;
+
+use std::path::Path;
+
+use image::{pnm, Image};
+use image::pnm::PnmImage;
+use super::{create_layered_texture, layer_pixels, texture_pixels, upload_layer, TextureFormat};
+use super::sampler::SamplerDesc;
+
+/// Wrapper for `TEXTURE_2D_ARRAY`, a stack of equally sized 2d layers that a shader selects by
+/// index, e.g. the ground materials of a terrain. Other than with a 3d texture, there is no
+/// filtering between layers.
+#[allow(dead_code)]
+pub struct TextureArray {
+    id     : gl::types::GLuint,
+    width  : u32,
+    height : u32,
+    layers : u32,
+    format : TextureFormat,
+}
+
+#[allow(dead_code)]
+impl TextureArray {
+
+    /// Allocates an array with the given number of layers, to be filled with `upload_layer`.
+    pub fn new(width: u32, height: u32, layers: u32, format: TextureFormat) -> Result<TextureArray, String> {
+        let id = create_layered_texture(gl::TEXTURE_2D_ARRAY, width, height, layers, format, None)?;
+
+        Ok(TextureArray {
+            id,
+            width,
+            height,
+            layers,
+            format,
+        })
+    }
+
+    /// Creates an array with one layer per image. As with `Texture::from_image`, the first row
+    /// of each image ends up at the bottom.
+    pub fn from_images(images: &[Image]) -> Result<TextureArray, String> {
+        let (width, height, format, pixels) = layer_pixels(images)?;
+        let layers = images.len() as u32;
+        let id = create_layered_texture(gl::TEXTURE_2D_ARRAY, width, height, layers, format, Some(&pixels))?;
+
+        Ok(TextureArray {
+            id,
+            width,
+            height,
+            layers,
+            format,
+        })
+    }
+
+    /// Creates an array from decoded Netpbm images, flipped such that their origin is at the
+    /// bottom left like with `Texture::from_pnm_file`.
+    pub fn from_pnm_images(images: &[PnmImage]) -> Result<TextureArray, String> {
+        let mut converted = Vec::with_capacity(images.len());
+        for image in images {
+            let mut image = image.to_image()?;
+            image.flip_vertical();
+            converted.push(image);
+        }
+        TextureArray::from_images(&converted)
+    }
+
+    /// Loads one layer per Netpbm file.
+    pub fn from_pnm_files<P: AsRef<Path>>(filenames: &[P]) -> Result<TextureArray, String> {
+        let images = filenames.iter().map(pnm::load).collect::<Result<Vec<PnmImage>, String>>()?;
+        TextureArray::from_pnm_images(&images)
+    }
+
+    pub fn id(&self) -> gl::types::GLuint {
+        self.id
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn layers(&self) -> u32 {
+        self.layers
+    }
+
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    /// Replaces a single layer with tightly packed pixels laid out as described by the format.
+    pub fn upload_layer(&self, layer: u32, pixels: &[u8]) -> Result<(), String> {
+        upload_layer(gl::TEXTURE_2D_ARRAY, self.id, (self.width, self.height, self.layers), self.format, layer, pixels)
+    }
+
+    /// Replaces a single layer with an image of the same size and format as the array.
+    pub fn upload_layer_image(&self, layer: u32, image: &Image) -> Result<(), String> {
+        let (format, pixels) = texture_pixels(image);
+        if format != self.format || image.width() != self.width || image.height() != self.height {
+            return Err(format!("a {} x {} image in {:?} does not fit into a {} x {} array in {:?}",
+                               image.width(), image.height(), format, self.width, self.height, self.format));
+        }
+        self.upload_layer(layer, &pixels)
+    }
+
+    pub fn set_sampler(&self, desc: &SamplerDesc) {
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D_ARRAY, self.id);
+            desc.apply(gl::TEXTURE_2D_ARRAY);
+            gl::BindTexture(gl::TEXTURE_2D_ARRAY, 0);
+        }
+    }
+
+    /// Computes the mipmap levels of all layers.
+    pub fn generate_mipmaps(&self) {
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D_ARRAY, self.id);
+            gl::GenerateMipmap(gl::TEXTURE_2D_ARRAY);
+            gl::BindTexture(gl::TEXTURE_2D_ARRAY, 0);
+        }
+    }
+
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D_ARRAY, self.id);
+        }
+    }
+
+    pub fn unbind() {
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D_ARRAY, 0);
+        }
+    }
+}
@@ -0,0 +1,286 @@
+extern crate ab_glyph;
+extern crate gl;
+
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::Path;
+
+use self::ab_glyph::{Font as OutlineFont, FontArc, GlyphId, PxScale, ScaleFont};
+use super::{Font, Texture, TextureFormat};
+use super::bmfont::{FontCommon, FontDescription, FontInfo, Glyph};
+
+/// A TrueType or OpenType font rasterized at a fixed pixel size into a glyph atlas at runtime.
+///
+/// Glyphs are rasterized on demand by `prepare`, packed into shelves of a single page and
+/// described like a BMFont, such that the resulting Font works with `Font::mesh` and
+/// TextLayout. When the page is full it doubles its height, up to the maximum texture size.
+///
+/// The page is a single channel texture read as white with the coverage in alpha, so text is
+/// drawn with blending `gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA`.
+#[allow(dead_code)]
+pub struct TrueTypeFont {
+    outlines : FontArc,
+    scale    : PxScale,
+    font     : Font,
+    /// The atlas with the top row first, mirroring the page texture.
+    pixels   : Vec<u8>,
+    padding  : u32,
+    shelf    : Shelf,
+}
+
+/// The free space of the atlas: the position of the next glyph on the current shelf and the
+/// height of that shelf.
+#[derive(Clone, Copy, Debug)]
+struct Shelf {
+    x      : u32,
+    y      : u32,
+    height : u32,
+}
+
+#[allow(dead_code)]
+impl TrueTypeFont {
+
+    /// Loads a font file, to be rasterized with the given size in pixels from descender to
+    /// ascender.
+    pub fn load<P: AsRef<Path>>(filename: P, size: f32) -> Result<TrueTypeFont, String> {
+        let path = filename.as_ref();
+
+        let mut file = File::open(path).map_err(|why| format!("couldn't open {}: {}", path.display(), why))?;
+        let mut data = Vec::new();
+        file.read_to_end(&mut data).map_err(|why| format!("couldn't read {}: {}", path.display(), why))?;
+
+        let mut font = TrueTypeFont::from_bytes(data, size).map_err(|why| format!("couldn't load {}: {}", path.display(), why))?;
+        if let Some(stem) = path.file_stem() {
+            font.font.description.info_mut().face = stem.to_string_lossy().into_owned();
+        }
+
+        Ok(font)
+    }
+
+    /// Creates a font from the contents of a font file, to be rasterized with the given size in
+    /// pixels. The glyph for missing characters is rasterized right away and serves
+    /// as the fallback glyph of the Font.
+    pub fn from_bytes(data: Vec<u8>, size: f32) -> Result<TrueTypeFont, String> {
+        if !size.is_finite() || size < 1.0 {
+            return Err(format!("invalid font size {}", size));
+        }
+
+        let outlines = FontArc::try_from_vec(data).map_err(|why| format!("invalid font: {}", why))?;
+        let scale = PxScale::from(size);
+        let scaled = outlines.as_scaled(scale);
+
+        // start with room for about a hundred glyphs, clamped before rounding up to a power of two
+        // such that huge sizes cannot overflow
+        let side = ((size * 10.0).min(1024.0) as u32).next_power_of_two().clamp(64, 1024);
+
+        let padding = 1;
+        let info = FontInfo {
+            size      : size.round() as i32,
+            unicode   : true,
+            stretch_h : 100,
+            smooth    : true,
+            aa        : 1,
+            padding   : [padding; 4],
+            ..FontInfo::default()
+        };
+        let common = FontCommon {
+            line_height : (scaled.height() + scaled.line_gap()).ceil() as u32,
+            base        : scaled.ascent().ceil() as u32,
+            scale_w     : side,
+            scale_h     : side,
+            pages       : 1,
+            ..FontCommon::default()
+        };
+
+        let mut description = FontDescription::new(info, common);
+        description.set_page(0, String::new())?;
+
+        let page = TrueTypeFont::page_texture(side, side, &vec![0; (side * side) as usize])?;
+
+        let mut font = TrueTypeFont {
+            outlines,
+            scale,
+            font     : Font::from_description(description, vec![page])?,
+            pixels   : vec![0; (side * side) as usize],
+            padding,
+            shelf    : Shelf { x: padding, y: padding, height: 0 },
+        };
+
+        font.add_glyph(0, GlyphId(0))?;
+        font.font.fallback = Some(0);
+
+        Ok(font)
+    }
+
+    /// Rasterizes all characters of the text that are not in the atlas yet. Characters the font
+    /// has no glyph for are left to the fallback glyph.
+    pub fn prepare(&mut self, text: &str) -> Result<(), String> {
+        for character in text.chars() {
+            if character.is_control() || self.font.description.glyph(character as u32).is_some() {
+                continue;
+            }
+
+            let glyph_id = self.outlines.glyph_id(character);
+            if glyph_id.0 != 0 {
+                self.add_glyph(character as u32, glyph_id)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Rasterizes the characters of the text if needed and builds a mesh like `Font::mesh`.
+    pub fn mesh(&mut self, text: &str) -> Result<super::mesh::Mesh, String> {
+        self.prepare(text)?;
+        Ok(self.font.mesh(text))
+    }
+
+    /// The Font made of the glyphs rasterized so far, for `Font::mesh` or a TextLayout.
+    pub fn font(&self) -> &Font {
+        &self.font
+    }
+
+    /// The texture of the glyph atlas, which is replaced whenever the atlas grows.
+    pub fn atlas(&self) -> &Texture {
+        self.font.tex()
+    }
+
+    /// The size in pixels from descender to ascender the glyphs are rasterized with.
+    pub fn size(&self) -> f32 {
+        self.scale.y
+    }
+
+    fn add_glyph(&mut self, id: u32, glyph_id: GlyphId) -> Result<(), String> {
+        let outlines = self.outlines.clone();
+        let scaled = outlines.as_scaled(self.scale);
+        let glyph = glyph_id.with_scale_and_position(self.scale, ab_glyph::point(0.0, scaled.ascent()));
+
+        let mut entry = Glyph {
+            id,
+            xadvance : scaled.h_advance(glyph_id).round() as i32,
+            chnl     : 15,
+            ..Glyph::default()
+        };
+
+        if let Some(outline) = outlines.outline_glyph(glyph) {
+            // bounds are relative to the top of the line, with y pointing down
+            let bounds = outline.px_bounds();
+            let width  = bounds.width() as u32;
+            let height = bounds.height() as u32;
+            let (x, y) = self.allocate(width, height)?;
+
+            let atlas_width = self.font.description.common().scale_w;
+            let pixels = &mut self.pixels;
+            outline.draw(|gx, gy, value| {
+                if gx < width && gy < height {
+                    pixels[((y + gy) * atlas_width + x + gx) as usize] = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+                }
+            });
+
+            if width > 0 && height > 0 {
+                // the texture has its origin at the bottom left
+                let mut flipped = Vec::with_capacity((width * height) as usize);
+                for row in (y..y + height).rev() {
+                    let start = (row * atlas_width + x) as usize;
+                    flipped.extend_from_slice(&self.pixels[start..start + width as usize]);
+                }
+                let page = self.font.tex();
+                page.update_region(x, page.height() - y - height, width, height, &flipped)?;
+            }
+
+            entry.x       = x;
+            entry.y       = y;
+            entry.width   = width;
+            entry.height  = height;
+            entry.xoffset = bounds.min.x as i32;
+            entry.yoffset = bounds.min.y as i32;
+        }
+
+        // kerning against all characters known so far, in both orders
+        if id != 0 {
+            let others : Vec<u32> = self.font.description.glyphs().keys().cloned().filter(|&other| other != 0).collect();
+
+            for other in others {
+                let other_id = match ::std::char::from_u32(other) {
+                    Some(character) => outlines.glyph_id(character),
+                    None            => continue,
+                };
+
+                for &(first, second, amount) in &[(other, id, scaled.kern(other_id, glyph_id)),
+                                                  (id, other, scaled.kern(glyph_id, other_id))] {
+                    if amount.round() != 0.0 {
+                        self.font.description.insert_kerning(first, second, amount.round() as i32);
+                    }
+                }
+            }
+        }
+
+        self.font.description.insert_glyph(entry);
+
+        Ok(())
+    }
+
+    /// Finds room for a glyph of the given size on the shelves, growing the atlas if needed, and
+    /// returns its position from the top left.
+    fn allocate(&mut self, width: u32, height: u32) -> Result<(u32, u32), String> {
+        let padding     = self.padding;
+        let atlas_width = self.font.description.common().scale_w;
+
+        if width + 2 * padding > atlas_width {
+            return Err(format!("a glyph of {} pixels does not fit into the atlas of {} pixels", width, atlas_width));
+        }
+
+        if self.shelf.x + width + padding > atlas_width {
+            self.shelf = Shelf {
+                x      : padding,
+                y      : self.shelf.y + self.shelf.height + padding,
+                height : 0,
+            };
+        }
+
+        while self.shelf.y + height + padding > self.font.description.common().scale_h {
+            self.grow()?;
+        }
+
+        let position = (self.shelf.x, self.shelf.y);
+        self.shelf.x += width + padding;
+        self.shelf.height = self.shelf.height.max(height);
+
+        Ok(position)
+    }
+
+    /// Doubles the height of the atlas. Glyph positions count from the top and stay valid.
+    fn grow(&mut self) -> Result<(), String> {
+        let width  = self.font.description.common().scale_w;
+        let height = self.font.description.common().scale_h * 2;
+
+        let mut max_size = 0;
+        unsafe {
+            gl::GetIntegerv(gl::MAX_TEXTURE_SIZE, &mut max_size);
+        }
+        if height > max_size as u32 {
+            return Err(format!("the glyph atlas can not grow beyond {} pixels", max_size));
+        }
+
+        self.pixels.resize((width * height) as usize, 0);
+        self.font.pages[0] = TrueTypeFont::page_texture(width, height, &self.pixels)?;
+        self.font.description.common_mut().scale_h = height;
+
+        Ok(())
+    }
+
+    /// Creates the page texture from atlas pixels with the top row first.
+    fn page_texture(width: u32, height: u32, pixels: &[u8]) -> Result<Texture, String> {
+        let flipped : Vec<u8> = pixels.chunks(width as usize).rev().flatten().cloned().collect();
+        let texture = Texture::with_format(width, height, TextureFormat::R8, Some(&flipped))?;
+
+        let swizzle = [gl::ONE as i32, gl::ONE as i32, gl::ONE as i32, gl::RED as i32];
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, texture.id());
+            gl::TexParameteriv(gl::TEXTURE_2D, gl::TEXTURE_SWIZZLE_RGBA, swizzle.as_ptr());
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+
+        Ok(texture)
+    }
+}
@@ -0,0 +1,324 @@
+extern crate gl;
+
+use linalg::{Matrix3, Matrix4, Vector3, Vector4};
+use std::slice;
+
+/// A value that can be written into a uniform variable of a Program.
+///
+/// Every implementation knows which GLSL types it may be assigned to, such that
+/// `Program::set` can check the value against the type the driver reports for the uniform
+/// before anything is uploaded. Uploading happens on the currently used Program.
+pub trait Uniform {
+
+    /// Returns true if the value can be assigned to a uniform of the given GLSL type,
+    /// for example `gl::FLOAT_MAT4`.
+    fn matches(&self, gl_type: gl::types::GLenum) -> bool;
+
+    /// Number of array elements this value occupies. Single values occupy one element.
+    fn count(&self) -> usize {
+        1
+    }
+
+    /// Uploads the value to the given uniform location of the currently used Program.
+    fn upload(&self, location: gl::types::GLint);
+}
+
+/// Texture unit a sampler uniform reads from, i.e. the `n` in `gl::TEXTURE0 + n`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TextureUnit(pub u32);
+
+/// Returns true if the given GLSL type is one of the sampler types.
+pub fn is_sampler(gl_type: gl::types::GLenum) -> bool {
+    matches!(gl_type,
+        gl::SAMPLER_1D | gl::SAMPLER_2D | gl::SAMPLER_3D | gl::SAMPLER_CUBE |
+        gl::SAMPLER_1D_SHADOW | gl::SAMPLER_2D_SHADOW | gl::SAMPLER_CUBE_SHADOW |
+        gl::SAMPLER_1D_ARRAY | gl::SAMPLER_2D_ARRAY |
+        gl::SAMPLER_1D_ARRAY_SHADOW | gl::SAMPLER_2D_ARRAY_SHADOW |
+        gl::SAMPLER_2D_MULTISAMPLE | gl::SAMPLER_2D_MULTISAMPLE_ARRAY |
+        gl::SAMPLER_BUFFER | gl::SAMPLER_2D_RECT | gl::SAMPLER_2D_RECT_SHADOW |
+        gl::INT_SAMPLER_1D | gl::INT_SAMPLER_2D | gl::INT_SAMPLER_3D | gl::INT_SAMPLER_CUBE |
+        gl::INT_SAMPLER_1D_ARRAY | gl::INT_SAMPLER_2D_ARRAY |
+        gl::UNSIGNED_INT_SAMPLER_1D | gl::UNSIGNED_INT_SAMPLER_2D |
+        gl::UNSIGNED_INT_SAMPLER_3D | gl::UNSIGNED_INT_SAMPLER_CUBE |
+        gl::UNSIGNED_INT_SAMPLER_1D_ARRAY | gl::UNSIGNED_INT_SAMPLER_2D_ARRAY)
+}
+
+/// Returns the GLSL name of a uniform type, used for readable error messages.
+pub fn type_name(gl_type: gl::types::GLenum) -> &'static str {
+    match gl_type {
+        gl::FLOAT             => "float",
+        gl::FLOAT_VEC2        => "vec2",
+        gl::FLOAT_VEC3        => "vec3",
+        gl::FLOAT_VEC4        => "vec4",
+        gl::INT               => "int",
+        gl::INT_VEC2          => "ivec2",
+        gl::INT_VEC3          => "ivec3",
+        gl::INT_VEC4          => "ivec4",
+        gl::UNSIGNED_INT      => "uint",
+        gl::UNSIGNED_INT_VEC2 => "uvec2",
+        gl::UNSIGNED_INT_VEC3 => "uvec3",
+        gl::UNSIGNED_INT_VEC4 => "uvec4",
+        gl::BOOL              => "bool",
+        gl::BOOL_VEC2         => "bvec2",
+        gl::BOOL_VEC3         => "bvec3",
+        gl::BOOL_VEC4         => "bvec4",
+        gl::FLOAT_MAT2        => "mat2",
+        gl::FLOAT_MAT3        => "mat3",
+        gl::FLOAT_MAT4        => "mat4",
+        t if is_sampler(t)    => "sampler",
+        _                     => "unknown",
+    }
+}
+
+impl Uniform for f32 {
+    fn matches(&self, gl_type: gl::types::GLenum) -> bool {
+        gl_type == gl::FLOAT
+    }
+
+    fn upload(&self, location: gl::types::GLint) {
+        unsafe { gl::Uniform1f(location, *self); }
+    }
+}
+
+// like slices of i32, which are the only way to set arrays of samplers, plain i32 values may
+// set samplers as well, although TextureUnit states the intent more clearly
+impl Uniform for i32 {
+    fn matches(&self, gl_type: gl::types::GLenum) -> bool {
+        gl_type == gl::INT || gl_type == gl::BOOL || is_sampler(gl_type)
+    }
+
+    fn upload(&self, location: gl::types::GLint) {
+        unsafe { gl::Uniform1i(location, *self); }
+    }
+}
+
+impl Uniform for u32 {
+    fn matches(&self, gl_type: gl::types::GLenum) -> bool {
+        gl_type == gl::UNSIGNED_INT || gl_type == gl::BOOL
+    }
+
+    fn upload(&self, location: gl::types::GLint) {
+        unsafe { gl::Uniform1ui(location, *self); }
+    }
+}
+
+impl Uniform for bool {
+    fn matches(&self, gl_type: gl::types::GLenum) -> bool {
+        gl_type == gl::BOOL
+    }
+
+    fn upload(&self, location: gl::types::GLint) {
+        unsafe { gl::Uniform1i(location, *self as i32); }
+    }
+}
+
+impl Uniform for TextureUnit {
+    fn matches(&self, gl_type: gl::types::GLenum) -> bool {
+        is_sampler(gl_type)
+    }
+
+    fn upload(&self, location: gl::types::GLint) {
+        unsafe { gl::Uniform1i(location, self.0 as i32); }
+    }
+}
+
+impl Uniform for [f32; 2] {
+    fn matches(&self, gl_type: gl::types::GLenum) -> bool {
+        gl_type == gl::FLOAT_VEC2
+    }
+
+    fn upload(&self, location: gl::types::GLint) {
+        unsafe { gl::Uniform2f(location, self[0], self[1]); }
+    }
+}
+
+impl Uniform for [f32; 3] {
+    fn matches(&self, gl_type: gl::types::GLenum) -> bool {
+        gl_type == gl::FLOAT_VEC3
+    }
+
+    fn upload(&self, location: gl::types::GLint) {
+        unsafe { gl::Uniform3f(location, self[0], self[1], self[2]); }
+    }
+}
+
+impl Uniform for [f32; 4] {
+    fn matches(&self, gl_type: gl::types::GLenum) -> bool {
+        gl_type == gl::FLOAT_VEC4
+    }
+
+    fn upload(&self, location: gl::types::GLint) {
+        unsafe { gl::Uniform4f(location, self[0], self[1], self[2], self[3]); }
+    }
+}
+
+impl Uniform for [i32; 2] {
+    fn matches(&self, gl_type: gl::types::GLenum) -> bool {
+        gl_type == gl::INT_VEC2 || gl_type == gl::BOOL_VEC2
+    }
+
+    fn upload(&self, location: gl::types::GLint) {
+        unsafe { gl::Uniform2i(location, self[0], self[1]); }
+    }
+}
+
+impl Uniform for [i32; 3] {
+    fn matches(&self, gl_type: gl::types::GLenum) -> bool {
+        gl_type == gl::INT_VEC3 || gl_type == gl::BOOL_VEC3
+    }
+
+    fn upload(&self, location: gl::types::GLint) {
+        unsafe { gl::Uniform3i(location, self[0], self[1], self[2]); }
+    }
+}
+
+impl Uniform for [i32; 4] {
+    fn matches(&self, gl_type: gl::types::GLenum) -> bool {
+        gl_type == gl::INT_VEC4 || gl_type == gl::BOOL_VEC4
+    }
+
+    fn upload(&self, location: gl::types::GLint) {
+        unsafe { gl::Uniform4i(location, self[0], self[1], self[2], self[3]); }
+    }
+}
+
+impl Uniform for Vector3 {
+    fn matches(&self, gl_type: gl::types::GLenum) -> bool {
+        gl_type == gl::FLOAT_VEC3
+    }
+
+    fn upload(&self, location: gl::types::GLint) {
+        unsafe { gl::Uniform3f(location, self.x, self.y, self.z); }
+    }
+}
+
+impl Uniform for Vector4 {
+    fn matches(&self, gl_type: gl::types::GLenum) -> bool {
+        gl_type == gl::FLOAT_VEC4
+    }
+
+    fn upload(&self, location: gl::types::GLint) {
+        unsafe { gl::Uniform4f(location, self.x, self.y, self.z, self.w); }
+    }
+}
+
+impl Uniform for Matrix3 {
+    fn matches(&self, gl_type: gl::types::GLenum) -> bool {
+        gl_type == gl::FLOAT_MAT3
+    }
+
+    fn upload(&self, location: gl::types::GLint) {
+        unsafe { gl::UniformMatrix3fv(location, 1, gl::FALSE, self.as_ptr()); }
+    }
+}
+
+impl Uniform for Matrix4 {
+    fn matches(&self, gl_type: gl::types::GLenum) -> bool {
+        gl_type == gl::FLOAT_MAT4
+    }
+
+    fn upload(&self, location: gl::types::GLint) {
+        unsafe { gl::UniformMatrix4fv(location, 1, gl::FALSE, self.as_ptr()); }
+    }
+}
+
+impl Uniform for [f32] {
+    fn matches(&self, gl_type: gl::types::GLenum) -> bool {
+        gl_type == gl::FLOAT
+    }
+
+    fn count(&self) -> usize {
+        self.len()
+    }
+
+    fn upload(&self, location: gl::types::GLint) {
+        unsafe { gl::Uniform1fv(location, self.len() as gl::types::GLsizei, self.as_ptr()); }
+    }
+}
+
+impl Uniform for [i32] {
+    fn matches(&self, gl_type: gl::types::GLenum) -> bool {
+        gl_type == gl::INT || gl_type == gl::BOOL || is_sampler(gl_type)
+    }
+
+    fn count(&self) -> usize {
+        self.len()
+    }
+
+    fn upload(&self, location: gl::types::GLint) {
+        unsafe { gl::Uniform1iv(location, self.len() as gl::types::GLsizei, self.as_ptr()); }
+    }
+}
+
+impl Uniform for [Vector3] {
+    fn matches(&self, gl_type: gl::types::GLenum) -> bool {
+        gl_type == gl::FLOAT_VEC3
+    }
+
+    fn count(&self) -> usize {
+        self.len()
+    }
+
+    fn upload(&self, location: gl::types::GLint) {
+        let mut floats : Vec<f32> = Vec::with_capacity(self.len() * 3);
+        for vec in self {
+            floats.push(vec.x);
+            floats.push(vec.y);
+            floats.push(vec.z);
+        }
+        unsafe { gl::Uniform3fv(location, self.len() as gl::types::GLsizei, floats.as_ptr()); }
+    }
+}
+
+impl Uniform for [Vector4] {
+    fn matches(&self, gl_type: gl::types::GLenum) -> bool {
+        gl_type == gl::FLOAT_VEC4
+    }
+
+    fn count(&self) -> usize {
+        self.len()
+    }
+
+    fn upload(&self, location: gl::types::GLint) {
+        let mut floats : Vec<f32> = Vec::with_capacity(self.len() * 4);
+        for vec in self {
+            floats.push(vec.x);
+            floats.push(vec.y);
+            floats.push(vec.z);
+            floats.push(vec.w);
+        }
+        unsafe { gl::Uniform4fv(location, self.len() as gl::types::GLsizei, floats.as_ptr()); }
+    }
+}
+
+impl Uniform for [Matrix4] {
+    fn matches(&self, gl_type: gl::types::GLenum) -> bool {
+        gl_type == gl::FLOAT_MAT4
+    }
+
+    fn count(&self) -> usize {
+        self.len()
+    }
+
+    fn upload(&self, location: gl::types::GLint) {
+        let mut floats : Vec<f32> = Vec::with_capacity(self.len() * 16);
+        for matrix in self {
+            floats.extend_from_slice(unsafe { slice::from_raw_parts(matrix.as_ptr(), 16) });
+        }
+        unsafe { gl::UniformMatrix4fv(location, self.len() as gl::types::GLsizei, gl::FALSE, floats.as_ptr()); }
+    }
+}
+
+impl<T: Uniform + ?Sized> Uniform for &T {
+    fn matches(&self, gl_type: gl::types::GLenum) -> bool {
+        (**self).matches(gl_type)
+    }
+
+    fn count(&self) -> usize {
+        (**self).count()
+    }
+
+    fn upload(&self, location: gl::types::GLint) {
+        (**self).upload(location)
+    }
+}
@@ -0,0 +1,91 @@
+extern crate gl;
+
+use std::ptr;
+use super::std140::Std140Writer;
+
+/// Wrapper for Uniform Buffer Objects (UBO). A UBO holds the data of a uniform block on the
+/// graphics card and is shared by all Programs whose block is attached to the same binding point,
+/// so per-frame data such as the camera matrices needs to be uploaded only once per frame.
+///
+/// The data is usually packed with a `Std140Writer` and the blocks of the Programs are attached
+/// to the binding point with `Program::bind_uniform_block`.
+#[allow(dead_code)]
+pub struct UniformBuffer {
+    id   : gl::types::GLuint,
+    size : usize,
+}
+
+#[allow(dead_code)]
+impl UniformBuffer {
+
+    /// Allocates a buffer of the given size in bytes without initializing it.
+    pub fn new(size: usize) -> UniformBuffer {
+        unsafe {
+            let mut id = 0;
+            gl::GenBuffers(1, &mut id);
+            gl::BindBuffer(gl::UNIFORM_BUFFER, id);
+            gl::BufferData(gl::UNIFORM_BUFFER, size as gl::types::GLsizeiptr, ptr::null(), gl::DYNAMIC_DRAW);
+            gl::BindBuffer(gl::UNIFORM_BUFFER, 0);
+
+            UniformBuffer {
+                id,
+                size,
+            }
+        }
+    }
+
+    /// Allocates a buffer holding the data packed by the given writer.
+    pub fn from_writer(writer: &Std140Writer) -> UniformBuffer {
+        let bytes = writer.bytes();
+        let mut buffer = UniformBuffer::new(bytes.len());
+        buffer.update(&bytes);
+        buffer
+    }
+
+    pub fn id(&self) -> gl::types::GLuint {
+        self.id
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Replaces the content of the buffer, growing it if the data does not fit.
+    pub fn update(&mut self, data: &[u8]) {
+        unsafe {
+            gl::BindBuffer(gl::UNIFORM_BUFFER, self.id);
+
+            if data.len() > self.size {
+                gl::BufferData(gl::UNIFORM_BUFFER, data.len() as gl::types::GLsizeiptr, data.as_ptr() as *const _, gl::DYNAMIC_DRAW);
+                self.size = data.len();
+            } else {
+                gl::BufferSubData(gl::UNIFORM_BUFFER, 0, data.len() as gl::types::GLsizeiptr, data.as_ptr() as *const _);
+            }
+
+            gl::BindBuffer(gl::UNIFORM_BUFFER, 0);
+        }
+    }
+
+    /// Replaces the content written by the given writer.
+    pub fn update_from_writer(&mut self, writer: &Std140Writer) {
+        self.update(&writer.bytes());
+    }
+
+    /// Overwrites a part of the buffer starting at the given offset in bytes.
+    pub fn update_range(&self, offset: usize, data: &[u8]) {
+        assert!(offset + data.len() <= self.size, "range {}..{} exceeds the UniformBuffer of size {}", offset, offset + data.len(), self.size);
+
+        unsafe {
+            gl::BindBuffer(gl::UNIFORM_BUFFER, self.id);
+            gl::BufferSubData(gl::UNIFORM_BUFFER, offset as gl::types::GLintptr, data.len() as gl::types::GLsizeiptr, data.as_ptr() as *const _);
+            gl::BindBuffer(gl::UNIFORM_BUFFER, 0);
+        }
+    }
+
+    /// Attaches the buffer to the given uniform buffer binding point.
+    pub fn bind_base(&self, binding: u32) {
+        unsafe {
+            gl::BindBufferBase(gl::UNIFORM_BUFFER, binding, self.id);
+        }
+    }
+}
@@ -0,0 +1,125 @@
+extern crate gl;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use super::preprocessor::Preprocessor;
+use super::program::Program;
+
+/// A watched file along with its modification time when it was last loaded.
+type WatchedFile = (PathBuf, Option<SystemTime>);
+
+/// Development helper that reloads a Program whenever one of its shader files changes on disk.
+///
+/// The watcher polls the modification times of the vertex and fragment shader as well as of all
+/// files they `#include`. On a change, a complete new Program is compiled and linked first and
+/// only swapped in if that succeeded, so a typo in a shader never leaves you without a Program.
+/// Call `poll` once per frame, e.g. right before rendering.
+pub struct ProgramWatcher {
+    vertex_shader_path   : PathBuf,
+    fragment_shader_path : PathBuf,
+    preprocessor         : Preprocessor,
+    program              : Program,
+    watched              : Vec<WatchedFile>,
+    last_error           : Option<String>,
+}
+
+impl ProgramWatcher {
+
+    /// Loads the Program from the given files. Fails if the initial version does not compile,
+    /// since there is no last good Program to fall back to yet.
+    pub fn new<P: AsRef<Path>>(vertex_shader_path: P, fragment_shader_path: P) -> Result<ProgramWatcher, String> {
+        ProgramWatcher::with_preprocessor(vertex_shader_path, fragment_shader_path, Preprocessor::new())
+    }
+
+    /// Same as `new`, but runs the shaders through the given Preprocessor, e.g. to provide
+    /// include search paths or defines.
+    pub fn with_preprocessor<P: AsRef<Path>>(vertex_shader_path: P, fragment_shader_path: P,
+                                             preprocessor: Preprocessor) -> Result<ProgramWatcher, String> {
+        let vertex_shader_path   = vertex_shader_path.as_ref().to_path_buf();
+        let fragment_shader_path = fragment_shader_path.as_ref().to_path_buf();
+
+        let (program, watched) = ProgramWatcher::load(&vertex_shader_path, &fragment_shader_path, &preprocessor);
+        let program = program?;
+
+        Ok(ProgramWatcher {
+            vertex_shader_path,
+            fragment_shader_path,
+            preprocessor,
+            program,
+            watched,
+            last_error : None,
+        })
+    }
+
+    /// Builds the Program and returns it along with the files to watch, which are all files
+    /// the shaders were read from even if that failed.
+    fn load(vertex_shader_path: &Path, fragment_shader_path: &Path, preprocessor: &Preprocessor)
+            -> (Result<Program, String>, Vec<WatchedFile>) {
+
+        let mut files = Vec::new();
+        let vertex_shader   = preprocessor.process_file_tracking(vertex_shader_path, &mut files);
+        let fragment_shader = preprocessor.process_file_tracking(fragment_shader_path, &mut files);
+
+        let watched = files.into_iter()
+            .map(|file| {
+                let time = modification_time(&file);
+                (file, time)
+            })
+            .collect();
+
+        let program = vertex_shader.and_then(|vertex_shader| {
+            Program::from_sources(&vertex_shader, &fragment_shader?)
+        });
+
+        (program, watched)
+    }
+
+    /// The most recent Program that compiled and linked successfully.
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    /// The error of the last failed reload, cleared as soon as a reload succeeds.
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    /// Checks the watched files for modifications and reloads the Program if any changed.
+    ///
+    /// Returns `Ok(true)` if a new Program was swapped in. It is in use afterwards, and since it
+    /// starts with fresh uniform state, all uniforms need to be set again. Returns an error if
+    /// the modified sources failed to compile or link, in which case the last good Program is kept.
+    pub fn poll(&mut self) -> Result<bool, String> {
+        let changed = self.watched.iter().any(|(path, time)| modification_time(path) != *time);
+
+        if !changed {
+            return Ok(false);
+        }
+
+        let (program, watched) = ProgramWatcher::load(&self.vertex_shader_path, &self.fragment_shader_path, &self.preprocessor);
+
+        // the new modification times keep a broken version from being compiled over and over
+        // again, and the new files include the ones that broke it, until they change once more
+        self.watched = watched;
+
+        match program {
+            Ok(program) => {
+                let old = std::mem::replace(&mut self.program, program);
+                unsafe { gl::DeleteProgram(old.id()); }
+
+                self.last_error = None;
+                Ok(true)
+            },
+            Err(msg) => {
+                self.last_error = Some(msg.clone());
+                Err(msg)
+            }
+        }
+    }
+}
+
+fn modification_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
@@ -0,0 +1,126 @@
+extern crate dust;
+
+use std::env;
+use std::fs;
+
+use dust::image::{Image, PixelData, PixelFormat};
+use dust::opengl::Sprite;
+use dust::opengl::atlas::{Atlas, AtlasBuilder};
+
+/// An RGB image whose pixels differ from each other and from those of other seeds.
+fn image(width: u32, height: u32, seed: u8) -> Image {
+    let mut data = Vec::new();
+    for y in 0..height {
+        for x in 0..width {
+            data.extend_from_slice(&[x as u8, y as u8, seed]);
+        }
+    }
+    Image::new(width, height, PixelFormat::Rgb, PixelData::U8(data)).unwrap()
+}
+
+fn crop(atlas: &Atlas, sprite: &Sprite) -> Image {
+    atlas.image().crop(sprite.x(), sprite.y(), sprite.width(), sprite.height()).unwrap()
+}
+
+fn icons() -> AtlasBuilder {
+    (0..12u32).fold(AtlasBuilder::new(), |builder, index| {
+        builder.add(&format!("icon {}", index), image(4 + index * 3 % 17, 5 + index * 7 % 13, index as u8 + 1))
+    })
+}
+
+#[test]
+fn pack_without_overlaps() {
+    let atlas = icons().padding(2).build().unwrap();
+    let (width, height) = (atlas.image().width(), atlas.image().height());
+
+    assert!(width.is_power_of_two() && height.is_power_of_two());
+    assert_eq!(atlas.image().format(), PixelFormat::Rgb);
+    assert_eq!(atlas.sprites().len(), 12);
+
+    let sprites : Vec<&Sprite> = atlas.sprites().values().collect();
+    for (i, a) in sprites.iter().enumerate() {
+        assert!(a.x() >= 2 && a.y() >= 2);
+        assert!(a.x() + a.width() + 2 <= width && a.y() + a.height() + 2 <= height);
+
+        for b in &sprites[i + 1..] {
+            let apart_x = a.x() + a.width() + 2 <= b.x() || b.x() + b.width() + 2 <= a.x();
+            let apart_y = a.y() + a.height() + 2 <= b.y() || b.y() + b.height() + 2 <= a.y();
+            assert!(apart_x || apart_y, "{:?} and {:?} are too close", a, b);
+        }
+    }
+
+    for index in 0..12u32 {
+        let sprite = atlas.sprite(&format!("icon {}", index)).unwrap();
+        assert_eq!(crop(&atlas, sprite), image(4 + index * 3 % 17, 5 + index * 7 % 13, index as u8 + 1));
+    }
+}
+
+#[test]
+fn extrude_the_edges() {
+    let atlas = AtlasBuilder::new().add("a", image(3, 2, 1)).add("b", image(2, 2, 2)).extrusion(2).build().unwrap();
+    let sprite = *atlas.sprite("a").unwrap();
+    assert_eq!((sprite.width(), sprite.height()), (3, 2));
+    assert!(sprite.x() >= 2 && sprite.y() >= 2);
+
+    let around = atlas.image().crop(sprite.x() - 2, sprite.y() - 2, 7, 6).unwrap();
+    let pixel = |x: usize, y: usize| match *around.data() {
+        PixelData::U8(ref data) => data[(y * 7 + x) * 3..(y * 7 + x) * 3 + 3].to_vec(),
+        _                       => panic!("expected 8 bit pixels"),
+    };
+
+    // corners repeat the corner pixels, edges the edge pixels
+    assert_eq!(pixel(0, 0), vec![0, 0, 1]);
+    assert_eq!(pixel(6, 5), vec![2, 1, 1]);
+    assert_eq!(pixel(3, 0), vec![1, 0, 1]);
+    assert_eq!(pixel(0, 3), vec![0, 1, 1]);
+    assert_eq!(pixel(6, 2), vec![2, 0, 1]);
+    assert_eq!(pixel(3, 3), vec![1, 1, 1]);
+}
+
+#[test]
+fn formats_with_alpha_are_kept() {
+    let gray = Image::new(2, 2, PixelFormat::GrayAlpha, PixelData::U8(vec![9, 128, 9, 128, 9, 128, 9, 128])).unwrap();
+    let atlas = AtlasBuilder::new().add("rgb", image(2, 2, 1)).add("gray", gray).build().unwrap();
+
+    assert_eq!(atlas.image().format(), PixelFormat::Rgba);
+    let sprite = atlas.sprite("gray").unwrap();
+    assert_eq!(*crop(&atlas, sprite).data(), PixelData::U8([9, 9, 9, 128].repeat(4)));
+}
+
+#[test]
+fn reject_invalid_images() {
+    assert!(AtlasBuilder::new().add("a", image(2, 2, 1)).add("a", image(2, 2, 2)).build().is_err());
+    assert!(AtlasBuilder::new().add("a\nb", image(2, 2, 1)).build().is_err());
+    assert!(AtlasBuilder::new().add("a", image(20, 20, 1)).max_size(16).build().is_err());
+    assert!(AtlasBuilder::new().add("a", image(16, 16, 1)).max_size(16).build().is_ok());
+    assert!(AtlasBuilder::new().add("a", image(16, 16, 1)).padding(1).max_size(16).build().is_err());
+}
+
+#[test]
+fn sprite_map_round_trip() {
+    let atlas = icons().padding(1).extrusion(1).build().unwrap();
+
+    let path = env::temp_dir().join("dust_atlas_round_trip.txt");
+    atlas.save_map(&path).unwrap();
+    let (width, height, sprites) = Atlas::load_map(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!((width, height), (atlas.image().width(), atlas.image().height()));
+    assert_eq!(&sprites, atlas.sprites());
+
+    let map = atlas.to_map();
+    assert!(map.starts_with(&format!("atlas {} {}\n", width, height)));
+    assert!(map.contains(&format!("sprite {} {} 4 5 icon 0\n", sprites["icon 0"].x(), sprites["icon 0"].y())));
+}
+
+#[test]
+fn reject_invalid_maps() {
+    assert!(Atlas::parse_map("atlas 16 16\nsprite 0 0 4 4 a\n").is_ok());
+    assert!(Atlas::parse_map("sprite 0 0 4 4 a\n").is_err());
+    assert!(Atlas::parse_map("atlas 16 16\nsprite 0 0 4 a\n").is_err());
+    assert!(Atlas::parse_map("atlas 16 16\nsprite 14 0 4 4 a\n").is_err());
+    assert!(Atlas::parse_map("atlas 16 16\nsprite 4294967295 0 4 4 a").is_err());
+    assert!(Atlas::parse_map("atlas 16 16\nsprite 0 4 4 4294967295 a").is_err());
+    assert!(Atlas::parse_map("atlas 16 16\nsprite 0 0 4 4 a\nsprite 4 0 4 4 a\n").is_err());
+    assert!(Atlas::parse_map("").is_err());
+}
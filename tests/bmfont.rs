@@ -0,0 +1,242 @@
+extern crate dust;
+extern crate gl;
+
+mod common;
+
+use dust::opengl::{Font, Texture, TextureFormat};
+use dust::opengl::bmfont::{FontDescription, Glyph};
+
+const TWO_PAGES : &str = "\
+info face=\"Open Sans\" size=-16 bold=1 italic=0 charset=\"\" unicode=1 stretchH=100 smooth=1 aa=1 padding=0,1,2,3 spacing=1,1 outline=0
+common lineHeight=20 base=16 scaleW=64 scaleH=64 pages=2 packed=0
+page id=1 file=\"second page.png\"
+page id=0 file=\"first.png\"
+chars count=3
+char id=32 x=0 y=0 width=0 height=0 xoffset=0 yoffset=0 xadvance=4 page=0 chnl=15
+char id=65 x=0 y=0 width=8 height=10 xoffset=1 yoffset=6 xadvance=9 page=0 chnl=15
+char id=66 x=8 y=4 width=6 height=12 xoffset=-1 yoffset=4 xadvance=7 page=1 chnl=15
+kernings count=1
+kerning first=65 second=66 amount=-2
+";
+
+#[test]
+fn parse_asset_font() {
+    let description = FontDescription::load("assets/font.fnt").unwrap();
+
+    assert_eq!(description.info().face, "Arial");
+    assert_eq!(description.info().size, 32);
+    assert_eq!(description.info().padding, [1, 1, 1, 1]);
+    assert_eq!(description.common().line_height, 40);
+    assert_eq!(description.common().base, 30);
+    assert_eq!(description.common().scale_w, 512);
+    assert_eq!(description.pages(), &["tesdt.png".to_string()]);
+    assert_eq!(description.glyphs().len(), 191);
+    assert_eq!(description.kernings().len(), 95);
+
+    let space = description.glyph(32).unwrap();
+    assert_eq!((space.width, space.xoffset, space.xadvance), (0, -1, 11));
+    assert_eq!(description.kerning(65, 86), -2);
+    assert_eq!(description.kerning(86, 65), description.kernings()[&(86, 65)]);
+    assert_eq!(description.kerning(1000, 1001), 0);
+}
+
+#[test]
+fn parse_quoted_values_and_out_of_order_pages() {
+    let description = FontDescription::parse_text(TWO_PAGES).unwrap();
+
+    assert_eq!(description.info().face, "Open Sans");
+    assert_eq!(description.info().size, -16);
+    assert!(description.info().bold && description.info().unicode);
+    assert_eq!(description.info().padding, [0, 1, 2, 3]);
+    assert_eq!(description.pages(), &["first.png".to_string(), "second page.png".to_string()]);
+
+    assert_eq!(*description.glyph(66).unwrap(), Glyph {
+        id: 66, x: 8, y: 4, width: 6, height: 12, xoffset: -1, yoffset: 4, xadvance: 7, page: 1, chnl: 15,
+    });
+}
+
+#[test]
+fn parse_errors_name_the_line() {
+    let error = FontDescription::parse_text("common lineHeight=20 base=16 scaleW=64 scaleH=64\nchar id=x").unwrap_err();
+    assert!(error.starts_with("line 2:"), "{}", error);
+
+    let error = FontDescription::parse_text("info face=\"Arial size=32").unwrap_err();
+    assert!(error.starts_with("line 1:"), "{}", error);
+
+    assert!(FontDescription::parse_text("char id=65").is_err());
+}
+
+#[test]
+fn page_ids_must_be_below_the_page_count() {
+    let error = FontDescription::parse_text("common lineHeight=20 base=16 scaleW=64 scaleH=64 pages=1\npage id=1 file=\"a.png\"").unwrap_err();
+    assert!(error.starts_with("line 2:"), "{}", error);
+    assert!(FontDescription::parse_text("common lineHeight=20 base=16 scaleW=64 scaleH=64\npage id=18446744073709551615 file=\"a.png\"").is_err());
+
+    // every page up to the page count needs a file
+    let error = FontDescription::parse_text("common lineHeight=20 base=16 scaleW=64 scaleH=64 pages=2\npage id=1 file=\"b.png\"").unwrap_err();
+    assert!(error.contains("page id 0 is missing"), "{}", error);
+    let error = FontDescription::parse_xml("<font><common lineHeight=\"20\" base=\"16\" scaleW=\"64\" scaleH=\"64\" pages=\"2\"/>\
+        <page id=\"0\" file=\"a.png\"/></font>").unwrap_err();
+    assert!(error.contains("page id 1 is missing"), "{}", error);
+
+    let mut description = FontDescription::parse_text(TWO_PAGES).unwrap();
+    description.common_mut().pages = 3;
+    let error = FontDescription::parse_binary(&description.to_binary()).unwrap_err();
+    assert!(error.contains("page id 2 is missing"), "{}", error);
+
+    let mut description = FontDescription::parse_text(TWO_PAGES).unwrap();
+    assert!(description.set_page(usize::MAX, "b.png".to_string()).is_err());
+    description.set_page(1, "b.png".to_string()).unwrap();
+    assert_eq!(description.pages(), &["first.png".to_string(), "b.png".to_string()]);
+}
+
+#[test]
+fn binary_round_trip_of_asset_font() {
+    let description = FontDescription::load("assets/font.fnt").unwrap();
+    let binary = description.to_binary();
+
+    assert_eq!(&binary[..4], b"BMF\x03");
+    assert_eq!(FontDescription::parse_binary(&binary).unwrap(), description);
+    assert_eq!(FontDescription::parse(&binary).unwrap(), description);
+}
+
+#[test]
+fn xml_round_trip_of_asset_font() {
+    let description = FontDescription::load("assets/font.fnt").unwrap();
+    let xml = description.to_xml();
+
+    assert!(xml.contains("<char id=\"65\" x=\"0\" y=\"0\" width=\"23\" height=\"40\""));
+    assert_eq!(FontDescription::parse_xml(&xml).unwrap(), description);
+    assert_eq!(FontDescription::parse(xml.as_bytes()).unwrap(), description);
+}
+
+#[test]
+fn text_round_trip_through_all_formats() {
+    let description = FontDescription::parse_text(TWO_PAGES).unwrap();
+
+    let text = FontDescription::parse_text(&description.to_text()).unwrap();
+    let xml = FontDescription::parse_xml(&text.to_xml()).unwrap();
+    let binary = FontDescription::parse_binary(&xml.to_binary()).unwrap();
+
+    assert_eq!(text, description);
+    assert_eq!(xml, description);
+    assert_eq!(binary, description);
+}
+
+#[test]
+fn parse_xml_with_comments_and_entities() {
+    let xml = "<?xml version=\"1.0\"?>
+<!-- exported by hand -->
+<font>
+  <info face='Fish &amp; Chips' size=\"12\"/>
+  <common lineHeight=\"14\" base=\"11\" scaleW=\"32\" scaleH=\"32\" pages=\"1\" packed=\"0\"/>
+  <pages><page id=\"0\" file=\"fish&#x20;chips.png\"/></pages>
+  <chars count=\"1\">
+    <char id=\"8364\" x=\"1\" y=\"2\" width=\"3\" height=\"4\" xoffset=\"0\" yoffset=\"1\" xadvance=\"5\" page=\"0\" chnl=\"15\" />
+  </chars>
+</font>
+";
+    let description = FontDescription::parse_xml(xml).unwrap();
+
+    assert_eq!(description.info().face, "Fish & Chips");
+    assert_eq!(description.pages(), &["fish chips.png".to_string()]);
+    assert_eq!(description.glyph(0x20ac).unwrap().xadvance, 5);
+
+    let error = FontDescription::parse_xml("<font>\n<common lineHeight=\"x\"/>\n</font>").unwrap_err();
+    assert!(error.starts_with("line 2:"), "{}", error);
+}
+
+#[test]
+fn parse_binary_rejects_truncated_and_unknown_versions() {
+    let binary = FontDescription::load("assets/font.fnt").unwrap().to_binary();
+
+    assert!(FontDescription::parse_binary(&binary[..binary.len() - 3]).is_err());
+
+    let mut version = binary.clone();
+    version[3] = 2;
+    assert!(FontDescription::parse_binary(&version).is_err());
+}
+
+#[test]
+fn glyph_quads_use_offsets_kerning_and_pages() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    let description = FontDescription::parse_text(TWO_PAGES).unwrap();
+    let pages = vec![
+        Texture::with_format(64, 64, TextureFormat::Rgba8, None).unwrap(),
+        Texture::with_format(64, 64, TextureFormat::Rgba8, None).unwrap(),
+    ];
+    let font = Font::from_description(description, pages).unwrap();
+    let scale = 0.005;
+
+    let meshes = font.page_meshes("AB\nA");
+    assert_eq!(meshes.len(), 2);
+    assert_eq!(meshes[0].vertex_count(), 8);
+    assert_eq!(meshes[1].vertex_count(), 4);
+
+    // the baseline of the last line is at 0, so the first line is one line height above
+    let first = meshes[0].vertex(0);
+    assert!((first[0] - 1.0 * scale).abs() < 1e-6);
+    assert!((first[1] - (20.0 + 16.0 - 6.0 - 10.0) * scale).abs() < 1e-6);
+    assert!((first[4] - 54.0 / 64.0).abs() < 1e-6);
+
+    // B follows the advance of A minus the kerning and starts with its own offset
+    let b = meshes[1].vertex(0);
+    assert!((b[0] - (9.0 - 2.0 - 1.0) * scale).abs() < 1e-6);
+    assert!((b[3] - 8.0 / 64.0).abs() < 1e-6);
+
+    let second_line = meshes[0].vertex(4);
+    assert!((second_line[1] - (16.0 - 6.0 - 10.0) * scale).abs() < 1e-6);
+
+    assert_eq!(font.mesh("AB\nA").vertex_count(), 12);
+}
+
+#[test]
+fn glyphs_must_reference_existing_pages() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    let description = FontDescription::parse_text(TWO_PAGES).unwrap();
+    let pages = vec![Texture::with_format(64, 64, TextureFormat::Rgba8, None).unwrap()];
+    assert!(Font::from_description(description, pages).is_err());
+}
+
+#[test]
+fn missing_characters_use_the_fallback_glyph() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    let tex = Texture::with_format(512, 512, TextureFormat::Rgba8, None).unwrap();
+    let font = Font::new(tex, "assets/font.fnt");
+    let scale = 0.005;
+
+    assert_eq!(font.fallback(), Some(0));
+    assert_eq!(font.glyph('\u{4e16}').unwrap().id, 0);
+    assert_eq!(font.glyph('a').unwrap().id, 97);
+
+    let mesh = font.mesh("a\u{4e16}");
+    assert_eq!(mesh.vertex_count(), 8);
+    // the box is placed after the advance of 'a' with its own offset
+    assert!((mesh.vertex(4)[0] - (19.0 + 3.0) * scale).abs() < 1e-6);
+
+    // spaces advance by the width the font gives them, carriage returns are ignored
+    let mesh = font.mesh(" a\r\na");
+    assert_eq!(mesh.vertex_count(), 8);
+    assert!((mesh.vertex(0)[0] - 11.0 * scale).abs() < 1e-6);
+    assert!((mesh.vertex(0)[1] - mesh.vertex(4)[1] - 40.0 * scale).abs() < 1e-6);
+
+    let font = font.with_fallback(None);
+    assert!(font.glyph('\u{4e16}').is_none());
+    assert_eq!(font.mesh("a\u{4e16}").vertex_count(), 4);
+}
+
+#[test]
+fn glyph_ids_beyond_latin_1() {
+    let description = FontDescription::parse_text("\
+common lineHeight=20 base=16 scaleW=64 scaleH=64 pages=1
+page id=0 file=\"emoji.png\"
+char id=8364 x=0 y=0 width=8 height=10 xoffset=0 yoffset=0 xadvance=9 page=0
+char id=128512 x=8 y=0 width=16 height=16 xoffset=0 yoffset=0 xadvance=17 page=0
+").unwrap();
+
+    assert_eq!(description.glyph('€' as u32).unwrap().xadvance, 9);
+    assert_eq!(description.glyph('\u{1f600}' as u32).unwrap().width, 16);
+}
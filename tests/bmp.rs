@@ -0,0 +1,129 @@
+extern crate dust;
+
+use dust::image::{self, bmp, PixelData, PixelFormat};
+
+/// Builds a bitmap with a 40 byte info header, followed by `extra` (masks or palette) and the pixels.
+fn bitmap(width: i32, height: i32, bits: u16, compression: u32, colors: u32, extra: &[u8], pixels: &[u8]) -> Vec<u8> {
+    let offset = 14 + 40 + extra.len() as u32;
+
+    let mut bytes = b"BM".to_vec();
+    bytes.extend_from_slice(&(offset + pixels.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&[0, 0, 0, 0]);
+    bytes.extend_from_slice(&offset.to_le_bytes());
+
+    bytes.extend_from_slice(&40u32.to_le_bytes());
+    bytes.extend_from_slice(&width.to_le_bytes());
+    bytes.extend_from_slice(&height.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&bits.to_le_bytes());
+    bytes.extend_from_slice(&compression.to_le_bytes());
+    bytes.extend_from_slice(&(pixels.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&[0; 8]);
+    bytes.extend_from_slice(&colors.to_le_bytes());
+    bytes.extend_from_slice(&[0; 4]);
+
+    bytes.extend_from_slice(extra);
+    bytes.extend_from_slice(pixels);
+    bytes
+}
+
+/// A palette of black, white, red and blue in BGRX order.
+const PALETTE : [u8; 16] = [0, 0, 0, 0, 255, 255, 255, 0, 0, 0, 255, 0, 255, 0, 0, 0];
+
+#[test]
+fn twenty_four_bit_rows_are_padded_and_bottom_up() {
+    let pixels = [
+        3, 2, 1, 6, 5, 4, 0, 0,
+        9, 8, 7, 12, 11, 10, 0, 0,
+    ];
+    let image = bmp::decode(&bitmap(2, 2, 24, 0, 0, &[], &pixels)).unwrap();
+
+    assert_eq!((image.width(), image.height(), image.format()), (2, 2, PixelFormat::Rgb));
+    assert_eq!(*image.data(), PixelData::U8(vec![7, 8, 9, 10, 11, 12, 1, 2, 3, 4, 5, 6]));
+}
+
+#[test]
+fn top_down() {
+    let pixels = [3, 2, 1, 0, 6, 5, 4, 0];
+    let image = bmp::decode(&bitmap(1, -2, 24, 0, 0, &[], &pixels)).unwrap();
+
+    assert_eq!(*image.data(), PixelData::U8(vec![1, 2, 3, 4, 5, 6]));
+}
+
+#[test]
+fn palette_images() {
+    let one_bit = bitmap(3, 1, 1, 0, 2, &PALETTE[..8], &[0b1010_0000, 0, 0, 0]);
+    assert_eq!(*bmp::decode(&one_bit).unwrap().data(), PixelData::U8(vec![255, 255, 255, 0, 0, 0, 255, 255, 255]));
+
+    let four_bit = bitmap(3, 1, 4, 0, 4, &PALETTE, &[0x23, 0x10, 0, 0]);
+    assert_eq!(*bmp::decode(&four_bit).unwrap().data(), PixelData::U8(vec![255, 0, 0, 0, 0, 255, 255, 255, 255]));
+
+    let eight_bit = bitmap(1, 1, 8, 0, 4, &PALETTE, &[9, 0, 0, 0]);
+    assert!(bmp::decode(&eight_bit).is_err());
+}
+
+#[test]
+fn run_length_encoded() {
+    // RLE8: a run of two red pixels, an absolute run of three, end of line, a delta and end of bitmap
+    let rle8 = [2, 2, 0, 3, 1, 3, 0, 0, 0, 0, 0, 2, 1, 0, 0, 1];
+    let image = bmp::decode(&bitmap(5, 2, 8, 1, 4, &PALETTE, &rle8)).unwrap();
+    assert_eq!(*image.data(), PixelData::U8(vec![
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        255, 0, 0, 255, 0, 0, 255, 255, 255, 0, 0, 255, 0, 0, 0,
+    ]));
+
+    // RLE4: a run of four alternating indices and an absolute run of three
+    let rle4 = [4, 0x23, 0, 3, 0x10, 0x10, 0, 1];
+    let image = bmp::decode(&bitmap(7, 1, 4, 2, 4, &PALETTE, &rle4)).unwrap();
+    assert_eq!(*image.data(), PixelData::U8(vec![
+        255, 0, 0, 0, 0, 255, 255, 0, 0, 0, 0, 255, 255, 255, 255, 0, 0, 0, 255, 255, 255,
+    ]));
+}
+
+#[test]
+fn sixteen_bit_defaults_to_555() {
+    let mut pixels = 0x7c00u16.to_le_bytes().to_vec();
+    pixels.extend_from_slice(&0x001fu16.to_le_bytes());
+    let image = bmp::decode(&bitmap(2, 1, 16, 0, 0, &[], &pixels)).unwrap();
+
+    assert_eq!(*image.data(), PixelData::U8(vec![255, 0, 0, 0, 0, 255]));
+}
+
+#[test]
+fn bit_fields_with_alpha() {
+    let mut masks = Vec::new();
+    for mask in &[0x0000_ff00u32, 0x00ff_0000, 0xff00_0000, 0x0000_00ff] {
+        masks.extend_from_slice(&mask.to_le_bytes());
+    }
+    let pixels = 0x0302_0180u32.to_le_bytes();
+    let image = bmp::decode(&bitmap(1, 1, 32, 6, 0, &masks, &pixels)).unwrap();
+
+    assert_eq!(image.format(), PixelFormat::Rgba);
+    assert_eq!(*image.data(), PixelData::U8(vec![1, 2, 3, 128]));
+}
+
+#[test]
+fn truncated_files_are_rejected() {
+    let bytes = bitmap(2, 2, 24, 0, 0, &[], &[0; 12]);
+    assert!(bmp::decode(&bytes).is_err());
+    assert!(bmp::decode(&bytes[..20]).is_err());
+    assert!(image::decode(&bitmap(1, 1, 24, 0, 0, &[], &[3, 2, 1, 0])).is_ok());
+}
+
+#[test]
+fn oversized_headers_are_rejected() {
+    let bytes = bitmap(2, 2, 8, 0, 4, &PALETTE, &[0; 8]);
+    assert!(bmp::decode(&bytes).is_ok());
+    for length in 0..bytes.len() {
+        assert!(bmp::decode(&bytes[..length]).is_err());
+    }
+
+    // huge images with next to no pixels, uncompressed and run length encoded
+    assert!(bmp::decode(&bitmap(i32::MAX, i32::MIN + 1, 32, 0, 0, &[], &[0; 16])).is_err());
+    assert!(bmp::decode(&bitmap(i32::MAX, i32::MAX, 8, 1, 4, &PALETTE, &[0, 1])).is_err());
+    assert!(bmp::decode(&bitmap(i32::MAX, i32::MAX, 4, 2, 4, &PALETTE, &[0, 1])).is_err());
+
+    // more colors than the bits can address
+    let bytes = bitmap(1, 1, 1, 0, u32::MAX, &PALETTE[..8], &[0x80, 0, 0, 0]);
+    assert_eq!(*bmp::decode(&bytes).unwrap().data(), PixelData::U8(vec![255, 255, 255]));
+}
@@ -0,0 +1,74 @@
+extern crate dust;
+extern crate gl;
+extern crate glutin;
+
+use std::ffi::CString;
+
+use self::dust::opengl::{Font, Texture, TextureFormat};
+use self::dust::opengl::bmfont::FontDescription;
+use self::dust::opengl::text::{TextLayout, TextSize};
+
+use self::glutin::api::egl::context::PossiblyCurrentContext;
+use self::glutin::api::egl::device::Device;
+use self::glutin::api::egl::display::Display;
+use self::glutin::config::{ConfigSurfaceTypes, ConfigTemplateBuilder};
+use self::glutin::context::{ContextApi, ContextAttributesBuilder, Version};
+use self::glutin::prelude::*;
+
+/// Creates a headless OpenGL context through EGL and makes it current on the calling thread,
+/// such that tests can talk to a (software) driver. Returns None if there is no EGL device,
+/// in which case the tests that need a context pass without checking anything.
+pub fn context() -> Option<PossiblyCurrentContext> {
+    let device = Device::query_devices().ok()?.next()?;
+    let display = unsafe { Display::with_device(&device, None) }.ok()?;
+
+    let template = ConfigTemplateBuilder::new().with_surface_type(ConfigSurfaceTypes::empty()).build();
+    let config = unsafe { display.find_configs(template) }.ok()?.next()?;
+
+    let attributes = ContextAttributesBuilder::new()
+        .with_context_api(ContextApi::OpenGl(Some(Version::new(4, 5))))
+        .build(None);
+    let context = unsafe { display.create_context(&config, &attributes) }.ok()?;
+    let context = context.make_current_surfaceless().ok()?;
+
+    gl::load_with(|symbol| {
+        let symbol = CString::new(symbol).unwrap();
+        display.get_proc_address(symbol.as_c_str()) as *const _
+    });
+
+    Some(context)
+}
+
+/// A single page font with a space, a hyphen, 'A', 'B' and an underscore, 20 pixels high.
+#[allow(dead_code)]
+pub const FONT : &str = "\
+info face=\"Test\" size=20
+common lineHeight=20 base=16 scaleW=64 scaleH=64 pages=1
+page id=0 file=\"test.png\"
+char id=32 x=0 y=0 width=0 height=0 xoffset=0 yoffset=0 xadvance=5 page=0
+char id=45 x=24 y=0 width=8 height=2 xoffset=1 yoffset=9 xadvance=10 page=0
+char id=65 x=0 y=0 width=8 height=12 xoffset=1 yoffset=4 xadvance=10 page=0
+char id=66 x=8 y=0 width=8 height=12 xoffset=1 yoffset=4 xadvance=10 page=0
+char id=95 x=16 y=0 width=8 height=2 xoffset=1 yoffset=17 xadvance=10 page=0
+kerning first=65 second=66 amount=-2
+";
+
+/// The Font of the descriptor with an empty 64 by 64 page. Needs a context.
+#[allow(dead_code)]
+pub fn font_from(fnt: &str) -> Font {
+    let description = FontDescription::parse_text(fnt).unwrap();
+    let page = Texture::with_format(64, 64, TextureFormat::Rgba8, None).unwrap();
+    Font::from_description(description, vec![page]).unwrap()
+}
+
+/// The Font of `FONT`. Needs a context.
+#[allow(dead_code)]
+pub fn font() -> Font {
+    font_from(FONT)
+}
+
+/// A layout that draws `FONT` in its native size.
+#[allow(dead_code)]
+pub fn pixels() -> TextLayout {
+    TextLayout::new().size(TextSize::Pixels(20.0))
+}
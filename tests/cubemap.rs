@@ -0,0 +1,57 @@
+extern crate dust;
+extern crate gl;
+
+use dust::image::{Image, PixelData, PixelFormat};
+use dust::opengl::cubemap::{self, CubeFace};
+
+/// A gray cross with faces of 2x2 pixels, each pixel holding the index of its cell in the grid
+/// times 10 plus its position within the face.
+fn cross(columns: u32, rows: u32) -> Image {
+    let (width, height) = (columns * 2, rows * 2);
+    let mut data = Vec::new();
+    for y in 0..height {
+        for x in 0..width {
+            let cell = (y / 2) * columns + x / 2;
+            data.push((cell * 10 + (y % 2) * 2 + x % 2) as u8);
+        }
+    }
+    Image::new(width, height, PixelFormat::Gray, PixelData::U8(data)).unwrap()
+}
+
+#[test]
+fn horizontal_cross() {
+    let faces = cubemap::split_cross(&cross(4, 3)).unwrap();
+    let first : Vec<PixelData> = faces.iter().map(|face| face.data().clone()).collect();
+
+    // +X is in column 2 of row 1, i.e. cell 6, -Z in column 3, i.e. cell 7
+    assert_eq!(first[0], PixelData::U8(vec![60, 61, 62, 63]));
+    assert_eq!(first[1], PixelData::U8(vec![40, 41, 42, 43]));
+    assert_eq!(first[2], PixelData::U8(vec![10, 11, 12, 13]));
+    assert_eq!(first[3], PixelData::U8(vec![90, 91, 92, 93]));
+    assert_eq!(first[4], PixelData::U8(vec![50, 51, 52, 53]));
+    assert_eq!(first[5], PixelData::U8(vec![70, 71, 72, 73]));
+}
+
+#[test]
+fn vertical_cross_rotates_negative_z() {
+    let faces = cubemap::split_cross(&cross(3, 4)).unwrap();
+
+    assert_eq!(faces.len(), 6);
+    assert_eq!(*faces[0].data(), PixelData::U8(vec![50, 51, 52, 53]));
+    assert_eq!(*faces[4].data(), PixelData::U8(vec![40, 41, 42, 43]));
+    // -Z is cell 10, stored upside down
+    assert_eq!(*faces[5].data(), PixelData::U8(vec![103, 102, 101, 100]));
+}
+
+#[test]
+fn other_layouts_are_rejected() {
+    assert!(cubemap::split_cross(&cross(4, 4)).is_err());
+    assert!(cubemap::split_cross(&Image::empty(9, 6, PixelFormat::Rgb)).is_err());
+}
+
+#[test]
+fn faces_map_to_gl_targets() {
+    assert_eq!(CubeFace::PositiveX.gl_enum(), gl::TEXTURE_CUBE_MAP_POSITIVE_X);
+    assert_eq!(CubeFace::NegativeY.gl_enum(), gl::TEXTURE_CUBE_MAP_NEGATIVE_Y);
+    assert_eq!(CubeFace::NegativeZ.gl_enum(), gl::TEXTURE_CUBE_MAP_NEGATIVE_Z);
+}
@@ -0,0 +1,148 @@
+extern crate dust;
+extern crate gl;
+
+mod common;
+
+use dust::image::{PixelData, PixelFormat};
+use dust::opengl::TextureFormat;
+use dust::opengl::framebuffer::{self, Framebuffer, FramebufferBuilder};
+
+fn clear(framebuffer: &Framebuffer, color: [f32; 4]) {
+    framebuffer.bind();
+    unsafe {
+        gl::ClearColor(color[0], color[1], color[2], color[3]);
+        gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+    }
+    Framebuffer::unbind();
+}
+
+#[test]
+fn invalid_descriptions_are_rejected_before_creation() {
+    assert!(FramebufferBuilder::new(0, 4).color_texture(TextureFormat::Rgba8).validate().is_err());
+    assert!(FramebufferBuilder::new(4, 4).validate().is_err());
+    assert!(FramebufferBuilder::new(4, 4).color_texture(TextureFormat::Depth24).validate().is_err());
+    assert!(FramebufferBuilder::new(4, 4).depth_texture(TextureFormat::Rgb8).validate().is_err());
+    assert!(FramebufferBuilder::new(4, 4).color_texture(TextureFormat::Rgba8).samples(4).validate().is_err());
+
+    assert!(FramebufferBuilder::new(4, 4).depth_texture(TextureFormat::Depth32f).validate().is_ok());
+    assert!(FramebufferBuilder::new(4, 4).color_renderbuffer(TextureFormat::Rgba8)
+        .depth_renderbuffer(TextureFormat::Depth24Stencil8).samples(4).validate().is_ok());
+}
+
+#[test]
+fn status_messages_are_readable() {
+    assert_eq!(framebuffer::status_message(gl::FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT), "there are no attachments");
+    assert_eq!(framebuffer::status_message(0), "unknown framebuffer status");
+}
+
+#[test]
+fn render_to_texture() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    let target = FramebufferBuilder::new(3, 2)
+        .color_texture(TextureFormat::Rgba8)
+        .depth_renderbuffer(TextureFormat::Depth24Stencil8)
+        .build()
+        .unwrap();
+
+    clear(&target, [1.0, 0.0, 1.0, 1.0]);
+
+    assert!(target.depth_texture().is_none());
+    assert_eq!(target.color_texture(0).unwrap().read_texels(), [255, 0, 255, 255].repeat(6));
+}
+
+#[test]
+fn multiple_render_targets() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    let target = FramebufferBuilder::new(2, 2)
+        .color_texture(TextureFormat::Rgba8)
+        .color_texture(TextureFormat::R8)
+        .depth_texture(TextureFormat::Depth32f)
+        .build()
+        .unwrap();
+
+    target.bind();
+    unsafe {
+        gl::ClearBufferfv(gl::COLOR, 0, [0.0, 1.0, 0.0, 1.0].as_ptr());
+        gl::ClearBufferfv(gl::COLOR, 1, [0.2, 0.0, 0.0, 0.0].as_ptr());
+        gl::ClearBufferfv(gl::DEPTH, 0, [0.5].as_ptr());
+    }
+    Framebuffer::unbind();
+
+    assert_eq!(target.color_attachments().len(), 2);
+    assert_eq!(target.color_texture(0).unwrap().read_texels(), [0, 255, 0, 255].repeat(4));
+    assert_eq!(target.color_texture(1).unwrap().read_texels(), vec![51; 4]);
+    assert_eq!(target.depth_texture().unwrap().read_texels(), 0.5f32.to_ne_bytes().repeat(4));
+}
+
+#[test]
+fn depth_only_framebuffer_for_shadow_maps() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    let shadow_map = FramebufferBuilder::new(4, 4).depth_texture(TextureFormat::Depth24).build().unwrap();
+    assert!(shadow_map.color_texture(0).is_none());
+    assert!(shadow_map.depth_texture().is_some());
+}
+
+#[test]
+fn resolve_multisampled_framebuffer() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    let msaa = FramebufferBuilder::new(4, 4)
+        .color_renderbuffer(TextureFormat::Rgba8)
+        .depth_renderbuffer(TextureFormat::Depth24Stencil8)
+        .samples(4)
+        .build()
+        .unwrap();
+    let resolved = FramebufferBuilder::new(4, 4).color_texture(TextureFormat::Rgba8).build().unwrap();
+
+    clear(&msaa, [0.0, 0.0, 1.0, 1.0]);
+    msaa.resolve_into(&resolved).unwrap();
+
+    assert_eq!(msaa.samples(), 4);
+    assert_eq!(resolved.color_texture(0).unwrap().read_texels(), [0, 0, 255, 255].repeat(16));
+
+    let smaller = FramebufferBuilder::new(2, 2).color_texture(TextureFormat::Rgba8).build().unwrap();
+    assert!(msaa.resolve_into(&smaller).is_err());
+}
+
+#[test]
+fn blit_scales_into_another_framebuffer() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    let source = FramebufferBuilder::new(4, 4).color_texture(TextureFormat::Rgba8).build().unwrap();
+    let target = FramebufferBuilder::new(2, 2).color_texture(TextureFormat::Rgba8).build().unwrap();
+
+    clear(&source, [1.0, 1.0, 0.0, 1.0]);
+    source.blit_to(Some(&target), (2, 2), gl::COLOR_BUFFER_BIT, gl::LINEAR);
+
+    assert_eq!(target.color_texture(0).unwrap().read_texels(), [255, 255, 0, 255].repeat(4));
+}
+
+#[test]
+fn read_pixels_flips_rows() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    let target = FramebufferBuilder::new(2, 2).color_texture(TextureFormat::Rgba8).build().unwrap();
+    clear(&target, [0.0, 0.0, 0.0, 1.0]);
+
+    // paint the upper row white
+    target.bind();
+    unsafe {
+        gl::Enable(gl::SCISSOR_TEST);
+        gl::Scissor(0, 1, 2, 1);
+        gl::ClearColor(1.0, 1.0, 1.0, 1.0);
+        gl::Clear(gl::COLOR_BUFFER_BIT);
+        gl::Disable(gl::SCISSOR_TEST);
+    }
+    Framebuffer::unbind();
+
+    let rgb = target.read_pixels(0, 0, 2, 2, PixelFormat::Rgb).unwrap();
+    assert_eq!(*rgb.data(), PixelData::U8(vec![255, 255, 255, 255, 255, 255, 0, 0, 0, 0, 0, 0]));
+
+    let rgba = target.read_pixels(1, 0, 1, 2, PixelFormat::Rgba).unwrap();
+    assert_eq!(*rgba.data(), PixelData::U8(vec![255, 255, 255, 255, 0, 0, 0, 255]));
+
+    assert!(target.read_pixels(0, 0, 1, 1, PixelFormat::Gray).is_err());
+}
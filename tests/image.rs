@@ -0,0 +1,115 @@
+extern crate dust;
+
+use dust::image::{Image, PixelData, PixelFormat};
+use dust::image::pnm;
+
+fn gray(width: u32, height: u32, data: Vec<u8>) -> Image {
+    Image::new(width, height, PixelFormat::Gray, PixelData::U8(data)).unwrap()
+}
+
+#[test]
+fn size_is_checked() {
+    assert!(Image::new(2, 2, PixelFormat::Rgb, PixelData::U8(vec![0; 11])).is_err());
+    assert!(Image::new(2, 2, PixelFormat::Rgb, PixelData::F32(vec![0.0; 12])).is_ok());
+    assert!(Image::new(0, 4, PixelFormat::Rgb, PixelData::U8(Vec::new())).is_err());
+    assert!(Image::new(4, 0, PixelFormat::Gray, PixelData::U8(Vec::new())).is_err());
+}
+
+#[test]
+#[should_panic(expected = "has no pixels")]
+fn empty_images_need_pixels() {
+    Image::empty(0, 4, PixelFormat::Rgb);
+}
+
+#[test]
+fn flip_vertical() {
+    let mut image = gray(2, 3, vec![1, 2, 3, 4, 5, 6]);
+    image.flip_vertical();
+    assert_eq!(*image.data(), PixelData::U8(vec![5, 6, 3, 4, 1, 2]));
+}
+
+#[test]
+fn crop_and_blit() {
+    let image = gray(3, 3, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]);
+
+    let cropped = image.crop(1, 1, 2, 2).unwrap();
+    assert_eq!((cropped.width(), cropped.height()), (2, 2));
+    assert_eq!(*cropped.data(), PixelData::U8(vec![5, 6, 8, 9]));
+    assert!(image.crop(2, 2, 2, 1).is_err());
+    assert!(image.crop(1, 0, u32::MAX, 1).is_err());
+    assert!(image.crop(0, u32::MAX, 1, 2).is_err());
+    assert!(image.crop(1, 1, 0, 2).is_err());
+
+    // blitting is clipped at the right and bottom border
+    let mut target = gray(3, 3, vec![0; 9]);
+    target.blit(&cropped, 2, 1).unwrap();
+    assert_eq!(*target.data(), PixelData::U8(vec![0, 0, 0, 0, 0, 5, 0, 0, 8]));
+
+    let rgb = Image::empty(1, 1, PixelFormat::Rgb);
+    assert!(target.blit(&rgb, 0, 0).is_err());
+}
+
+#[test]
+fn convert_formats_and_component_types() {
+    let image = Image::new(2, 1, PixelFormat::Rgb, PixelData::U8(vec![255, 255, 255, 255, 0, 0])).unwrap();
+
+    let gray = image.convert(PixelFormat::GrayAlpha);
+    assert_eq!(*gray.data(), PixelData::U8(vec![255, 255, 54, 255]));
+
+    let rgba = gray.convert(PixelFormat::Rgba);
+    assert_eq!(*rgba.data(), PixelData::U8(vec![255, 255, 255, 255, 54, 54, 54, 255]));
+
+    assert_eq!(*image.to_u16().data(), PixelData::U16(vec![65535, 65535, 65535, 65535, 0, 0]));
+    assert_eq!(*image.to_f32().data(), PixelData::F32(vec![1.0, 1.0, 1.0, 1.0, 0.0, 0.0]));
+    assert_eq!(image.to_f32().to_u8(), image);
+}
+
+#[test]
+fn premultiply_alpha() {
+    let mut image = Image::new(1, 2, PixelFormat::Rgba, PixelData::U8(vec![255, 128, 0, 128, 200, 100, 50, 0])).unwrap();
+    image.premultiply_alpha();
+    assert_eq!(*image.data(), PixelData::U8(vec![128, 64, 0, 128, 0, 0, 0, 0]));
+}
+
+#[test]
+fn pnm_to_image_scales_to_full_range() {
+    let image = pnm::decode(b"P2 2 1 15 0 15").unwrap().to_image().unwrap();
+    assert_eq!(image.format(), PixelFormat::Gray);
+    assert_eq!(*image.data(), PixelData::U8(vec![0, 255]));
+}
+
+#[test]
+fn downsample_averages_blocks() {
+    let image = gray(4, 2, vec![0, 100, 10, 10, 200, 100, 30, 50]);
+    let half = image.downsample();
+
+    assert_eq!((half.width(), half.height()), (2, 1));
+    assert_eq!(*half.data(), PixelData::U8(vec![100, 25]));
+}
+
+#[test]
+fn downsample_keeps_sides_of_one_and_drops_odd_pixels() {
+    let column = gray(1, 4, vec![0, 2, 4, 8]).downsample();
+    assert_eq!((column.width(), column.height()), (1, 2));
+    assert_eq!(*column.data(), PixelData::U8(vec![1, 6]));
+
+    let odd = gray(3, 3, vec![4, 4, 99, 4, 4, 99, 99, 99, 99]).downsample();
+    assert_eq!(*odd.data(), PixelData::U8(vec![4]));
+}
+
+#[test]
+fn mip_chain_ends_at_one_pixel() {
+    let image = Image::new(8, 2, PixelFormat::Rgb, PixelData::F32(vec![0.5; 48])).unwrap();
+    let sizes : Vec<(u32, u32)> = image.mip_chain().iter().map(|level| (level.width(), level.height())).collect();
+
+    assert_eq!(sizes, vec![(4, 1), (2, 1), (1, 1)]);
+    assert_eq!(*image.mip_chain()[2].data(), PixelData::F32(vec![0.5; 3]));
+    assert!(gray(1, 1, vec![0]).mip_chain().is_empty());
+}
+
+#[test]
+fn flip_horizontal() {
+    let mut image = Image::new(3, 2, PixelFormat::GrayAlpha, PixelData::U8(vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12])).unwrap();
+    image.flip_horizontal();
+    assert_eq!(*image.data(), PixelData::U8(vec![5, 6, 3, 4, 1, 2, 11, 12, 9, 10, 7, 8]));
+}
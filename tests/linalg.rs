@@ -0,0 +1,39 @@
+extern crate dust;
+
+use dust::linalg::{Matrix4, Vector3};
+use std::slice;
+
+fn elements(matrix: &Matrix4) -> Vec<f32> {
+    unsafe { slice::from_raw_parts(matrix.as_ptr(), 16).to_vec() }
+}
+
+#[test]
+fn strip_translation_keeps_the_rotation() {
+    let mut view = Matrix4::new();
+    view.look_at(&Vector3::new(2.0, 2.0, 3.0), &Vector3::zero(), &Vector3::new(0.0, 0.0, 1.0));
+
+    let mut rotation = Matrix4::new();
+    rotation.copy(&view);
+    rotation.strip_translation();
+
+    let (before, after) = (elements(&view), elements(&rotation));
+    assert_eq!(&before[..12], &after[..12]);
+    assert_eq!(&after[12..], &[0.0, 0.0, 0.0, 1.0]);
+}
+
+#[test]
+fn orthographic_maps_the_box_onto_clip_space() {
+    let mut projection = Matrix4::new();
+    projection.orthographic(0.0, 640.0, 480.0, 0.0, 0.0, 1.0);
+
+    let m = elements(&projection);
+    let transform = |x: f32, y: f32, z: f32| [
+        m[0] * x + m[4] * y + m[8] * z + m[12],
+        m[1] * x + m[5] * y + m[9] * z + m[13],
+        m[2] * x + m[6] * y + m[10] * z + m[14],
+    ];
+
+    assert_eq!(transform(0.0, 0.0, 0.0), [-1.0, 1.0, -1.0]);
+    assert_eq!(transform(640.0, 480.0, -1.0), [1.0, -1.0, 1.0]);
+    assert_eq!(transform(320.0, 240.0, -0.5), [0.0, 0.0, 0.0]);
+}
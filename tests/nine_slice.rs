@@ -0,0 +1,75 @@
+extern crate dust;
+
+use dust::opengl::Sprite;
+use dust::opengl::nine_slice::{Insets, NineSlice};
+
+/// The size of the texture the sprite is part of.
+const TEXTURE : (u32, u32) = (64, 32);
+
+/// A 16 x 12 sprite at (8, 4) with borders of 2 left, 3 top, 4 right and 1 bottom.
+fn panel() -> NineSlice {
+    NineSlice::new(Sprite::new(8, 4, 16, 12), Insets::new(2, 3, 4, 1)).unwrap()
+}
+
+/// The left, bottom, right and top of positions and uvs of a quad.
+fn quad(vertices: &[Vec<f32>]) -> ([f32; 4], [f32; 4]) {
+    let (bottom_left, top_right) = (&vertices[0], &vertices[2]);
+    ([bottom_left[0], bottom_left[1], top_right[0], top_right[1]],
+     [bottom_left[3], bottom_left[4], top_right[3], top_right[4]])
+}
+
+#[test]
+fn reject_overlapping_insets() {
+    assert!(NineSlice::new(Sprite::new(0, 0, 8, 8), Insets::uniform(4)).is_ok());
+    assert!(NineSlice::new(Sprite::new(0, 0, 8, 8), Insets::new(5, 0, 4, 0)).is_err());
+    assert!(NineSlice::new(Sprite::new(0, 0, 8, 8), Insets::new(0, 5, 0, 4)).is_err());
+    assert!(NineSlice::new(Sprite::new(0, 0, 8, 8), Insets::new(u32::MAX, 0, 1, 0)).is_err());
+}
+
+#[test]
+fn corners_keep_their_size() {
+    let mesh = panel().mesh(TEXTURE, 100.0, 50.0);
+    assert_eq!(mesh.vertex_count(), 36);
+    assert_eq!(mesh.faces().len(), 18);
+
+    let quads : Vec<([f32; 4], [f32; 4])> = mesh.vertices().chunks(4).map(quad).collect();
+
+    // top left corner: sprite x from 8 to 10 and y from 4 to 7, counted from the top of 32
+    assert_eq!(quads[0], ([0.0, -3.0, 2.0, 0.0], [8.0 / 64.0, 25.0 / 32.0, 10.0 / 64.0, 28.0 / 32.0]));
+    // top edge stretches horizontally
+    assert_eq!(quads[1], ([2.0, -3.0, 96.0, 0.0], [10.0 / 64.0, 25.0 / 32.0, 20.0 / 64.0, 28.0 / 32.0]));
+    // center stretches in both directions
+    assert_eq!(quads[4], ([2.0, -49.0, 96.0, -3.0], [10.0 / 64.0, 17.0 / 32.0, 20.0 / 64.0, 25.0 / 32.0]));
+    // bottom right corner
+    assert_eq!(quads[8], ([96.0, -50.0, 100.0, -49.0], [20.0 / 64.0, 16.0 / 32.0, 24.0 / 64.0, 17.0 / 32.0]));
+}
+
+#[test]
+fn borders_shrink_to_fit_small_sizes() {
+    let mesh = panel().mesh(TEXTURE, 3.0, 8.0);
+    let quads : Vec<([f32; 4], [f32; 4])> = mesh.vertices().chunks(4).map(quad).collect();
+
+    // 2 + 4 pixels of border share 3 units, the center gets none
+    assert_eq!(quads[0].0, [0.0, -3.0, 1.0, 0.0]);
+    assert_eq!(quads[4].0, [1.0, -7.0, 1.0, -3.0]);
+    assert_eq!(quads[8].0, [1.0, -8.0, 3.0, -7.0]);
+}
+
+#[test]
+fn border_scale_sizes_the_borders() {
+    let mesh = panel().border_scale(0.5).mesh(TEXTURE, 10.0, 10.0);
+    let quads : Vec<([f32; 4], [f32; 4])> = mesh.vertices().chunks(4).map(quad).collect();
+
+    assert_eq!(quads[0].0, [0.0, -1.5, 1.0, 0.0]);
+    assert_eq!(quads[8].0, [8.0, -10.0, 10.0, -9.5]);
+    assert_eq!(quads[8].1, [20.0 / 64.0, 16.0 / 32.0, 24.0 / 64.0, 17.0 / 32.0]);
+}
+
+#[test]
+fn sprites_beyond_the_texture_do_not_overflow() {
+    let slice = NineSlice::new(Sprite::new(60, 28, 8, 8), Insets::uniform(2)).unwrap();
+    let quads : Vec<([f32; 4], [f32; 4])> = slice.mesh(TEXTURE, 8.0, 8.0).vertices().chunks(4).map(quad).collect();
+
+    // the bottom right corner lies below and right of the texture
+    assert_eq!(quads[8].1, [66.0 / 64.0, -4.0 / 32.0, 68.0 / 64.0, -2.0 / 32.0]);
+}
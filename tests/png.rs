@@ -0,0 +1,193 @@
+extern crate dust;
+extern crate miniz_oxide;
+
+use dust::image::{self, png, Image, PixelData, PixelFormat};
+use miniz_oxide::deflate::compress_to_vec_zlib;
+
+fn chunk(png: &mut Vec<u8>, kind: &[u8], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let mut body = kind.to_vec();
+    body.extend_from_slice(data);
+    png.extend_from_slice(&body);
+    png.extend_from_slice(&png::crc32(&body).to_be_bytes());
+}
+
+/// Builds a PNG from already filtered scanlines.
+fn encode(width: u32, height: u32, bit_depth: u8, color_type: u8, interlace: u8,
+          extra: &[(&[u8], Vec<u8>)], scanlines: &[u8]) -> Vec<u8> {
+    let mut png = png::SIGNATURE.to_vec();
+
+    let mut header = Vec::new();
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    header.extend_from_slice(&[bit_depth, color_type, 0, 0, interlace]);
+    chunk(&mut png, b"IHDR", &header);
+
+    for (kind, data) in extra {
+        chunk(&mut png, kind, data);
+    }
+
+    // split the data stream to make sure consecutive IDAT chunks are joined
+    let compressed = compress_to_vec_zlib(scanlines, 6);
+    let (first, second) = compressed.split_at(compressed.len() / 2);
+    chunk(&mut png, b"IDAT", first);
+    chunk(&mut png, b"IDAT", second);
+    chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+#[test]
+fn rgb_with_all_filter_types() {
+    // one row per filter type, each row holding the pixel (10, 20, 30) twice
+    let scanlines = [
+        0, 10, 20, 30, 10, 20, 30,
+        1, 10, 20, 30, 0, 0, 0,
+        2, 0, 0, 0, 0, 0, 0,
+        3, 5, 10, 15, 0, 0, 0,
+        4, 0, 0, 0, 0, 0, 0,
+    ];
+    let image = png::decode(&encode(2, 5, 8, 2, 0, &[], &scanlines)).unwrap();
+
+    assert_eq!((image.width(), image.height(), image.format()), (2, 5, PixelFormat::Rgb));
+    assert_eq!(*image.data(), PixelData::U8([10, 20, 30].repeat(10)));
+}
+
+#[test]
+fn palette_with_transparency() {
+    let palette = vec![255, 0, 0, 0, 255, 0, 0, 0, 255];
+    let alpha   = vec![0, 128];
+    // 2 bit indices 0, 1, 2, 1
+    let scanlines = [0, 0b0001_1001];
+    let image = png::decode(&encode(4, 1, 2, 3, 0, &[(b"PLTE", palette), (b"tRNS", alpha)], &scanlines)).unwrap();
+
+    assert_eq!(image.format(), PixelFormat::Rgba);
+    assert_eq!(*image.data(), PixelData::U8(vec![
+        255, 0, 0, 0,
+        0, 255, 0, 128,
+        0, 0, 255, 255,
+        0, 255, 0, 128,
+    ]));
+}
+
+#[test]
+fn low_bit_depth_gray_is_scaled() {
+    let scanlines = [0, 0b0001_1011, 0, 0b1110_0100];
+    let image = png::decode(&encode(4, 2, 2, 0, 0, &[], &scanlines)).unwrap();
+
+    assert_eq!(image.format(), PixelFormat::Gray);
+    assert_eq!(*image.data(), PixelData::U8(vec![0, 85, 170, 255, 255, 170, 85, 0]));
+}
+
+#[test]
+fn sixteen_bit_gray_alpha() {
+    let scanlines = [0, 0x12, 0x34, 0xff, 0xff, 0xab, 0xcd, 0x00, 0x01];
+    let image = png::decode(&encode(2, 1, 16, 4, 0, &[], &scanlines)).unwrap();
+
+    assert_eq!(image.format(), PixelFormat::GrayAlpha);
+    assert_eq!(*image.data(), PixelData::U16(vec![0x1234, 0xffff, 0xabcd, 0x0001]));
+}
+
+#[test]
+fn rgba() {
+    let scanlines = [0, 1, 2, 3, 4];
+    let image = png::decode(&encode(1, 1, 8, 6, 0, &[], &scanlines)).unwrap();
+
+    assert_eq!(image.format(), PixelFormat::Rgba);
+    assert_eq!(*image.data(), PixelData::U8(vec![1, 2, 3, 4]));
+}
+
+#[test]
+fn adam7_interlacing() {
+    // 3x3 gray image with the values 0..9, passes 1, 4, 6 and 7 hold pixels for this size:
+    // pass 1: (0,0); pass 4: (2,0); pass 5: (0,2), (2,2); pass 6: (1,0), (1,2); pass 7: (0,1), (1,1), (2,1)
+    let scanlines = [
+        0, 0,
+        0, 2,
+        0, 6, 8,
+        0, 1, 0, 7,
+        0, 3, 4, 5,
+    ];
+    let image = png::decode(&encode(3, 3, 8, 0, 1, &[], &scanlines)).unwrap();
+
+    assert_eq!(*image.data(), PixelData::U8((0..9).collect()));
+}
+
+#[test]
+fn corrupt_files_are_rejected() {
+    let mut png = encode(1, 1, 8, 0, 0, &[], &[0, 7]);
+    assert!(png::decode(&png).is_ok());
+
+    // flip a bit in the IHDR chunk, the CRC no longer matches
+    png[20] ^= 1;
+    assert!(png::decode(&png).is_err());
+
+    assert!(png::decode(b"\x89PNG").is_err());
+    assert!(png::decode(&encode(1, 1, 8, 0, 0, &[], &[0])).is_err());
+}
+
+#[test]
+fn crc32_check_value() {
+    assert_eq!(png::crc32(b"123456789"), 0xcbf4_3926);
+}
+
+#[test]
+fn decode_dispatches_on_signature() {
+    let image = image::decode(&encode(1, 1, 8, 0, 0, &[], &[0, 7])).unwrap();
+    assert_eq!(*image.data(), PixelData::U8(vec![7]));
+
+    let image = image::decode(b"P2 1 1 255 9").unwrap();
+    assert_eq!(*image.data(), PixelData::U8(vec![9]));
+
+    assert!(image::decode(b"no image at all").is_err());
+}
+
+#[test]
+fn encode_round_trips_all_formats() {
+    let images = vec![
+        Image::new(3, 2, PixelFormat::Gray, PixelData::U8(vec![0, 50, 100, 150, 200, 250])).unwrap(),
+        Image::new(2, 2, PixelFormat::GrayAlpha, PixelData::U8(vec![1, 2, 3, 4, 5, 6, 7, 8])).unwrap(),
+        Image::new(2, 3, PixelFormat::Rgb, PixelData::U8((0..18).map(|i| i * 13).collect())).unwrap(),
+        Image::new(1, 2, PixelFormat::Rgba, PixelData::U16(vec![0, 1, 65535, 300, 7, 8, 9, 10])).unwrap(),
+    ];
+
+    for image in &images {
+        assert_eq!(png::decode(&png::encode(image)).unwrap(), *image);
+    }
+
+    // floating point images are stored with 16 bits
+    let float = Image::new(1, 1, PixelFormat::Gray, PixelData::F32(vec![1.0])).unwrap();
+    assert_eq!(*png::decode(&png::encode(&float)).unwrap().data(), PixelData::U16(vec![65535]));
+}
+
+#[test]
+fn save_picks_the_format_by_extension() {
+    let image = Image::new(2, 1, PixelFormat::Rgb, PixelData::U8(vec![1, 2, 3, 4, 5, 6])).unwrap();
+    let directory = std::env::temp_dir();
+
+    for name in &["dust-save-test.png", "dust-save-test.PPM"] {
+        let path = directory.join(name);
+        image::save(&image, &path).unwrap();
+        assert_eq!(image::load(&path).unwrap(), image);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    assert!(image::save(&image, directory.join("dust-save-test.gif")).is_err());
+}
+
+#[test]
+fn truncated_files_are_rejected() {
+    // the filtered rows of the four Adam7 passes a 3 x 2 RGB image has
+    let png = encode(3, 2, 8, 2, 1, &[], &[0; 4 + 4 + 4 + 10]);
+    assert!(png::decode(&png).is_ok());
+    for length in 0..png.len() {
+        assert!(png::decode(&png[..length]).is_err());
+    }
+
+    // the scanlines of a small image that claims to be huge, interlaced or not
+    assert!(png::decode(&encode(0x7fff_ffff, 0x7fff_ffff, 16, 6, 0, &[], &[0; 64])).is_err());
+    assert!(png::decode(&encode(0x7fff_ffff, 0x7fff_ffff, 16, 6, 1, &[], &[0; 64])).is_err());
+    assert!(png::decode(&encode(100_000, 100_000, 8, 0, 1, &[], &[0; 64])).is_err());
+
+    // more data than the header asks for
+    assert!(png::decode(&encode(1, 1, 8, 0, 0, &[], &[0; 64])).is_err());
+}
@@ -0,0 +1,126 @@
+extern crate dust;
+
+use dust::image::{Image, PixelData, PixelFormat};
+use dust::image::pnm::{self, PnmSamples};
+
+fn binary(header: &str, raster: &[u8]) -> Vec<u8> {
+    let mut bytes = header.as_bytes().to_vec();
+    bytes.extend_from_slice(raster);
+    bytes
+}
+
+#[test]
+fn ascii_bitmap() {
+    let image = pnm::decode(b"P1\n# a comment\n3 2\n1 0 1\n010\n").unwrap();
+
+    assert_eq!((image.width(), image.height(), image.depth(), image.max_value()), (3, 2, 1, 1));
+    assert_eq!(image.tuple_type(), "BLACKANDWHITE");
+    // 1 is black in PBM and ends up as 0
+    assert_eq!(*image.samples(), PnmSamples::U8(vec![0, 1, 0, 1, 0, 1]));
+}
+
+#[test]
+fn binary_bitmap_rows_are_padded_to_bytes() {
+    let image = pnm::decode(&binary("P4 10 2\n", &[0b1000_0000, 0b0100_0000, 0b0000_0000, 0b1100_0000])).unwrap();
+
+    assert_eq!((image.width(), image.height()), (10, 2));
+    assert_eq!(*image.samples(), PnmSamples::U8(vec![
+        0, 1, 1, 1, 1, 1, 1, 1, 1, 0,
+        1, 1, 1, 1, 1, 1, 1, 1, 0, 0,
+    ]));
+}
+
+#[test]
+fn ascii_graymap_and_pixmap() {
+    let gray = pnm::decode(b"P2 2 2 15 0 5\n10 15").unwrap();
+    assert_eq!(gray.tuple_type(), "GRAYSCALE");
+    assert_eq!(gray.max_value(), 15);
+    assert_eq!(*gray.samples(), PnmSamples::U8(vec![0, 5, 10, 15]));
+    assert_eq!(gray.to_rgb8(), vec![0, 0, 0, 85, 85, 85, 170, 170, 170, 255, 255, 255]);
+
+    let rgb = pnm::decode(b"P3\n#c1\n#c2\n1 1\n#c3\n255\n1 2 3\n").unwrap();
+    assert_eq!(rgb.depth(), 3);
+    assert_eq!(*rgb.samples(), PnmSamples::U8(vec![1, 2, 3]));
+}
+
+#[test]
+fn binary_pixmap_without_comment_and_with_several_comments() {
+    let raster = [10, 20, 30, 40, 50, 60];
+
+    let plain = pnm::decode(&binary("P6\n2 1\n255\n", &raster)).unwrap();
+    assert_eq!(*plain.samples(), PnmSamples::U8(raster.to_vec()));
+
+    let commented = pnm::decode(&binary("P6\n# one\n# two\n2 # three\n1\t255 ", &raster)).unwrap();
+    assert_eq!(*commented.samples(), PnmSamples::U8(raster.to_vec()));
+}
+
+#[test]
+fn sixteen_bit_samples_are_big_endian() {
+    let image = pnm::decode(&binary("P5 2 1 65535\n", &[0x01, 0x02, 0xff, 0xff])).unwrap();
+
+    assert_eq!(*image.samples(), PnmSamples::U16(vec![0x0102, 0xffff]));
+    assert_eq!(image.to_rgb8(), vec![1, 1, 1, 255, 255, 255]);
+}
+
+#[test]
+fn trailing_bytes_are_ignored() {
+    let image = pnm::decode(&binary("P5 2 1 255\n", &[1, 2, 3, 4, 5, 6, 7])).unwrap();
+
+    assert_eq!(*image.samples(), PnmSamples::U8(vec![1, 2]));
+}
+
+#[test]
+fn pam_with_alpha() {
+    let header = "P7\nWIDTH 2\nHEIGHT 1\n# comment\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n";
+    let image = pnm::decode(&binary(header, &[1, 2, 3, 4, 5, 6, 7, 8])).unwrap();
+
+    assert_eq!((image.width(), image.height(), image.depth()), (2, 1, 4));
+    assert!(image.has_alpha());
+    assert_eq!(*image.samples(), PnmSamples::U8(vec![1, 2, 3, 4, 5, 6, 7, 8]));
+    assert_eq!(image.to_rgb8(), vec![1, 2, 3, 5, 6, 7]);
+}
+
+#[test]
+fn malformed_files_are_rejected() {
+    assert!(pnm::decode(b"").is_err());
+    assert!(pnm::decode(b"P9 1 1 255\n").is_err());
+    assert!(pnm::decode(b"P6 2 2 255\n\x01\x02").is_err());
+    assert!(pnm::decode(b"P5 1 1 0\n\x00").is_err());
+    assert!(pnm::decode(b"P5 1 1 70000\n\x00\x00").is_err());
+    assert!(pnm::decode(b"P2 1 1 10 11").is_err());
+    assert!(pnm::decode(b"P5 1 1 10\n\xff").is_err());
+    assert!(pnm::decode(b"P5 1 1 1000\n\x03\xe9").is_err());
+    assert!(pnm::decode(b"P7\nWIDTH 1\nHEIGHT 1\nDEPTH 1\n").is_err());
+    assert!(pnm::decode(b"P7\nWIDTH 1\nHEIGHT 1\nDEPTH 1\nENDHDR\n\x00").is_err());
+}
+
+#[test]
+fn oversized_headers_are_rejected_without_allocating() {
+    assert!(pnm::decode(b"P2 100000 100000 255\n1 2 3").is_err());
+    assert!(pnm::decode(b"P1 100000 100000\n0 1").is_err());
+    assert!(pnm::decode(b"P4 100000 100000\n\x00").is_err());
+    assert!(pnm::decode(b"P6 4294967295 4294967295 65535\n\x00").is_err());
+    assert!(pnm::decode(b"P7\nWIDTH 4294967295\nHEIGHT 4294967295\nDEPTH 255\nMAXVAL 255\nENDHDR\n\x00").is_err());
+}
+
+#[test]
+fn bundled_assets() {
+    let image = pnm::load("assets/crate.pnm").unwrap();
+
+    assert_eq!((image.width(), image.height(), image.depth(), image.max_value()), (512, 512, 3, 255));
+}
+
+#[test]
+fn encode_picks_pgm_ppm_or_pam() {
+    let gray = Image::new(2, 1, PixelFormat::Gray, PixelData::U16(vec![1, 60000])).unwrap();
+    let rgb  = Image::new(1, 1, PixelFormat::Rgb, PixelData::U8(vec![1, 2, 3])).unwrap();
+    let rgba = Image::new(1, 1, PixelFormat::Rgba, PixelData::U8(vec![1, 2, 3, 4])).unwrap();
+
+    assert!(pnm::encode(&gray).starts_with(b"P5\n2 1\n65535\n"));
+    assert_eq!(pnm::encode(&rgb), b"P6\n1 1\n255\n\x01\x02\x03".to_vec());
+    assert!(pnm::encode(&rgba).starts_with(b"P7\n"));
+
+    for image in &[gray, rgb, rgba] {
+        assert_eq!(pnm::decode(&pnm::encode(image)).unwrap().to_image().unwrap(), *image);
+    }
+}
@@ -0,0 +1,107 @@
+extern crate dust;
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use dust::opengl::preprocessor::{Preprocessor, ShaderSource};
+
+/// Creates an empty directory for the files of one test.
+fn scratch(name: &str) -> PathBuf {
+    let directory = env::temp_dir().join(format!("dust_preprocessor_{}", name));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(directory.join("lib")).unwrap();
+    directory
+}
+
+#[test]
+fn include_from_the_directory_and_the_search_paths() {
+    let directory = scratch("include");
+    fs::write(directory.join("main.glsl"), "#version 330\n#include \"local.glsl\"\n#include <lib/shared.glsl>\nvoid main() {}\n").unwrap();
+    fs::write(directory.join("local.glsl"), "float local;\n").unwrap();
+
+    // lib/shared.glsl is only found through the search path, helper.glsl next to it
+    let other = scratch("include_search_path");
+    fs::write(other.join("lib").join("shared.glsl"), "float shared;\n#include \"helper.glsl\"\n").unwrap();
+    fs::write(other.join("lib").join("helper.glsl"), "float helper;\n").unwrap();
+
+    let source = Preprocessor::new().search_path(&other).process_file(directory.join("main.glsl")).unwrap();
+    assert_eq!(source.code(), "#version 330\nfloat local;\nfloat shared;\nfloat helper;\nvoid main() {}");
+    assert_eq!(source.files().len(), 4);
+    assert_eq!(source.files()[0], directory.join("main.glsl"));
+
+    assert!(Preprocessor::new().process_file(directory.join("main.glsl")).is_err());
+    fs::remove_dir_all(&directory).unwrap();
+    fs::remove_dir_all(&other).unwrap();
+}
+
+#[test]
+fn defines_follow_the_version() {
+    let code = "// a comment\n#version 330\nvoid main() {}\0";
+    let source = Preprocessor::new().define("COUNT", "4").flag("SHADOWS").process("inline", code).unwrap();
+
+    assert_eq!(source.code(), "// a comment\n#version 330\n#define COUNT 4\n#define SHADOWS\nvoid main() {}");
+    assert_eq!(source.origin(2), Some(("inline", 2)));
+    assert_eq!(source.origin(3), Some(("<define>", 0)));
+    assert_eq!(source.origin(5), Some(("inline", 3)));
+    assert_eq!(source.origin(6), None);
+
+    let source = Preprocessor::new().flag("SHADOWS").process("inline", "void main() {}").unwrap();
+    assert_eq!(source.code(), "#define SHADOWS\nvoid main() {}");
+}
+
+#[test]
+fn reject_recursive_and_missing_includes() {
+    let directory = scratch("recursive");
+    fs::write(directory.join("a.glsl"), "#include \"b.glsl\"\n").unwrap();
+    fs::write(directory.join("b.glsl"), "float b;\n#include \"a.glsl\"\n").unwrap();
+    fs::write(directory.join("c.glsl"), "#include \"missing.glsl\"\n").unwrap();
+    fs::write(directory.join("d.glsl"), "#include missing.glsl\n").unwrap();
+
+    let preprocessor = Preprocessor::new();
+    let error = preprocessor.process_file(directory.join("a.glsl")).unwrap_err();
+    assert!(error.contains("recursive #include"), "{}", error);
+    let error = preprocessor.process_file(directory.join("c.glsl")).unwrap_err();
+    assert!(error.contains("could not find included file 'missing.glsl'"), "{}", error);
+    let error = preprocessor.process_file(directory.join("d.glsl")).unwrap_err();
+    assert!(error.contains("malformed #include"), "{}", error);
+
+    // including the same file twice is fine as long as it does not include itself
+    fs::write(directory.join("e.glsl"), "#include \"f.glsl\"\n#include \"f.glsl\"\n").unwrap();
+    fs::write(directory.join("f.glsl"), "float f;\n").unwrap();
+    let source = preprocessor.process_file(directory.join("e.glsl")).unwrap();
+    assert_eq!(source.code(), "float f;\nfloat f;");
+    assert_eq!(source.files().len(), 2);
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn map_log_to_the_original_lines() {
+    let directory = scratch("map_log");
+    fs::write(directory.join("main.glsl"), "#version 330\n#include \"light.glsl\"\nvoid main() {}\n").unwrap();
+    fs::write(directory.join("light.glsl"), "float a;\nfloat b = c;\n").unwrap();
+    let light = directory.join("light.glsl").display().to_string();
+    let main = directory.join("main.glsl").display().to_string();
+
+    let source = Preprocessor::new().flag("X").process_file(directory.join("main.glsl")).unwrap();
+    assert_eq!(source.origin(4), Some((light.as_str(), 2)));
+
+    // Mesa, AMD and Intel write 0:line(column), NVIDIA writes 0(line)
+    assert_eq!(source.map_log("0:4(11): error: `c' undeclared\n"), format!("{}:2(11): error: `c' undeclared\n", light));
+    assert_eq!(source.map_log("ERROR: 0(5) : syntax error"), format!("ERROR: {}:3 : syntax error\n", main));
+    assert_eq!(source.map_log("warning: 0:2: x"), "warning: <define>:0: x\n");
+
+    // unknown lines and lines without a reference stay as they are
+    assert_eq!(source.map_log("0:99(1): error\nlinking failed"), "0:99(1): error\nlinking failed\n");
+    assert_eq!(source.map_log("vec3 (1.0) and v2:3"), "vec3 (1.0) and v2:3\n");
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn code_without_preprocessing_maps_onto_itself() {
+    let source = ShaderSource::from_code("embedded", "a\nb\0");
+    assert_eq!(source.name(), "embedded");
+    assert_eq!(source.origin(2), Some(("embedded", 2)));
+    assert_eq!(source.to_bytes(), b"a\nb\n\0".to_vec());
+    assert_eq!(source.map_log("0:1(3): error"), "embedded:1(3): error\n");
+}
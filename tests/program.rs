@@ -0,0 +1,224 @@
+extern crate dust;
+extern crate gl;
+
+mod common;
+
+use std::mem;
+use std::ptr;
+
+use dust::image::{PixelData, PixelFormat};
+use dust::opengl::{TextureFormat, VertexBufferObject};
+use dust::opengl::framebuffer::{Framebuffer, FramebufferBuilder};
+use dust::opengl::program::{self, Program, ProgramBuilder, ShaderStage};
+
+const VERTEX_SHADER_SRC : &[u8] = b"
+#version 330
+in vec3 position;
+void main() {
+    gl_Position = vec4(position, 1.0);
+}
+\0";
+
+const FRAGMENT_SHADER_SRC : &[u8] = b"
+#version 330
+out vec4 color;
+void main() {
+    color = vec4(1.0);
+}
+\0";
+
+const COMPUTE_SHADER_SRC : &[u8] = b"
+#version 430
+layout(local_size_x = 1) in;
+void main() {}
+\0";
+
+const GEOMETRY_SHADER_SRC : &[u8] = b"
+#version 330
+layout(points) in;
+layout(triangle_strip, max_vertices = 4) out;
+void main() {
+    gl_Position = vec4(-1.0, -1.0, 0.0, 1.0); EmitVertex();
+    gl_Position = vec4( 1.0, -1.0, 0.0, 1.0); EmitVertex();
+    gl_Position = vec4(-1.0,  1.0, 0.0, 1.0); EmitVertex();
+    gl_Position = vec4( 1.0,  1.0, 0.0, 1.0); EmitVertex();
+    EndPrimitive();
+}
+\0";
+
+const TESS_CONTROL_SHADER_SRC : &[u8] = b"
+#version 400
+layout(vertices = 3) out;
+void main() {
+    gl_out[gl_InvocationID].gl_Position = gl_in[gl_InvocationID].gl_Position;
+    gl_TessLevelOuter[0] = 2.0;
+    gl_TessLevelOuter[1] = 2.0;
+    gl_TessLevelOuter[2] = 2.0;
+    gl_TessLevelInner[0] = 2.0;
+}
+\0";
+
+const TESS_EVALUATION_SHADER_SRC : &[u8] = b"
+#version 400
+layout(triangles) in;
+void main() {
+    gl_Position = gl_TessCoord.x * gl_in[0].gl_Position
+                + gl_TessCoord.y * gl_in[1].gl_Position
+                + gl_TessCoord.z * gl_in[2].gl_Position;
+}
+\0";
+
+const SQUARES_SHADER_SRC : &[u8] = b"
+#version 430
+layout(local_size_x = 1) in;
+layout(std430, binding = 0) buffer Values {
+    uint values[];
+};
+void main() {
+    uint index = gl_GlobalInvocationID.x;
+    values[index] = index * index;
+}
+\0";
+
+/// Draws the vertices with the program into a small framebuffer and returns the color in its center.
+fn center(program: &Program, vertices: &[f32], draw: &dyn Fn(&VertexBufferObject)) -> [u8; 4] {
+    let target = FramebufferBuilder::new(16, 16).color_texture(TextureFormat::Rgba8).build().unwrap();
+    target.bind();
+
+    let location = program.attrib_location("position").unwrap() as gl::types::GLuint;
+    let mut vao = 0;
+    unsafe {
+        gl::Viewport(0, 0, 16, 16);
+        gl::ClearColor(0.0, 0.0, 0.0, 0.0);
+        gl::Clear(gl::COLOR_BUFFER_BIT);
+        gl::GenVertexArrays(1, &mut vao);
+        gl::BindVertexArray(vao);
+    }
+    let vbo = VertexBufferObject::from_vec_as_array_buffer(&vertices.to_vec(), 3);
+    unsafe {
+        gl::EnableVertexAttribArray(location);
+        gl::VertexAttribPointer(location, 3, gl::FLOAT, gl::FALSE, 3 * mem::size_of::<f32>() as i32, ptr::null());
+    }
+    program.use_program();
+    draw(&vbo);
+
+    let image = target.read_pixels(0, 0, 16, 16, PixelFormat::Rgba).unwrap();
+    Framebuffer::unbind();
+    unsafe {
+        gl::DeleteVertexArrays(1, &vao);
+    }
+    match *image.data() {
+        PixelData::U8(ref data) => {
+            let at = (8 * 16 + 8) * 4;
+            [data[at], data[at + 1], data[at + 2], data[at + 3]]
+        },
+        _ => panic!("expected 8 bit pixels"),
+    }
+}
+
+/// The validation error of a builder, which is returned before any shader is compiled.
+fn error(builder: ProgramBuilder) -> String {
+    match builder.build() {
+        Ok(_)    => panic!("expected the builder to be rejected"),
+        Err(msg) => msg,
+    }
+}
+
+#[test]
+fn reject_empty_builders() {
+    assert!(error(ProgramBuilder::new()).contains("at least one shader stage"));
+}
+
+#[test]
+fn reject_duplicate_stages() {
+    let builder = ProgramBuilder::new().vertex(VERTEX_SHADER_SRC).fragment(FRAGMENT_SHADER_SRC)
+        .stage(ShaderStage::Fragment, FRAGMENT_SHADER_SRC);
+    assert!(error(builder).contains("Fragment stage was given more than once"));
+}
+
+#[test]
+fn reject_compute_with_other_stages() {
+    let builder = ProgramBuilder::new().compute(COMPUTE_SHADER_SRC).vertex(VERTEX_SHADER_SRC);
+    assert!(error(builder).contains("compute shader cannot be combined"));
+}
+
+#[test]
+fn reject_missing_vertex_stage() {
+    let builder = ProgramBuilder::new().fragment(FRAGMENT_SHADER_SRC);
+    assert!(error(builder).contains("needs a vertex shader"));
+}
+
+#[test]
+fn reject_tess_control_without_evaluation() {
+    let builder = ProgramBuilder::new().vertex(VERTEX_SHADER_SRC).tess_control(b"\0").fragment(FRAGMENT_SHADER_SRC);
+    assert!(error(builder).contains("requires a tessellation evaluation shader"));
+}
+
+#[test]
+fn build_programs() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    let program = ProgramBuilder::new().vertex(VERTEX_SHADER_SRC).fragment(FRAGMENT_SHADER_SRC).build().unwrap();
+    assert!(program.id() != 0);
+    assert!(program.attrib_location("position").is_ok());
+
+    let compute = ProgramBuilder::new().compute(COMPUTE_SHADER_SRC).build().unwrap();
+    assert!(compute.id() != 0);
+
+    let broken = ProgramBuilder::new().vertex(VERTEX_SHADER_SRC).fragment(b"#version 330\nvoid main() { broken }\n\0").build();
+    assert!(broken.is_err());
+}
+
+#[test]
+fn dispatch_a_compute_shader() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    let program = ProgramBuilder::new().compute(SQUARES_SHADER_SRC).build().unwrap();
+    assert_eq!(program.compute_work_group_size(), (1, 1, 1));
+
+    let mut values = [0u32; 4];
+    let size = mem::size_of_val(&values) as gl::types::GLsizeiptr;
+    let mut buffer = 0;
+    unsafe {
+        gl::GenBuffers(1, &mut buffer);
+        gl::BindBuffer(gl::SHADER_STORAGE_BUFFER, buffer);
+        gl::BufferData(gl::SHADER_STORAGE_BUFFER, size, values.as_ptr() as *const _, gl::DYNAMIC_READ);
+        gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, 0, buffer);
+    }
+
+    program.dispatch_compute(4, 1, 1);
+    program::memory_barrier(gl::BUFFER_UPDATE_BARRIER_BIT);
+
+    unsafe {
+        gl::GetBufferSubData(gl::SHADER_STORAGE_BUFFER, 0, size, values.as_mut_ptr() as *mut _);
+        gl::DeleteBuffers(1, &buffer);
+    }
+    assert_eq!(values, [0, 1, 4, 9]);
+}
+
+#[test]
+fn draw_with_a_geometry_shader() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    let program = ProgramBuilder::new().vertex(VERTEX_SHADER_SRC).geometry(GEOMETRY_SHADER_SRC).fragment(FRAGMENT_SHADER_SRC)
+        .build().unwrap();
+
+    // a single point is expanded into a quad covering the whole target
+    assert_eq!(center(&program, &[0.5, 0.5, 0.0], &|vbo| vbo.draw_as(gl::POINTS)), [255; 4]);
+}
+
+#[test]
+fn draw_with_tessellation_shaders() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    let program = ProgramBuilder::new().vertex(VERTEX_SHADER_SRC)
+        .tess_control(TESS_CONTROL_SHADER_SRC).tess_evaluation(TESS_EVALUATION_SHADER_SRC)
+        .fragment(FRAGMENT_SHADER_SRC)
+        .build().unwrap();
+
+    let triangle = [-1.0, -1.0, 0.0, 3.0, -1.0, 0.0, -1.0, 3.0, 0.0];
+    assert_eq!(center(&program, &triangle, &|vbo| vbo.draw_patches(3)), [255; 4]);
+    unsafe {
+        assert_eq!(gl::GetError(), gl::NO_ERROR);
+    }
+}
@@ -0,0 +1,152 @@
+extern crate dust;
+extern crate gl;
+
+mod common;
+
+use dust::opengl::AttribType;
+use dust::opengl::rich_text::{parse_color, RichText, SpanStyle};
+use dust::opengl::text::{TextLayout, TextSize};
+
+use common::{font, font_from, pixels};
+
+const LARGE : &str = "\
+info face=\"Large\" size=40
+common lineHeight=40 base=32 scaleW=64 scaleH=64 pages=1
+page id=0 file=\"large.png\"
+char id=65 x=0 y=0 width=16 height=24 xoffset=2 yoffset=8 xadvance=20 page=0
+char id=66 x=16 y=0 width=16 height=24 xoffset=2 yoffset=8 xadvance=20 page=0
+";
+
+#[test]
+fn parse_markup() {
+    let text = RichText::parse("plain [color=#ff0000]red [u]under[/u][/color][[x] [scale=2][s]big[/s][/scale][font=1]B[/font]").unwrap();
+    let spans = text.spans();
+
+    assert_eq!(text.plain_text(), "plain red under[x] bigB");
+    assert_eq!(spans.len(), 6);
+
+    assert_eq!(spans[0].text(), "plain ");
+    assert_eq!(*spans[0].style(), SpanStyle::new());
+
+    assert_eq!(spans[1].text(), "red ");
+    assert_eq!(spans[1].style().get_color(), [1.0, 0.0, 0.0, 1.0]);
+
+    assert_eq!(spans[2].text(), "under");
+    assert!(spans[2].style().is_underlined());
+    assert_eq!(spans[2].style().get_color(), [1.0, 0.0, 0.0, 1.0]);
+
+    assert_eq!(spans[3].text(), "[x] ");
+    assert_eq!(spans[4].style().get_scale(), 2.0);
+    assert!(spans[4].style().is_struck());
+    assert_eq!(spans[5].style().get_font(), 1);
+}
+
+#[test]
+fn parse_colors() {
+    assert_eq!(parse_color("#fff").unwrap(), [1.0, 1.0, 1.0, 1.0]);
+    assert_eq!(parse_color("#00ff0080").unwrap(), [0.0, 1.0, 0.0, 128.0 / 255.0]);
+    assert!(parse_color("ff0000").is_err());
+    assert!(parse_color("#ff00").is_err());
+    assert!(parse_color("#gg0000").is_err());
+}
+
+#[test]
+fn reject_invalid_markup() {
+    assert!(RichText::parse("[u]open").is_err());
+    assert!(RichText::parse("[u]crossed[s][/u][/s]").is_err());
+    assert!(RichText::parse("closed[/u]").is_err());
+    assert!(RichText::parse("[blink]x[/blink]").is_err());
+    assert!(RichText::parse("[scale=big]x[/scale]").is_err());
+    assert!(RichText::parse("[color=#ff0000").is_err());
+}
+
+#[test]
+fn vertices_carry_span_colors() {
+    let _context = match common::context() { Some(context) => context, None => return };
+    let font = font();
+
+    let text = RichText::parse("A[color=#00ff00]B[/color]").unwrap();
+    let meshes = pixels().rich_meshes(&[&font], &text).unwrap();
+    let mesh = &meshes[0][0];
+
+    assert!(mesh.attribs().by_type(AttribType::ColorRgba).is_ok());
+    assert_eq!(mesh.vertex_count(), 8);
+    assert_eq!(mesh.vertex(0)[5..9], [1.0, 1.0, 1.0, 1.0]);
+    assert_eq!(mesh.vertex(4)[5..9], [0.0, 1.0, 0.0, 1.0]);
+
+    // kerning still applies across spans of the same font
+    assert_eq!(mesh.vertex(4)[0], 10.0 - 2.0 + 1.0);
+}
+
+#[test]
+fn plain_rich_text_matches_plain_layout() {
+    let _context = match common::context() { Some(context) => context, None => return };
+    let font = font();
+    let layout = pixels().max_width(25.0);
+
+    let text = RichText::new().push("AB A\nBA", SpanStyle::new());
+    assert_eq!(layout.measure_rich(&[&font], &text).unwrap(), layout.measure(&font, "AB A\nBA"));
+}
+
+#[test]
+fn scaled_spans_share_the_baseline() {
+    let _context = match common::context() { Some(context) => context, None => return };
+    let font = font();
+
+    let text = RichText::parse("A[scale=2]A[/scale]").unwrap();
+    let metrics = pixels().measure_rich(&[&font], &text).unwrap();
+    assert_eq!(metrics.width(), 10.0 + 20.0);
+    assert_eq!(metrics.height(), 40.0);
+
+    let mesh = &pixels().rich_meshes(&[&font], &text).unwrap()[0][0];
+    // the large glyph has its top at 8 below the line, the small one drops by 16
+    assert_eq!((mesh.vertex(2)[1], mesh.vertex(1)[1]), (-16.0 - 4.0, -16.0 - 4.0 - 12.0));
+    assert_eq!((mesh.vertex(6)[1], mesh.vertex(5)[1]), (-8.0, -8.0 - 24.0));
+}
+
+#[test]
+fn underline_and_strike_span_the_run() {
+    let _context = match common::context() { Some(context) => context, None => return };
+    let font = font();
+
+    let text = RichText::parse("[u]AA[/u] [s]B[/s]").unwrap();
+    let mesh = &pixels().rich_meshes(&[&font], &text).unwrap()[0][0];
+
+    // three glyphs, one underline and one strikethrough
+    assert_eq!(mesh.vertex_count(), 20);
+
+    let underline = &mesh.vertices()[12..16];
+    assert_eq!((underline[0][0], underline[1][0]), (0.0, 20.0));
+    assert_eq!((underline[0][1], underline[2][1]), (-19.0, -17.0));
+    assert_eq!(underline[0][3], underline[1][3]);
+    assert_eq!(underline[0][3], 20.0 / 64.0);
+
+    let strike = &mesh.vertices()[16..20];
+    assert_eq!((strike[0][0], strike[1][0]), (25.0, 35.0));
+    assert_eq!((strike[0][1], strike[2][1]), (-11.0, -9.0));
+}
+
+#[test]
+fn spans_switch_fonts() {
+    let _context = match common::context() { Some(context) => context, None => return };
+    let small = font();
+    let large = font_from(LARGE);
+
+    let text = RichText::parse("A[font=1]B[/font]").unwrap();
+    let layout = TextLayout::new().size(TextSize::World(1.0));
+
+    let meshes = layout.rich_meshes(&[&small, &large], &text).unwrap();
+    assert_eq!(meshes.len(), 2);
+    assert_eq!(meshes[0][0].vertex_count(), 4);
+    assert_eq!(meshes[1][0].vertex_count(), 4);
+
+    // no kerning between fonts, and both glyphs sit on the baseline of the large font
+    assert_eq!(meshes[1][0].vertex(0)[0], 10.0 + 2.0);
+    assert_eq!(meshes[0][0].vertex(2)[1], -16.0 - 4.0);
+    assert_eq!(meshes[1][0].vertex(2)[1], -8.0);
+
+    let metrics = layout.measure_rich(&[&small, &large], &text).unwrap();
+    assert_eq!(metrics.lines()[0].height(), 40.0);
+
+    assert!(layout.rich_meshes(&[&small], &text).is_err());
+}
@@ -0,0 +1,63 @@
+extern crate dust;
+extern crate gl;
+
+mod common;
+
+use dust::opengl::{Texture, TextureFormat};
+use dust::opengl::sampler::{CompareFunc, Filter, MipmapFilter, Sampler, SamplerDesc, Wrap};
+
+#[test]
+fn defaults_match_the_previous_texture_parameters() {
+    let desc = SamplerDesc::default();
+    assert_eq!(desc.gl_min_filter(), gl::LINEAR);
+    assert_eq!(desc.gl_mag_filter(), gl::LINEAR);
+    assert!(!desc.uses_mipmaps());
+}
+
+#[test]
+fn min_filter_combines_with_mipmap_filter() {
+    let desc = SamplerDesc::new().filter(Filter::Nearest).mipmap_filter(MipmapFilter::Linear);
+    assert_eq!(desc.gl_min_filter(), gl::NEAREST_MIPMAP_LINEAR);
+    assert_eq!(desc.gl_mag_filter(), gl::NEAREST);
+    assert!(desc.uses_mipmaps());
+
+    let desc = SamplerDesc::new().min_filter(Filter::Linear).mipmap_filter(MipmapFilter::Nearest);
+    assert_eq!(desc.gl_min_filter(), gl::LINEAR_MIPMAP_NEAREST);
+}
+
+#[test]
+fn builder_sets_every_option() {
+    let desc = SamplerDesc::new()
+        .wrap(Wrap::ClampToBorder)
+        .wrap_s(Wrap::Repeat)
+        .anisotropy(0.5)
+        .border_color([1.0, 0.0, 0.0, 1.0])
+        .compare(CompareFunc::LessEqual);
+
+    assert_ne!(desc, SamplerDesc::new());
+    assert_eq!(desc, SamplerDesc::new()
+        .wrap_s(Wrap::Repeat)
+        .wrap_t(Wrap::ClampToBorder)
+        .wrap_r(Wrap::ClampToBorder)
+        .anisotropy(1.0)
+        .border_color([1.0, 0.0, 0.0, 1.0])
+        .compare(CompareFunc::LessEqual));
+
+    assert_eq!(Wrap::MirrorClampToEdge.gl_enum(), gl::MIRROR_CLAMP_TO_EDGE);
+    assert_eq!(CompareFunc::LessEqual.gl_enum(), gl::LEQUAL);
+}
+
+#[test]
+fn apply_without_errors_with_and_without_anisotropy() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    let texture = Texture::with_format(4, 4, TextureFormat::Rgba8, None).unwrap();
+    for anisotropy in &[1.0, 16.0] {
+        let desc = SamplerDesc::new().mipmap_filter(MipmapFilter::Linear).anisotropy(*anisotropy);
+        texture.set_sampler(&desc);
+        let sampler = Sampler::new(&desc);
+
+        assert_eq!(unsafe { gl::GetError() }, gl::NO_ERROR);
+        assert!(sampler.id() != 0);
+    }
+}
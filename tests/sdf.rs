@@ -0,0 +1,216 @@
+extern crate dust;
+extern crate gl;
+
+mod common;
+
+use std::env;
+use std::fs;
+
+use dust::image::{png, Image, PixelData, PixelFormat};
+use dust::linalg::Matrix4;
+use dust::opengl::TextureFormat;
+use dust::opengl::bmfont::{FieldType, FontDescription};
+use dust::opengl::framebuffer::{Framebuffer, FramebufferBuilder};
+use dust::opengl::sdf::{self, SdfFont, SdfShader, SdfStyle};
+use dust::opengl::text::{TextLayout, TextSize};
+
+/// A 32 by 32 image with a white 16 by 16 square in the middle.
+fn square() -> Image {
+    let data = (0..32 * 32).map(|i| {
+        let (x, y) = (i % 32, i / 32);
+        if (8..24).contains(&x) && (8..24).contains(&y) { 255 } else { 0 }
+    }).collect();
+    Image::new(32, 32, PixelFormat::Gray, PixelData::U8(data)).unwrap()
+}
+
+fn value(image: &Image, x: usize, y: usize) -> u8 {
+    match *image.data() {
+        PixelData::U8(ref data) => data[y * image.width() as usize + x],
+        _                       => panic!("expected 8 bit pixels"),
+    }
+}
+
+#[test]
+fn distance_field_of_a_square() {
+    let field = sdf::distance_field(&square(), 8).unwrap();
+    assert_eq!(field.format(), PixelFormat::Gray);
+
+    // half a pixel off the outline on either side
+    assert_eq!(value(&field, 8, 16), 143);
+    assert_eq!(value(&field, 7, 16), 112);
+
+    // half the range of eight pixels away from the outline the values are saturated
+    assert_eq!(value(&field, 12, 16), 255);
+    assert_eq!(value(&field, 16, 16), 255);
+    assert_eq!(value(&field, 4, 16), 16);
+    assert_eq!(value(&field, 3, 16), 0);
+
+    // diagonal distances are euclidean
+    let corner = 0.5 - ((2.0f32 * 2.0 + 2.0 * 2.0).sqrt() - 0.5) / 8.0;
+    assert_eq!(value(&field, 6, 6), (corner * 255.0).round() as u8);
+}
+
+#[test]
+fn distance_field_uses_alpha_for_coverage() {
+    let data = vec![255, 0, 255, 255, 0, 0, 0, 255];
+    let image = Image::new(2, 2, PixelFormat::GrayAlpha, PixelData::U8(data)).unwrap();
+    let field = sdf::distance_field(&image, 2).unwrap();
+
+    assert!(value(&field, 0, 0) < 128);
+    assert!(value(&field, 1, 0) > 128);
+    assert!(sdf::distance_field(&image, 0).is_err());
+}
+
+#[test]
+fn distance_field_block_in_descriptors() {
+    let text = "\
+common lineHeight=32 base=32 scaleW=32 scaleH=32 pages=1
+page id=0 file=\"msdf.png\"
+distanceField fieldType=msdf distanceRange=4
+";
+    let description = FontDescription::parse_text(text).unwrap();
+    let field = *description.distance_field().unwrap();
+    assert_eq!(field.field_type, FieldType::Msdf);
+    assert_eq!(field.distance_range, 4);
+    assert!(field.field_type.is_multi_channel());
+
+    assert_eq!(FontDescription::parse_xml(&description.to_xml()).unwrap(), description);
+    assert_eq!(FontDescription::parse_text(&description.to_text()).unwrap(), description);
+    assert!(FontDescription::parse_text("common lineHeight=1 base=1 scaleW=1 scaleH=1\ndistanceField fieldType=x distanceRange=4").is_err());
+}
+
+/// Draws the glyph 'A' of the font such that its 32 by 32 pixels cover a 32 by 32 target.
+fn render(font: &SdfFont, style: &SdfStyle) -> Image {
+    // the glyph covers x from 0 to 32 and y from -32 to 0, which is mapped onto the viewport
+    let mut mesh = TextLayout::new().size(TextSize::Pixels(32.0)).mesh(font.font(), "A");
+    mesh.apply_to_3(0, |x, y, z| (x / 16.0 - 1.0, y / 16.0 + 1.0, z));
+
+    let target = FramebufferBuilder::new(32, 32).color_texture(TextureFormat::Rgba8).build().unwrap();
+
+    let shader = SdfShader::new().unwrap();
+    shader.set_matrices(&Matrix4::new(), &Matrix4::new()).unwrap();
+    shader.set_page(font, 0, 0).unwrap();
+    shader.set_style(style).unwrap();
+
+    let vbo = mesh.to_array_buffer_vbo();
+    let program = shader.program();
+
+    unsafe {
+        let mut vertex_array = 0;
+        gl::GenVertexArrays(1, &mut vertex_array);
+        gl::BindVertexArray(vertex_array);
+        vbo.bind();
+        mesh.attribs().by_name("position").unwrap().attrib_array_pointer(program.attrib_location("position").unwrap());
+        mesh.attribs().by_name("uv").unwrap().attrib_array_pointer(program.attrib_location("uv").unwrap());
+
+        target.bind();
+        gl::ClearColor(0.0, 0.0, 0.0, 0.0);
+        gl::Clear(gl::COLOR_BUFFER_BIT);
+        vbo.draw();
+        gl::DeleteVertexArrays(1, &vertex_array);
+    }
+
+    let image = target.read_pixels(0, 0, 32, 32, PixelFormat::Rgba).unwrap();
+    Framebuffer::unbind();
+    image
+}
+
+/// The RGBA color of a pixel of the rendered image, whose rows go from top to bottom.
+fn pixel(image: &Image, x: usize, y: usize) -> Vec<u8> {
+    match *image.data() {
+        PixelData::U8(ref data) => data[(y * 32 + x) * 4..(y * 32 + x) * 4 + 4].to_vec(),
+        _                       => panic!("expected 8 bit pixels"),
+    }
+}
+
+/// The square as a single glyph distance field font with a range of 8 pixels.
+fn square_font() -> SdfFont {
+    let description = FontDescription::parse_text("\
+common lineHeight=32 base=32 scaleW=32 scaleH=32 pages=1
+page id=0 file=\"square.png\"
+char id=65 x=0 y=0 width=32 height=32 xoffset=0 yoffset=0 xadvance=32 page=0
+").unwrap();
+    SdfFont::from_bitmap(description, &[square()], 8).unwrap()
+}
+
+#[test]
+fn render_text_with_outline() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    let font = square_font();
+    assert_eq!(font.field().field_type, FieldType::Sdf);
+
+    let shader = SdfShader::new().unwrap();
+    assert!(shader.set_page(&font, 1, 0).is_err());
+
+    let image = render(&font, &SdfStyle::new().color([1.0, 0.0, 0.0, 1.0]).outline(3.0, [0.0, 0.0, 1.0, 1.0]));
+    assert_eq!(pixel(&image, 16, 16), vec![255, 0, 0, 255]);
+    assert_eq!(pixel(&image, 6, 16), vec![0, 0, 255, 255]);
+    assert_eq!(pixel(&image, 1, 1)[3], 0);
+}
+
+#[test]
+fn render_text_with_glow() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    let image = render(&square_font(), &SdfStyle::new().color([1.0, 0.0, 0.0, 1.0]).glow(4.0, [0.0, 1.0, 0.0, 1.0]));
+    assert_eq!(pixel(&image, 16, 16), vec![255, 0, 0, 255]);
+
+    // the glow fades out with the distance to the outline
+    let near = pixel(&image, 7, 16);
+    let far  = pixel(&image, 5, 16);
+    assert_eq!((near[0], near[1], near[2]), (0, 255, 0));
+    assert!(near[3] > far[3] && far[3] > 0, "{:?} {:?}", near, far);
+    assert_eq!(pixel(&image, 1, 16)[3], 0);
+}
+
+#[test]
+fn render_text_with_shadow() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    let style = SdfStyle::new().color([1.0, 0.0, 0.0, 1.0]).shadow([4.0, 4.0], 0.0, [0.0, 0.0, 1.0, 1.0]);
+    let image = render(&square_font(), &style);
+    assert_eq!(pixel(&image, 16, 16), vec![255, 0, 0, 255]);
+
+    // the square from 8 to 24 casts a shadow from 12 to 28 to the right and from 4 to 20 in
+    // the rows of the image, which go down
+    assert_eq!(pixel(&image, 26, 12), vec![0, 0, 255, 255]);
+    assert_eq!(pixel(&image, 16, 6), vec![0, 0, 255, 255]);
+    assert_eq!(pixel(&image, 26, 22)[3], 0);
+    assert_eq!(pixel(&image, 6, 12)[3], 0);
+}
+
+#[test]
+fn load_and_render_multi_channel_fields() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    // the left half is inside by the median of its channels though its red channel is not,
+    // the right half is outside though its red channel is inside
+    let data = (0..32 * 32).flat_map(|i| if i % 32 < 16 { vec![0, 255, 255] } else { vec![255, 0, 0] }).collect();
+    let page = Image::new(32, 32, PixelFormat::Rgb, PixelData::U8(data)).unwrap();
+
+    let directory = env::temp_dir().join("dust_sdf_msdf");
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+    png::save(&page, directory.join("msdf.png")).unwrap();
+
+    let common = "\
+common lineHeight=32 base=32 scaleW=32 scaleH=32 pages=1
+page id=0 file=\"msdf.png\"
+char id=65 x=0 y=0 width=32 height=32 xoffset=0 yoffset=0 xadvance=32 page=0
+";
+    fs::write(directory.join("bitmap.fnt"), common).unwrap();
+    fs::write(directory.join("msdf.fnt"), format!("{}distanceField fieldType=msdf distanceRange=4\n", common)).unwrap();
+
+    let error = SdfFont::load(&directory.join("bitmap.fnt").to_string_lossy()).err().unwrap();
+    assert!(error.contains("is not a distance field font"), "{}", error);
+
+    let font = SdfFont::load(&directory.join("msdf.fnt").to_string_lossy()).unwrap();
+    assert_eq!(font.field().field_type, FieldType::Msdf);
+    assert_eq!(font.field().distance_range, 4);
+
+    let image = render(&font, &SdfStyle::new().color([1.0, 0.0, 0.0, 1.0]));
+    assert_eq!(pixel(&image, 4, 16), vec![255, 0, 0, 255]);
+    assert_eq!(pixel(&image, 28, 16)[3], 0);
+    fs::remove_dir_all(&directory).unwrap();
+}
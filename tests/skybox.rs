@@ -0,0 +1,73 @@
+extern crate dust;
+extern crate gl;
+
+mod common;
+
+use std::f32::consts::PI;
+
+use dust::image::{Image, PixelData, PixelFormat};
+use dust::linalg::{Matrix4, Vector3};
+use dust::opengl::TextureFormat;
+use dust::opengl::cubemap::CubeMap;
+use dust::opengl::framebuffer::{Framebuffer, FramebufferBuilder};
+use dust::opengl::sampler::{Filter, SamplerDesc};
+use dust::opengl::skybox::Skybox;
+
+/// The colors of the faces +X, -X, +Y, -Y, +Z and -Z.
+const COLORS : [[u8; 3]; 6] = [[255, 0, 0], [0, 255, 0], [0, 0, 255], [255, 255, 0], [0, 255, 255], [255, 0, 255]];
+
+/// The color in the center of the view when looking along the rotated -Z axis.
+fn center(skybox: &Skybox, cubemap: &CubeMap, angle: f32, axis: Vector3) -> [u8; 3] {
+    let target = FramebufferBuilder::new(16, 16).color_texture(TextureFormat::Rgba8).build().unwrap();
+    target.bind();
+    unsafe {
+        gl::Viewport(0, 0, 16, 16);
+        gl::ClearColor(0.0, 0.0, 0.0, 0.0);
+        gl::Clear(gl::COLOR_BUFFER_BIT);
+    }
+
+    let mut projection = Matrix4::new();
+    projection.frustum(-1.0, 1.0, -1.0, 1.0, 1.0, 10.0);
+    let mut view = Matrix4::new();
+    if angle != 0.0 {
+        view.rotation(angle, &axis);
+    }
+
+    skybox.draw(cubemap, &projection, &view, 0).unwrap();
+
+    let image = target.read_pixels(0, 0, 16, 16, PixelFormat::Rgba).unwrap();
+    Framebuffer::unbind();
+    match *image.data() {
+        PixelData::U8(ref data) => {
+            let at = (8 * 16 + 8) * 4;
+            [data[at], data[at + 1], data[at + 2]]
+        },
+        _ => panic!("expected 8 bit pixels"),
+    }
+}
+
+#[test]
+fn draw_the_face_in_view() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    let faces : Vec<Image> = COLORS.iter()
+        .map(|color| Image::new(1, 1, PixelFormat::Rgb, PixelData::U8(color.to_vec())).unwrap())
+        .collect();
+    let cubemap = CubeMap::from_images(&faces).unwrap();
+    cubemap.set_sampler(&SamplerDesc::new().filter(Filter::Nearest));
+    let skybox = Skybox::new().unwrap();
+
+    // without a rotation the camera looks at -Z. The view matrix turns the world, so turning it
+    // counterclockwise about an axis turns the camera clockwise about it.
+    assert_eq!(center(&skybox, &cubemap, 0.0, Vector3::new(0.0, 1.0, 0.0)), COLORS[5]);
+    assert_eq!(center(&skybox, &cubemap, PI, Vector3::new(0.0, 1.0, 0.0)), COLORS[4]);
+    assert_eq!(center(&skybox, &cubemap, PI * 0.5, Vector3::new(0.0, 1.0, 0.0)), COLORS[0]);
+    assert_eq!(center(&skybox, &cubemap, -PI * 0.5, Vector3::new(0.0, 1.0, 0.0)), COLORS[1]);
+    assert_eq!(center(&skybox, &cubemap, PI * 0.5, Vector3::new(1.0, 0.0, 0.0)), COLORS[3]);
+    assert_eq!(center(&skybox, &cubemap, -PI * 0.5, Vector3::new(1.0, 0.0, 0.0)), COLORS[2]);
+
+    // seamless filtering is only enabled while the skybox is drawn
+    unsafe {
+        assert_eq!(gl::IsEnabled(gl::TEXTURE_CUBE_MAP_SEAMLESS), gl::FALSE);
+    }
+}
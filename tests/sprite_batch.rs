@@ -0,0 +1,139 @@
+extern crate dust;
+extern crate gl;
+
+mod common;
+
+use std::f32::consts::PI;
+
+use dust::image::{Image, PixelData, PixelFormat};
+use dust::opengl::{Sprite, Texture, TextureFormat};
+use dust::opengl::framebuffer::{Framebuffer, FramebufferBuilder};
+use dust::opengl::sampler::{Filter, SamplerDesc};
+use dust::opengl::sprite_batch::{SpriteBatch, SpriteDraw};
+
+/// A texture of the given texels, given with the bottom row first.
+fn texture(width: u32, height: u32, texels: &[[u8; 4]]) -> Texture {
+    let pixels : Vec<u8> = texels.iter().flat_map(|texel| texel.iter().cloned()).collect();
+    let texture = Texture::with_format(width, height, TextureFormat::Rgba8, Some(&pixels)).unwrap();
+    texture.set_sampler(&SamplerDesc::new().filter(Filter::Nearest));
+    texture
+}
+
+fn target(depth: bool) -> Framebuffer {
+    let builder = FramebufferBuilder::new(32, 32).color_texture(TextureFormat::Rgba8);
+    let builder = if depth { builder.depth_renderbuffer(TextureFormat::Depth24) } else { builder };
+    let target = builder.build().unwrap();
+
+    target.bind();
+    unsafe {
+        gl::ClearColor(0.0, 0.0, 0.0, 0.0);
+        gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+    }
+    target
+}
+
+/// The pixel at x and y from the top left of the target.
+fn pixel(image: &Image, x: usize, y: usize) -> Vec<u8> {
+    match *image.data() {
+        PixelData::U8(ref data) => data[(y * 32 + x) * 4..(y * 32 + x) * 4 + 4].to_vec(),
+        _                       => panic!("expected 8 bit pixels"),
+    }
+}
+
+fn read(target: &Framebuffer) -> Image {
+    target.read_pixels(0, 0, 32, 32, PixelFormat::Rgba).unwrap()
+}
+
+#[test]
+fn one_draw_call_per_texture() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    let red   = texture(1, 1, &[[255, 0, 0, 255]]);
+    let green = texture(1, 1, &[[0, 255, 0, 255]]);
+    let target = target(false);
+
+    let mut batch = SpriteBatch::new(32, 32).unwrap();
+    let square = SpriteDraw::new(Sprite::new(0, 0, 1, 1)).scale(8.0, 8.0);
+
+    batch.draw(&red, &square.position(0.0, 0.0));
+    batch.draw(&green, &square.position(8.0, 0.0));
+    batch.draw(&red, &square.position(0.0, 8.0).color([0.0, 0.0, 1.0, 1.0]));
+    assert_eq!(batch.len(), 3);
+
+    assert_eq!(batch.flush().unwrap(), 2);
+    assert!(batch.is_empty());
+    assert_eq!(batch.flush().unwrap(), 0);
+
+    let image = read(&target);
+    assert_eq!(pixel(&image, 4, 4), vec![255, 0, 0, 255]);
+    assert_eq!(pixel(&image, 12, 4), vec![0, 255, 0, 255]);
+    assert_eq!(pixel(&image, 4, 12), vec![0, 0, 0, 255]);
+    assert_eq!(pixel(&image, 20, 4), vec![0, 0, 0, 0]);
+    Framebuffer::unbind();
+}
+
+#[test]
+fn sprites_select_their_area_from_the_top() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    // red at the top, blue at the bottom right and green at the bottom left
+    let atlas = texture(2, 2, &[[0, 255, 0, 255], [0, 0, 255, 255], [255, 0, 0, 255], [255, 0, 0, 255]]);
+    let target = target(false);
+
+    let mut batch = SpriteBatch::new(32, 32).unwrap();
+    batch.draw(&atlas, &SpriteDraw::new(Sprite::new(1, 1, 1, 1)).position(16.0, 16.0).scale(16.0, 16.0));
+    batch.draw(&atlas, &SpriteDraw::new(Sprite::new(0, 0, 2, 1)).scale(16.0, 8.0));
+    batch.flush().unwrap();
+
+    let image = read(&target);
+    assert_eq!(pixel(&image, 24, 24), vec![0, 0, 255, 255]);
+    assert_eq!(pixel(&image, 8, 4), vec![255, 0, 0, 255]);
+    Framebuffer::unbind();
+}
+
+#[test]
+fn rotate_around_the_origin() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    let white = texture(1, 1, &[[255, 255, 255, 255]]);
+    let target = target(false);
+
+    // a horizontal bar turned upright around its center
+    let mut batch = SpriteBatch::new(32, 32).unwrap();
+    batch.draw(&white, &SpriteDraw::new(Sprite::new(0, 0, 1, 1))
+        .scale(24.0, 4.0).origin(0.5, 0.5).position(16.0, 16.0).rotation(PI * 0.5));
+    batch.flush().unwrap();
+
+    let image = read(&target);
+    assert_eq!(pixel(&image, 16, 6), vec![255, 255, 255, 255]);
+    assert_eq!(pixel(&image, 15, 26), vec![255, 255, 255, 255]);
+    assert_eq!(pixel(&image, 6, 16), vec![0, 0, 0, 0]);
+    assert_eq!(pixel(&image, 26, 15), vec![0, 0, 0, 0]);
+    Framebuffer::unbind();
+}
+
+#[test]
+fn depth_decides_over_draw_order() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    let white = texture(1, 1, &[[255, 255, 255, 255]]);
+    let target = target(true);
+
+    let mut batch = SpriteBatch::new(32, 32).unwrap();
+    let square = SpriteDraw::new(Sprite::new(0, 0, 1, 1)).scale(16.0, 16.0);
+    batch.draw(&white, &square.color([1.0, 0.0, 0.0, 1.0]).depth(0.2));
+    batch.draw(&white, &square.color([0.0, 1.0, 0.0, 1.0]).depth(0.8).position(8.0, 8.0));
+
+    unsafe {
+        gl::Enable(gl::DEPTH_TEST);
+    }
+    batch.flush().unwrap();
+    unsafe {
+        gl::Disable(gl::DEPTH_TEST);
+    }
+
+    let image = read(&target);
+    assert_eq!(pixel(&image, 12, 12), vec![255, 0, 0, 255]);
+    assert_eq!(pixel(&image, 20, 20), vec![0, 255, 0, 255]);
+    Framebuffer::unbind();
+}
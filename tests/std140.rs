@@ -0,0 +1,91 @@
+extern crate dust;
+
+use dust::linalg::{Matrix3, Matrix4, Vector3, Vector4};
+use dust::opengl::std140::Std140Writer;
+
+fn float_at(bytes: &[u8], offset: usize) -> f32 {
+    let mut raw = [0u8; 4];
+    raw.copy_from_slice(&bytes[offset..offset + 4]);
+    f32::from_bits(u32::from_le_bytes(raw))
+}
+
+#[test]
+fn camera_block_offsets() {
+    let mut writer = Std140Writer::new();
+
+    assert_eq!(writer.push_mat4(&Matrix4::new()), 0);
+    assert_eq!(writer.push_mat4(&Matrix4::new()), 64);
+    assert_eq!(writer.push_vec3(&Vector3::new(1.0, 2.0, 3.0)), 128);
+    assert_eq!(writer.push_f32(4.0), 140);
+
+    let bytes = writer.bytes();
+    assert_eq!(bytes.len(), 144);
+    assert_eq!(float_at(&bytes, 0), 1.0);
+    assert_eq!(float_at(&bytes, 4), 0.0);
+    assert_eq!(float_at(&bytes, 20), 1.0);
+    assert_eq!(float_at(&bytes, 128), 1.0);
+    assert_eq!(float_at(&bytes, 136), 3.0);
+    assert_eq!(float_at(&bytes, 140), 4.0);
+}
+
+#[test]
+fn scalars_and_vectors_are_aligned() {
+    let mut writer = Std140Writer::new();
+
+    assert_eq!(writer.push_f32(1.0), 0);
+    assert_eq!(writer.push_vec2([2.0, 3.0]), 8);
+    assert_eq!(writer.push_vec3(&Vector3::new(4.0, 5.0, 6.0)), 16);
+    assert_eq!(writer.push_i32(7), 28);
+    assert_eq!(writer.push_vec4(&Vector4::new(8.0, 9.0, 10.0, 11.0)), 32);
+    assert_eq!(writer.push_bool(true), 48);
+    assert_eq!(writer.push_vec3(&Vector3::zero()), 64);
+    assert_eq!(writer.push_vec3(&Vector3::zero()), 80);
+
+    assert_eq!(writer.bytes().len(), 96);
+}
+
+#[test]
+fn arrays_have_a_stride_of_sixteen_bytes() {
+    let mut writer = Std140Writer::new();
+
+    assert_eq!(writer.push_f32(1.0), 0);
+    assert_eq!(writer.push_f32_array(&[2.0, 3.0, 4.0]), 16);
+    assert_eq!(writer.push_f32(5.0), 64);
+
+    let bytes = writer.bytes();
+    assert_eq!(float_at(&bytes, 16), 2.0);
+    assert_eq!(float_at(&bytes, 32), 3.0);
+    assert_eq!(float_at(&bytes, 48), 4.0);
+    assert_eq!(float_at(&bytes, 64), 5.0);
+
+    let mut writer = Std140Writer::new();
+    assert_eq!(writer.push_vec3_array(&[Vector3::zero(), Vector3::zero()]), 0);
+    assert_eq!(writer.push_f32(1.0), 32);
+    assert_eq!(writer.push_mat4_array(&[Matrix4::new(), Matrix4::new()]), 48);
+    assert_eq!(writer.offset(), 176);
+}
+
+#[test]
+fn mat3_columns_are_padded() {
+    let mut writer = Std140Writer::new();
+
+    assert_eq!(writer.push_mat3(&Matrix3::new()), 0);
+    assert_eq!(writer.push_f32(2.0), 48);
+
+    let bytes = writer.bytes();
+    assert_eq!(float_at(&bytes, 0), 1.0);
+    assert_eq!(float_at(&bytes, 12), 0.0);
+    assert_eq!(float_at(&bytes, 20), 1.0);
+    assert_eq!(float_at(&bytes, 40), 1.0);
+}
+
+#[test]
+fn structs_are_aligned_and_padded() {
+    let mut writer = Std140Writer::new();
+
+    assert_eq!(writer.push_f32(1.0), 0);
+    assert_eq!(writer.begin_struct(), 16);
+    assert_eq!(writer.push_vec2([2.0, 3.0]), 16);
+    writer.end_struct();
+    assert_eq!(writer.push_f32(4.0), 32);
+}
@@ -0,0 +1,118 @@
+extern crate dust;
+extern crate gl;
+
+mod common;
+
+use dust::opengl::Font;
+use dust::opengl::text::{Align, TextLayout, TextSize};
+
+use common::{font, pixels};
+
+fn line_ranges(layout: &TextLayout, font: &Font, text: &str) -> Vec<(usize, usize)> {
+    layout.measure(font, text).lines().iter().map(|line| (line.start(), line.end())).collect()
+}
+
+#[test]
+fn measure_single_line() {
+    let _context = match common::context() { Some(context) => context, None => return };
+    let font = font();
+
+    let metrics = pixels().measure(&font, "AB A");
+    assert_eq!(metrics.width(), 10.0 - 2.0 + 10.0 + 5.0 + 10.0);
+    assert_eq!(metrics.height(), 20.0);
+    assert_eq!(metrics.glyphs().len(), 4);
+
+    let space = metrics.glyphs()[2];
+    assert_eq!((space.index(), space.character(), space.line()), (2, ' ', 0));
+    assert_eq!((space.x(), space.y(), space.width(), space.height()), (18.0, 0.0, 5.0, 20.0));
+}
+
+#[test]
+fn size_scales_the_layout() {
+    let _context = match common::context() { Some(context) => context, None => return };
+    let font = font();
+
+    assert_eq!(TextLayout::new().size(TextSize::Pixels(10.0)).scale(&font), 0.5);
+    assert_eq!(TextLayout::new().scale(&font), 0.005);
+    assert_eq!(TextLayout::new().size(TextSize::World(0.1)).measure(&font, "AA").width(), 2.0);
+}
+
+#[test]
+fn wrap_at_spaces_and_within_long_words() {
+    let _context = match common::context() { Some(context) => context, None => return };
+    let font = font();
+    let layout = pixels().max_width(25.0);
+
+    assert_eq!(line_ranges(&layout, &font, "AA AA  AA"), vec![(0, 2), (3, 5), (7, 9)]);
+    assert_eq!(line_ranges(&layout, &font, "AAAAA"), vec![(0, 2), (2, 4), (4, 5)]);
+    assert_eq!(line_ranges(&layout, &font, "A\n\nA"), vec![(0, 1), (2, 2), (3, 4)]);
+
+    let metrics = layout.measure(&font, "AA AA  AA");
+    assert_eq!(metrics.height(), 60.0);
+    assert_eq!(metrics.width(), 20.0);
+    assert_eq!(metrics.lines()[2].y(), -40.0);
+    // the spaces at the wrap have no rectangles
+    assert_eq!(metrics.glyphs().len(), 6);
+}
+
+#[test]
+fn alignment_within_max_width() {
+    let _context = match common::context() { Some(context) => context, None => return };
+    let font = font();
+
+    let first_x = |align: Align| pixels().max_width(30.0).align(align).measure(&font, "AA").glyphs()[0].x();
+    assert_eq!(first_x(Align::Left), 0.0);
+    assert_eq!(first_x(Align::Center), 5.0);
+    assert_eq!(first_x(Align::Right), 10.0);
+
+    // without a max width lines are aligned to the widest one
+    let metrics = pixels().align(Align::Right).measure(&font, "AAA\nA");
+    assert_eq!(metrics.lines()[1].x(), 20.0);
+}
+
+#[test]
+fn justify_stretches_spaces_except_on_the_last_line() {
+    let _context = match common::context() { Some(context) => context, None => return };
+    let font = font();
+
+    let metrics = pixels().max_width(30.0).align(Align::Justify).measure(&font, "A A AAA");
+    assert_eq!(metrics.lines().len(), 2);
+
+    let glyphs = metrics.glyphs();
+    assert_eq!(glyphs[1].width(), 10.0);
+    assert_eq!(glyphs[2].x(), 20.0);
+    assert_eq!(metrics.lines()[0].width(), 30.0);
+    assert_eq!(metrics.lines()[1].width(), 30.0);
+
+    let last = pixels().max_width(40.0).align(Align::Justify).measure(&font, "A A");
+    assert_eq!(last.glyphs()[2].x(), 15.0);
+}
+
+#[test]
+fn line_spacing() {
+    let _context = match common::context() { Some(context) => context, None => return };
+    let font = font();
+
+    let metrics = pixels().line_spacing(1.5).measure(&font, "A\nA");
+    assert_eq!(metrics.lines()[1].y(), -30.0);
+    assert_eq!(metrics.height(), 50.0);
+}
+
+#[test]
+fn mesh_has_quads_for_visible_glyphs() {
+    let _context = match common::context() { Some(context) => context, None => return };
+    let font = font();
+
+    let mesh = pixels().mesh(&font, "AB A\nB");
+    assert_eq!(mesh.vertex_count(), 16);
+
+    // left bottom corner of the first glyph, offset from the top of the line
+    let vertex = mesh.vertex(0);
+    assert_eq!((vertex[0], vertex[1]), (1.0, -16.0));
+    assert_eq!((vertex[3], vertex[4]), (0.0, 52.0 / 64.0));
+
+    let vertex = mesh.vertex(12);
+    assert_eq!((vertex[0], vertex[1]), (1.0, -36.0));
+
+    assert_eq!(pixels().page_meshes(&font, "AB")[0].vertex_count(), 8);
+}
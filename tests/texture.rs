@@ -0,0 +1,126 @@
+extern crate dust;
+extern crate gl;
+
+mod common;
+
+use dust::image::{Image, PixelData, PixelFormat};
+use dust::opengl::{Texture, TextureFormat};
+use dust::opengl::cubemap::CubeMap;
+use dust::opengl::streaming::StreamingTexture;
+use dust::opengl::texture_3d::Texture3D;
+use dust::opengl::texture_array::TextureArray;
+
+/// Reads the given mipmap level of a texture with four 8 bit components per texel.
+fn read_level(id: gl::types::GLuint, level: i32, texel_count: usize) -> Vec<u8> {
+    let mut texels = vec![0u8; texel_count * 4];
+    unsafe {
+        gl::GetTextureImage(id, level, gl::RGBA, gl::UNSIGNED_BYTE, texels.len() as i32, texels.as_mut_ptr() as *mut _);
+    }
+    texels
+}
+
+/// The width of the given mipmap level, which is zero as long as the level is undefined.
+fn level_width(id: gl::types::GLuint, level: i32) -> i32 {
+    let mut width = 0;
+    unsafe {
+        gl::GetTextureLevelParameteriv(id, level, gl::TEXTURE_WIDTH, &mut width);
+    }
+    width
+}
+
+#[test]
+fn update_region_of_rgb_texture_with_odd_width() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    let texture = Texture::with_format(3, 2, TextureFormat::Rgb8, Some(&[0; 18])).unwrap();
+    texture.update_region(1, 1, 2, 1, &[1, 2, 3, 4, 5, 6]).unwrap();
+
+    assert_eq!(texture.read_texels(), vec![
+        0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 1, 2, 3, 4, 5, 6,
+    ]);
+}
+
+#[test]
+fn update_region_of_float_texture() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    let texture = Texture::with_format(2, 2, TextureFormat::R16f, None).unwrap();
+    let texels : Vec<u8> = [0.5f32, 1.0, -2.0, 0.25].iter().flat_map(|f| f.to_ne_bytes()).collect();
+    texture.update_region(0, 0, 2, 2, &texels).unwrap();
+
+    assert_eq!(texture.read_texels(), texels);
+}
+
+#[test]
+fn update_region_is_validated() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    let texture = Texture::with_format(4, 4, TextureFormat::Rgba8, None).unwrap();
+    assert!(texture.update_region(3, 0, 2, 1, &[0; 8]).is_err());
+    assert!(texture.update_region(0, 0, 2, 1, &[0; 7]).is_err());
+    assert!(texture.update_region(0, 3, 4, 1, &[0; 16]).is_ok());
+}
+
+#[test]
+fn streaming_texture_shows_the_latest_frame() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    let mut stream = StreamingTexture::new(5, 3, TextureFormat::R8).unwrap();
+
+    // more frames than buffers, such that both buffers are reused
+    for frame in 0..5u8 {
+        let pixels : Vec<u8> = (0..15).map(|i| i * 10 + frame).collect();
+        stream.update(&pixels).unwrap();
+        assert_eq!(stream.texture().read_texels(), pixels);
+    }
+
+    assert!(stream.update(&[0; 14]).is_err());
+}
+
+#[test]
+fn upload_mipmaps_of_the_mip_chain() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    let data : Vec<u8> = (0..32).map(|i| i * 8).collect();
+    let image = Image::new(4, 2, PixelFormat::Rgba, PixelData::U8(data)).unwrap();
+    let texture = Texture::from_image(&image);
+
+    let chain = image.mip_chain();
+    assert_eq!(chain.len(), 2);
+    texture.upload_mipmaps(&chain).unwrap();
+    assert_eq!(*chain[0].data(), PixelData::U8(read_level(texture.id(), 1, 2)));
+    assert_eq!(*chain[1].data(), PixelData::U8(read_level(texture.id(), 2, 1)));
+
+    // levels of the wrong size or format are rejected before anything is uploaded
+    let error = texture.upload_mipmaps(&[Image::empty(1, 1, PixelFormat::Rgba)]).unwrap_err();
+    assert!(error.contains("has to be 2 x 1"), "{}", error);
+    assert!(texture.upload_mipmaps(&[Image::empty(2, 1, PixelFormat::Rgb)]).is_err());
+    assert!(texture.upload_mipmaps(&[chain[0].clone(), chain[0].clone()]).is_err());
+    assert_eq!(*chain[0].data(), PixelData::U8(read_level(texture.id(), 1, 2)));
+}
+
+#[test]
+fn generate_mipmaps_of_all_texture_kinds() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    let image = Image::new(2, 2, PixelFormat::Rgba, PixelData::U8([10, 20, 30, 255].repeat(4))).unwrap();
+
+    let texture = Texture::from_image(&image);
+    assert_eq!(level_width(texture.id(), 1), 0);
+    texture.generate_mipmaps();
+    assert_eq!(read_level(texture.id(), 1, 1), vec![10, 20, 30, 255]);
+
+    let array = TextureArray::from_images(&[image.clone(), image.clone()]).unwrap();
+    array.generate_mipmaps();
+    assert_eq!(level_width(array.id(), 1), 1);
+
+    let volume = Texture3D::from_images(&[image.clone(), image.clone()]).unwrap();
+    volume.generate_mipmaps();
+    assert_eq!(level_width(volume.id(), 1), 1);
+
+    let faces = vec![image; 6];
+    let cubemap = CubeMap::from_images(&faces).unwrap();
+    cubemap.generate_mipmaps();
+    assert_eq!(level_width(cubemap.id(), 1), 1);
+}
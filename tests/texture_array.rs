@@ -0,0 +1,75 @@
+extern crate dust;
+extern crate gl;
+
+mod common;
+
+use dust::image::{Image, PixelData, PixelFormat};
+use dust::opengl::TextureFormat;
+use dust::opengl::texture_3d::Texture3D;
+use dust::opengl::texture_array::TextureArray;
+
+// the validation runs without an OpenGL context, it fails before anything is uploaded
+
+#[test]
+fn layers_have_to_match() {
+    assert!(TextureArray::from_images(&[]).is_err());
+    assert!(TextureArray::from_images(&[Image::empty(2, 2, PixelFormat::Rgb), Image::empty(2, 1, PixelFormat::Rgb)]).is_err());
+    assert!(Texture3D::from_images(&[Image::empty(2, 2, PixelFormat::Rgb), Image::empty(2, 2, PixelFormat::Rgba)]).is_err());
+}
+
+#[test]
+fn raw_volume_size_is_checked() {
+    assert!(Texture3D::from_raw(2, 2, 2, TextureFormat::R8, &[0; 7]).is_err());
+    assert!(Texture3D::from_raw(4, 4, 4, TextureFormat::Rgba16f, &[0; 4 * 4 * 4 * 4]).is_err());
+    assert!(Texture3D::new(4, 0, 4, TextureFormat::R8).is_err());
+}
+
+/// Reads all texels of all layers or slices of the texture, tightly packed.
+fn read_texels(id: gl::types::GLuint, format: TextureFormat, texel_count: usize) -> Vec<u8> {
+    let mut texels = vec![0u8; texel_count * format.bytes_per_pixel()];
+    unsafe {
+        gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+        gl::GetTextureImage(id, 0, format.pixel_format(), format.pixel_type(), texels.len() as i32, texels.as_mut_ptr() as *mut _);
+        gl::PixelStorei(gl::PACK_ALIGNMENT, 4);
+    }
+    texels
+}
+
+#[test]
+fn upload_and_read_back_array_layers() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    let layers : Vec<Image> = (0..3u8)
+        .map(|layer| Image::new(3, 1, PixelFormat::Rgb, PixelData::U8((0..9).map(|i| layer * 10 + i).collect())).unwrap())
+        .collect();
+    let array = TextureArray::from_images(&layers).unwrap();
+    assert_eq!((array.width(), array.height(), array.layers(), array.format()), (3, 1, 3, TextureFormat::Rgb8));
+
+    let mut expected : Vec<u8> = (0..3u8).flat_map(|layer| (0..9).map(move |i| layer * 10 + i)).collect();
+    assert_eq!(read_texels(array.id(), array.format(), 9), expected);
+
+    // replacing the middle layer leaves the others untouched
+    array.upload_layer(1, &[99; 9]).unwrap();
+    expected[9..18].copy_from_slice(&[99; 9]);
+    assert_eq!(read_texels(array.id(), array.format(), 9), expected);
+
+    assert!(array.upload_layer(3, &[0; 9]).is_err());
+    assert!(array.upload_layer(0, &[0; 8]).is_err());
+}
+
+#[test]
+fn upload_and_read_back_volume_slices() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    let data : Vec<u8> = (0..2 * 2 * 3).collect();
+    let volume = Texture3D::from_raw(2, 2, 3, TextureFormat::R8, &data).unwrap();
+    assert_eq!((volume.width(), volume.height(), volume.depth()), (2, 2, 3));
+    assert_eq!(read_texels(volume.id(), volume.format(), 12), data);
+
+    let slice = Image::new(2, 2, PixelFormat::Gray, PixelData::U8(vec![7, 8, 9, 10])).unwrap();
+    volume.upload_slice_image(2, &slice).unwrap();
+    assert_eq!(read_texels(volume.id(), volume.format(), 12), vec![0, 1, 2, 3, 4, 5, 6, 7, 7, 8, 9, 10]);
+
+    assert!(volume.upload_slice(3, &[0; 4]).is_err());
+    assert!(volume.upload_slice_image(0, &Image::empty(2, 2, PixelFormat::Rgb)).is_err());
+}
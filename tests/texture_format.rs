@@ -0,0 +1,47 @@
+extern crate dust;
+extern crate gl;
+
+use dust::image::PixelFormat;
+use dust::opengl::TextureFormat;
+
+#[test]
+fn bytes_per_pixel() {
+    assert_eq!(TextureFormat::R8.bytes_per_pixel(), 1);
+    assert_eq!(TextureFormat::Rgb8.bytes_per_pixel(), 3);
+    assert_eq!(TextureFormat::Srgb8Alpha8.bytes_per_pixel(), 4);
+    assert_eq!(TextureFormat::R16f.bytes_per_pixel(), 4);
+    assert_eq!(TextureFormat::Rgba16f.bytes_per_pixel(), 16);
+    assert_eq!(TextureFormat::Depth16.bytes_per_pixel(), 2);
+    assert_eq!(TextureFormat::Depth24Stencil8.bytes_per_pixel(), 4);
+    assert_eq!(TextureFormat::Depth32fStencil8.bytes_per_pixel(), 8);
+}
+
+#[test]
+fn row_alignment_handles_odd_widths() {
+    assert_eq!(TextureFormat::Rgba8.row_alignment(3), 4);
+    assert_eq!(TextureFormat::Rgba8.row_alignment(2), 8);
+    assert_eq!(TextureFormat::Rgb8.row_alignment(3), 1);
+    assert_eq!(TextureFormat::Rgb8.row_alignment(2), 2);
+    assert_eq!(TextureFormat::Rg8.row_alignment(5), 2);
+    assert_eq!(TextureFormat::R8.row_alignment(7), 1);
+}
+
+#[test]
+fn upload_formats() {
+    assert_eq!(TextureFormat::Srgb8Alpha8.internal_format(), gl::SRGB8_ALPHA8);
+    assert_eq!(TextureFormat::Srgb8Alpha8.pixel_format(), gl::RGBA);
+    assert_eq!(TextureFormat::Rgba16f.pixel_type(), gl::FLOAT);
+    assert_eq!(TextureFormat::Depth24Stencil8.pixel_format(), gl::DEPTH_STENCIL);
+
+    assert!(TextureFormat::Depth32f.is_depth());
+    assert!(!TextureFormat::Depth32f.has_stencil());
+    assert!(!TextureFormat::Depth32f.is_float());
+    assert!(TextureFormat::R16f.is_float());
+}
+
+#[test]
+fn from_pixel_format() {
+    assert_eq!(TextureFormat::from_pixel_format(PixelFormat::Gray), TextureFormat::R8);
+    assert_eq!(TextureFormat::from_pixel_format(PixelFormat::GrayAlpha), TextureFormat::Rg8);
+    assert_eq!(TextureFormat::from_pixel_format(PixelFormat::Rgba), TextureFormat::Rgba8);
+}
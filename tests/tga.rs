@@ -0,0 +1,132 @@
+extern crate dust;
+
+use dust::image::{self, tga, PixelData, PixelFormat};
+
+fn header(image_type: u8, width: u16, height: u16, bits: u8, descriptor: u8) -> Vec<u8> {
+    let mut bytes = vec![0, 0, image_type, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    bytes.extend_from_slice(&width.to_le_bytes());
+    bytes.extend_from_slice(&height.to_le_bytes());
+    bytes.push(bits);
+    bytes.push(descriptor);
+    bytes
+}
+
+#[test]
+fn uncompressed_true_color_bottom_up() {
+    let mut tga = header(2, 2, 2, 24, 0);
+    // BGR, bottom row first
+    tga.extend_from_slice(&[3, 2, 1, 6, 5, 4, 9, 8, 7, 12, 11, 10]);
+    let image = tga::decode(&tga).unwrap();
+
+    assert_eq!(image.format(), PixelFormat::Rgb);
+    assert_eq!(*image.data(), PixelData::U8(vec![7, 8, 9, 10, 11, 12, 1, 2, 3, 4, 5, 6]));
+}
+
+#[test]
+fn uncompressed_with_alpha_top_down() {
+    let mut tga = header(2, 2, 1, 32, 0x28);
+    tga.extend_from_slice(&[3, 2, 1, 255, 6, 5, 4, 0]);
+    let image = tga::decode(&tga).unwrap();
+
+    assert_eq!(image.format(), PixelFormat::Rgba);
+    assert_eq!(*image.data(), PixelData::U8(vec![1, 2, 3, 255, 4, 5, 6, 0]));
+}
+
+#[test]
+fn right_to_left() {
+    let mut tga = header(3, 3, 1, 8, 0x30);
+    tga.extend_from_slice(&[1, 2, 3]);
+    let image = tga::decode(&tga).unwrap();
+
+    assert_eq!(image.format(), PixelFormat::Gray);
+    assert_eq!(*image.data(), PixelData::U8(vec![3, 2, 1]));
+}
+
+#[test]
+fn sixteen_bit_colors() {
+    let mut tga = header(2, 2, 1, 16, 0x21);
+    // pure red without the alpha bit, pure blue with it
+    tga.extend_from_slice(&0x7c00u16.to_le_bytes());
+    tga.extend_from_slice(&0x801fu16.to_le_bytes());
+    let image = tga::decode(&tga).unwrap();
+
+    assert_eq!(*image.data(), PixelData::U8(vec![255, 0, 0, 0, 0, 0, 255, 255]));
+}
+
+#[test]
+fn run_length_encoded() {
+    let mut tga = header(10, 5, 1, 24, 0x20);
+    // a run of three red pixels followed by two raw pixels
+    tga.extend_from_slice(&[0x82, 0, 0, 255, 0x01, 255, 0, 0, 0, 255, 0]);
+    let image = tga::decode(&tga).unwrap();
+
+    assert_eq!(*image.data(), PixelData::U8(vec![
+        255, 0, 0, 255, 0, 0, 255, 0, 0, 0, 0, 255, 0, 255, 0,
+    ]));
+
+    // the data ends in the middle of the last packet
+    tga.truncate(tga.len() - 3);
+    assert!(tga::decode(&tga).is_err());
+}
+
+#[test]
+fn run_length_encoded_color_map() {
+    let mut tga = header(9, 4, 1, 8, 0x20);
+    tga[1] = 1;
+    // the color map starts at index 1 and has two 24 bit entries
+    tga[3..8].copy_from_slice(&[1, 0, 2, 0, 24]);
+    tga.extend_from_slice(&[0, 0, 255, 255, 0, 0]);
+    tga.extend_from_slice(&[0x81, 1, 0x01, 2, 1]);
+    let image = tga::decode(&tga).unwrap();
+
+    assert_eq!(*image.data(), PixelData::U8(vec![255, 0, 0, 255, 0, 0, 0, 0, 255, 255, 0, 0]));
+}
+
+#[test]
+fn image_id_is_skipped_and_decode_falls_back_to_tga() {
+    let mut tga = header(3, 1, 1, 8, 0);
+    tga[0] = 3;
+    tga.extend_from_slice(b"id!");
+    tga.push(42);
+
+    assert_eq!(*image::decode(&tga).unwrap().data(), PixelData::U8(vec![42]));
+}
+
+#[test]
+fn truncated_files_are_rejected() {
+    let mut tga = header(2, 2, 2, 24, 0);
+    tga.extend_from_slice(&[0; 12]);
+    assert!(tga::decode(&tga).is_ok());
+    for length in 0..tga.len() {
+        assert!(tga::decode(&tga[..length]).is_err());
+    }
+
+    // an image ID reaching behind the end of the file
+    let mut tga = header(2, 1, 1, 24, 0);
+    tga[0] = 200;
+    assert!(tga::decode(&tga).is_err());
+    assert!(tga::decode(&header(10, 1, 1, 24, 0)[..18]).is_err());
+
+    // the largest possible size with next to no data
+    let mut tga = header(10, 65535, 65535, 32, 0);
+    tga.extend_from_slice(&[0xff, 1, 2, 3, 4]);
+    assert!(tga::decode(&tga).is_err());
+    assert!(tga::decode(&header(2, 65535, 65535, 32, 0)).is_err());
+}
+
+#[test]
+fn unsupported_color_maps_are_rejected() {
+    // a color map with entries of 0 bits would take up no space at all
+    let mut tga = header(1, 1, 1, 8, 0);
+    tga[1] = 1;
+    tga[3..8].copy_from_slice(&[0, 0, 1, 0, 0]);
+    let error = tga::decode(&tga).unwrap_err();
+    assert!(error.contains("entry size of 0 bits"), "{}", error);
+
+    let mut tga = header(1, 1, 1, 24, 0);
+    tga[1] = 1;
+    tga[3..8].copy_from_slice(&[0, 0, 1, 0, 24]);
+    tga.extend_from_slice(&[0, 0, 255, 0, 0, 0]);
+    let error = tga::decode(&tga).unwrap_err();
+    assert!(error.contains("index size of 24 bits"), "{}", error);
+}
@@ -0,0 +1,107 @@
+extern crate dust;
+extern crate gl;
+
+mod common;
+
+use dust::opengl::text::{TextLayout, TextSize};
+use dust::opengl::truetype::TrueTypeFont;
+
+fn load(size: f32) -> TrueTypeFont {
+    TrueTypeFont::load("assets/DejaVuSans.ttf", size).unwrap()
+}
+
+/// The coverage of the atlas within the glyph of the character.
+fn coverage(font: &TrueTypeFont, character: char) -> Vec<u8> {
+    let glyph = *font.font().glyph(character).unwrap();
+    let texels = font.atlas().read_texels();
+    let width = font.atlas().width();
+    let height = font.atlas().height();
+
+    let mut pixels = Vec::new();
+    for row in 0..glyph.height {
+        let start = ((height - glyph.y - 1 - row) * width + glyph.x) as usize;
+        pixels.extend_from_slice(&texels[start..start + glyph.width as usize]);
+    }
+    pixels
+}
+
+#[test]
+fn glyphs_are_rasterized_on_demand() {
+    let _context = match common::context() { Some(context) => context, None => return };
+    let mut font = load(32.0);
+
+    let description = font.font().description();
+    assert_eq!(description.info().face, "DejaVuSans");
+    assert_eq!(description.glyphs().len(), 1);
+    assert!(description.common().line_height >= 32);
+    assert!(description.common().base > 0 && description.common().base < 32);
+
+    font.prepare("Hello, world").unwrap();
+    assert_eq!(font.font().description().glyphs().len(), 1 + 9);
+
+    let h = *font.font().glyph('H').unwrap();
+    assert!(h.width > 10 && h.height > 15 && h.xadvance > h.width as i32);
+    // capitals rest on the baseline
+    assert_eq!(h.yoffset + h.height as i32, font.font().description().common().base as i32);
+
+    let space = *font.font().glyph(' ').unwrap();
+    assert_eq!(space.width, 0);
+    assert!(space.xadvance > 0);
+
+    assert!(coverage(&font, 'H').contains(&255));
+    assert!(coverage(&font, 'o').iter().any(|&value| value > 0 && value < 255));
+}
+
+#[test]
+fn missing_characters_fall_back_to_the_notdef_glyph() {
+    let _context = match common::context() { Some(context) => context, None => return };
+    let mut font = load(16.0);
+
+    let mesh = font.mesh("a\u{10fffd}").unwrap();
+    assert_eq!(font.font().description().glyph(0x10fffd), None);
+    assert_eq!(font.font().glyph('\u{10fffd}').unwrap().id, 0);
+    assert_eq!(mesh.vertex_count(), 8);
+}
+
+#[test]
+fn atlas_grows_and_keeps_its_glyphs() {
+    let _context = match common::context() { Some(context) => context, None => return };
+    let mut font = load(12.0);
+
+    font.prepare("A").unwrap();
+    let before = coverage(&font, 'A');
+    let height = font.atlas().height();
+
+    let text : String = (0x21u32..0x17f).filter_map(std::char::from_u32).collect();
+    font.prepare(&text).unwrap();
+
+    assert!(font.atlas().height() > height);
+    assert_eq!(font.atlas().height(), font.font().description().common().scale_h);
+    assert_eq!(coverage(&font, 'A'), before);
+}
+
+#[test]
+fn layout_with_rasterized_glyphs() {
+    let _context = match common::context() { Some(context) => context, None => return };
+    let mut font = load(24.0);
+
+    let text = "Größe 24 — ÆØÅ";
+    font.prepare(text).unwrap();
+
+    let layout = TextLayout::new().size(TextSize::Pixels(48.0));
+    let metrics = layout.measure(font.font(), text);
+    assert!(metrics.width() > 48.0 * 5.0);
+    assert_eq!(metrics.glyphs().len(), text.chars().count());
+}
+
+#[test]
+fn invalid_sizes_are_rejected() {
+    for &size in &[f32::NAN, f32::INFINITY, f32::NEG_INFINITY, 0.5] {
+        let error = TrueTypeFont::load("assets/DejaVuSans.ttf", size).err().unwrap();
+        assert!(error.contains("invalid font size"), "{}", error);
+    }
+
+    // huge sizes do not fit into the atlas, but the initial atlas size does not overflow
+    let _context = match common::context() { Some(context) => context, None => return };
+    assert!(TrueTypeFont::load("assets/DejaVuSans.ttf", 3.0e8).is_err());
+}
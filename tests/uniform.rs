@@ -0,0 +1,142 @@
+extern crate dust;
+extern crate gl;
+
+mod common;
+
+use std::slice;
+
+use dust::linalg::{Matrix3, Matrix4, Vector3};
+use dust::opengl::program::{Program, ProgramBuilder};
+use dust::opengl::uniform::{self, TextureUnit, Uniform};
+
+const VERTEX_SHADER_SRC : &[u8] = b"
+#version 330
+uniform float scale;
+uniform vec3 offset;
+uniform mat4 transform;
+uniform float weights[3];
+uniform int mode;
+
+in vec3 position;
+
+void main() {
+    float weight = weights[0] + weights[1] + weights[2] + float(mode);
+    gl_Position = transform * vec4(position * scale * weight + offset, 1.0);
+}
+\0";
+
+const FRAGMENT_SHADER_SRC : &[u8] = b"
+#version 330
+out vec4 color;
+
+void main() {
+    color = vec4(1.0);
+}
+\0";
+
+/// The values of a float uniform of the given number of components.
+fn read_floats(program: &Program, name: &str, components: usize) -> Vec<f32> {
+    let mut values = vec![0.0; components];
+    unsafe {
+        gl::GetUniformfv(program.id(), program.uniforms()[name].location(), values.as_mut_ptr());
+    }
+    values
+}
+
+#[test]
+fn values_match_their_types() {
+    assert!(1.0f32.matches(gl::FLOAT));
+    assert!(!1.0f32.matches(gl::FLOAT_VEC2));
+    assert!(1i32.matches(gl::INT) && 1i32.matches(gl::BOOL));
+    assert!(!1i32.matches(gl::FLOAT));
+    assert!(1i32.matches(gl::SAMPLER_2D) && [1i32, 2][..].matches(gl::SAMPLER_2D));
+    assert!(true.matches(gl::BOOL) && !true.matches(gl::INT));
+    assert!(TextureUnit(2).matches(gl::SAMPLER_2D) && TextureUnit(2).matches(gl::SAMPLER_CUBE));
+    assert!(!TextureUnit(2).matches(gl::INT));
+    assert!([1.0f32, 2.0, 3.0].matches(gl::FLOAT_VEC3));
+    assert!(Vector3::new(1.0, 2.0, 3.0).matches(gl::FLOAT_VEC3));
+    assert!(Matrix4::new().matches(gl::FLOAT_MAT4) && !Matrix4::new().matches(gl::FLOAT_MAT3));
+    assert!(Matrix3::new().matches(gl::FLOAT_MAT3) && !Matrix3::new().matches(gl::FLOAT_MAT4));
+}
+
+#[test]
+fn slices_count_their_elements() {
+    let weights = [1.0f32, 2.0, 3.0];
+    assert!(weights[..].matches(gl::FLOAT));
+    assert_eq!(weights[..].count(), 3);
+    assert_eq!(weights.count(), 1);
+
+    let vectors = [Vector3::zero(), Vector3::zero()];
+    assert!(vectors[..].matches(gl::FLOAT_VEC3));
+    assert_eq!(vectors[..].count(), 2);
+
+    let units = [0i32, 1];
+    assert!(units[..].matches(gl::SAMPLER_2D));
+    assert_eq!(units[..].count(), 2);
+    assert_eq!(1.0f32.count(), 1);
+}
+
+#[test]
+fn type_names() {
+    assert_eq!(uniform::type_name(gl::FLOAT), "float");
+    assert_eq!(uniform::type_name(gl::FLOAT_VEC3), "vec3");
+    assert_eq!(uniform::type_name(gl::INT_VEC2), "ivec2");
+    assert_eq!(uniform::type_name(gl::FLOAT_MAT4), "mat4");
+    assert_eq!(uniform::type_name(gl::SAMPLER_2D_ARRAY), "sampler");
+    assert_eq!(uniform::type_name(gl::DOUBLE), "unknown");
+    assert!(uniform::is_sampler(gl::UNSIGNED_INT_SAMPLER_3D));
+    assert!(!uniform::is_sampler(gl::FLOAT));
+}
+
+#[test]
+fn set_and_read_back() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    let program = ProgramBuilder::new().vertex(VERTEX_SHADER_SRC).fragment(FRAGMENT_SHADER_SRC).build().unwrap();
+    program.use_program();
+
+    let mut transform = Matrix4::new();
+    transform.frustum(-1.0, 1.0, -2.0, 2.0, 0.5, 10.0);
+
+    program.set("scale", &2.5f32).unwrap();
+    program.set("offset", &Vector3::new(1.0, -2.0, 3.0)).unwrap();
+    program.set("transform", &transform).unwrap();
+    program.set("weights", &[0.25f32, 0.5, 0.75][..]).unwrap();
+    program.set("mode", &7i32).unwrap();
+
+    assert_eq!(read_floats(&program, "scale", 1), vec![2.5]);
+    assert_eq!(read_floats(&program, "offset", 3), vec![1.0, -2.0, 3.0]);
+    let expected = unsafe { slice::from_raw_parts(transform.as_ptr(), 16) };
+    assert_eq!(read_floats(&program, "transform", 16), expected.to_vec());
+
+    let location = program.uniforms()["weights"].location();
+    let mut weights = [0.0f32; 3];
+    let mut mode = 0;
+    unsafe {
+        for (index, weight) in weights.iter_mut().enumerate() {
+            gl::GetUniformfv(program.id(), location + index as gl::types::GLint, weight);
+        }
+        gl::GetUniformiv(program.id(), program.uniforms()["mode"].location(), &mut mode);
+    }
+    assert_eq!(weights, [0.25, 0.5, 0.75]);
+    assert_eq!(mode, 7);
+}
+
+#[test]
+fn reject_mismatching_values() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    let program = ProgramBuilder::new().vertex(VERTEX_SHADER_SRC).fragment(FRAGMENT_SHADER_SRC).build().unwrap();
+    program.use_program();
+
+    let error = program.set("transform", &Matrix3::new()).unwrap_err();
+    assert!(error.contains("mat4"), "{}", error);
+    assert!(program.set("scale", &1i32).is_err());
+    assert!(program.set("weights", &[1.0f32, 2.0, 3.0, 4.0][..]).is_err());
+    assert!(program.set("missing", &1.0f32).is_err());
+
+    // a failed set leaves the uniform untouched
+    program.set("scale", &4.0f32).unwrap();
+    assert!(program.set("scale", &[1.0f32, 2.0][..]).is_err());
+    assert_eq!(read_floats(&program, "scale", 1), vec![4.0]);
+}
@@ -0,0 +1,117 @@
+extern crate dust;
+extern crate gl;
+
+mod common;
+
+use std::panic;
+
+use dust::linalg::{Matrix4, Vector3};
+use dust::opengl::program::{Program, ProgramBuilder};
+use dust::opengl::std140::Std140Writer;
+use dust::opengl::uniform_buffer::UniformBuffer;
+
+const VERTEX_SHADER_SRC : &[u8] = b"
+#version 330
+layout(std140) uniform Camera {
+    mat4 transform;
+    vec3 offset;
+    float scale;
+    float weights[3];
+};
+
+in vec3 position;
+
+void main() {
+    float weight = weights[0] + weights[1] + weights[2];
+    gl_Position = transform * vec4(position * scale * weight + offset, 1.0);
+}
+\0";
+
+const FRAGMENT_SHADER_SRC : &[u8] = b"
+#version 330
+out vec4 color;
+
+void main() {
+    color = vec4(1.0);
+}
+\0";
+
+/// The offset of a member of a uniform block as reported by the driver.
+fn member_offset(program: &Program, name: &str) -> usize {
+    let name = format!("{}\0", name);
+    let names = [name.as_ptr() as *const gl::types::GLchar];
+    let mut index = 0;
+    let mut offset = -1;
+    unsafe {
+        gl::GetUniformIndices(program.id(), 1, names.as_ptr(), &mut index);
+        assert_ne!(index, gl::INVALID_INDEX, "{}", name);
+        gl::GetActiveUniformsiv(program.id(), 1, &index, gl::UNIFORM_OFFSET, &mut offset);
+    }
+    offset as usize
+}
+
+/// The content of the buffer on the graphics card.
+fn read_bytes(buffer: &UniformBuffer) -> Vec<u8> {
+    let mut bytes = vec![0u8; buffer.size()];
+    unsafe {
+        gl::BindBuffer(gl::UNIFORM_BUFFER, buffer.id());
+        gl::GetBufferSubData(gl::UNIFORM_BUFFER, 0, bytes.len() as gl::types::GLsizeiptr, bytes.as_mut_ptr() as *mut _);
+        gl::BindBuffer(gl::UNIFORM_BUFFER, 0);
+    }
+    bytes
+}
+
+#[test]
+fn bind_a_block_to_a_buffer() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    let program = ProgramBuilder::new().vertex(VERTEX_SHADER_SRC).fragment(FRAGMENT_SHADER_SRC).build().unwrap();
+
+    // the offsets packed by the writer have to agree with the ones of the driver
+    let mut writer = Std140Writer::new();
+    assert_eq!(writer.push_mat4(&Matrix4::new()), member_offset(&program, "transform"));
+    assert_eq!(writer.push_vec3(&Vector3::new(1.0, 2.0, 3.0)), member_offset(&program, "offset"));
+    assert_eq!(writer.push_f32(2.0), member_offset(&program, "scale"));
+    assert_eq!(writer.push_f32_array(&[0.25, 0.5, 0.75]), member_offset(&program, "weights[0]"));
+
+    let buffer = UniformBuffer::from_writer(&writer);
+    assert_eq!(buffer.size(), writer.bytes().len());
+    assert_eq!(read_bytes(&buffer), writer.bytes());
+
+    buffer.bind_base(3);
+    program.bind_uniform_block("Camera", 3).unwrap();
+    assert!(program.bind_uniform_block("Missing", 3).is_err());
+
+    let mut binding = -1;
+    let mut bound = 0;
+    unsafe {
+        let index = gl::GetUniformBlockIndex(program.id(), b"Camera\0".as_ptr() as *const _);
+        gl::GetActiveUniformBlockiv(program.id(), index, gl::UNIFORM_BLOCK_BINDING, &mut binding);
+        gl::GetIntegeri_v(gl::UNIFORM_BUFFER_BINDING, 3, &mut bound);
+    }
+    assert_eq!(binding, 3);
+    assert_eq!(bound as gl::types::GLuint, buffer.id());
+}
+
+#[test]
+fn update_and_grow() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    let mut buffer = UniformBuffer::new(8);
+    buffer.update(&[1, 2, 3, 4]);
+    assert_eq!(buffer.size(), 8);
+    assert_eq!(&read_bytes(&buffer)[..4], &[1, 2, 3, 4]);
+
+    // data larger than the buffer reallocates it
+    buffer.update(&[5; 16]);
+    assert_eq!(buffer.size(), 16);
+    assert_eq!(read_bytes(&buffer), vec![5; 16]);
+
+    buffer.update_range(12, &[6, 7, 8, 9]);
+    assert_eq!(&read_bytes(&buffer)[8..], &[5, 5, 5, 5, 6, 7, 8, 9]);
+
+    // ranges past the end are rejected instead of being passed on to the driver
+    let result = panic::catch_unwind(|| buffer.update_range(13, &[0; 4]));
+    assert!(result.is_err());
+    assert_eq!(&read_bytes(&buffer)[12..], &[6, 7, 8, 9]);
+}
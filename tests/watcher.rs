@@ -0,0 +1,90 @@
+extern crate dust;
+extern crate gl;
+
+mod common;
+
+use std::env;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use dust::opengl::watcher::ProgramWatcher;
+
+const VERTEX_SHADER_SRC : &str = "#version 330
+in vec3 position;
+void main() {
+    gl_Position = vec4(position, 1.0);
+}
+";
+
+const FRAGMENT_SHADER_SRC : &str = "#version 330
+out vec4 color;
+void main() {
+    color = vec4(1.0);
+}
+";
+
+/// Writes the file and moves its modification time forward, such that a change is seen even
+/// within the resolution of the file system's time stamps.
+fn write(path: &Path, code: &str, age: u64) {
+    fs::write(path, code).unwrap();
+    let time = SystemTime::now() + Duration::from_secs(age);
+    File::options().write(true).open(path).unwrap().set_modified(time).unwrap();
+}
+
+fn scratch(name: &str) -> PathBuf {
+    let directory = env::temp_dir().join(format!("dust_watcher_{}", name));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+#[test]
+fn reload_on_changes_and_keep_the_last_good_program() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    let directory = scratch("reload");
+    let (vertex, fragment) = (directory.join("shader.vert"), directory.join("shader.frag"));
+    write(&vertex, VERTEX_SHADER_SRC, 0);
+    write(&fragment, FRAGMENT_SHADER_SRC, 0);
+
+    let mut watcher = ProgramWatcher::new(&vertex, &fragment).unwrap();
+    let first = watcher.program().id();
+    assert_eq!(watcher.poll(), Ok(false));
+
+    // a broken fragment shader is reported once and the program is kept
+    write(&fragment, "#version 330\nvoid main() { broken }\n", 10);
+    assert!(watcher.poll().is_err());
+    assert!(watcher.last_error().is_some());
+    assert_eq!(watcher.program().id(), first);
+    assert_eq!(watcher.poll(), Ok(false));
+
+    write(&fragment, FRAGMENT_SHADER_SRC, 20);
+    assert_eq!(watcher.poll(), Ok(true));
+    assert!(watcher.last_error().is_none());
+    assert_ne!(watcher.program().id(), first);
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn watch_new_includes_that_are_broken() {
+    let _context = match common::context() { Some(context) => context, None => return };
+
+    let directory = scratch("include");
+    let (vertex, fragment, include) = (directory.join("shader.vert"), directory.join("shader.frag"), directory.join("color.glsl"));
+    write(&vertex, VERTEX_SHADER_SRC, 0);
+    write(&fragment, FRAGMENT_SHADER_SRC, 0);
+
+    let mut watcher = ProgramWatcher::new(&vertex, &fragment).unwrap();
+
+    // the newly included file fails to compile, yet fixing it alone triggers a reload
+    write(&include, "vec4 white() { return vec4(1.0) }\n", 10);
+    write(&fragment, "#version 330\n#include \"color.glsl\"\nout vec4 color;\nvoid main() {\n    color = white();\n}\n", 10);
+    let error = watcher.poll().unwrap_err();
+    assert!(error.contains("color.glsl"), "{}", error);
+    assert_eq!(watcher.poll(), Ok(false));
+
+    write(&include, "vec4 white() { return vec4(1.0); }\n", 20);
+    assert_eq!(watcher.poll(), Ok(true));
+    fs::remove_dir_all(&directory).unwrap();
+}